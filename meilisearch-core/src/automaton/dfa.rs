@@ -47,6 +47,14 @@ pub fn build_dfa(query: &str) -> DFA {
     build_dfa_with_setting(query, PrefixSetting::NoPrefix)
 }
 
+/// builds a dfa more tolerant than the search one, used to find the words close to a misspelled one.
+pub fn build_suggestion_dfa(query: &str) -> DFA {
+    match query.chars().count() {
+        0..=4 => LEVDIST1.get_or_init(|| LevBuilder::new(1, true)).build_dfa(query),
+        _ => LEVDIST2.get_or_init(|| LevBuilder::new(2, true)).build_dfa(query),
+    }
+}
+
 pub fn build_exact_dfa(query: &str) -> DFA {
     let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
     builder.build_dfa(query)
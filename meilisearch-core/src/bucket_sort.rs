@@ -1,10 +1,11 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::mem;
 use std::ops::Deref;
 use std::ops::Range;
 use std::rc::Rc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
 use std::fmt;
 
@@ -13,10 +14,13 @@ use log::debug;
 use sdset::{Set, SetBuf, exponential_search, SetOperation, Counter, duo::OpBuilder};
 use slice_group_by::{GroupBy, GroupByMut};
 
+use meilisearch_schema::FieldId;
 use meilisearch_types::DocIndex;
 
-use crate::criterion::{Criteria, Context, ContextMut};
+use crate::criterion::{compare_distances, compare_ranked_values, document_distance, prepare_query_distances, Criteria, Context, ContextMut, GeoDistances};
 use crate::distinct_map::{BufferedDistinctMap, DistinctMap};
+use crate::facets::{facet_stats, FacetStats};
+use crate::filters::GEO_ATTRIBUTE;
 use crate::raw_document::RawDocument;
 use crate::{database::MainT, reordered_attrs::ReorderedAttrs};
 use crate::{store, Document, DocumentId, MResult, Index, RankedMap, MainReader, Error};
@@ -31,6 +35,21 @@ pub struct SortResult {
     pub exhaustive_nb_hit: bool,
     pub facets: Option<HashMap<String, HashMap<String, usize>>>,
     pub exhaustive_facets_count: Option<bool>,
+    /// the number of documents for each combination of the values of two facets
+    pub facets_pivot: Option<HashMap<String, HashMap<String, usize>>>,
+    /// the numeric stats of the requested facets over the filtered candidates
+    pub facet_stats: Option<HashMap<String, FacetStats>>,
+    /// the query with its unknown words replaced by the closest known words
+    pub suggested_query: Option<String>,
+}
+
+/// returns an error if the search has been cancelled, it is checked
+/// between the groups of documents ranked by the criteria.
+pub(crate) fn check_cancelled(cancelled: Option<&AtomicBool>) -> MResult<()> {
+    match cancelled {
+        Some(cancelled) if cancelled.load(Ordering::Relaxed) => Err(Error::Cancelled),
+        _ => Ok(()),
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -39,10 +58,18 @@ pub fn bucket_sort<'c, FI>(
     query: &str,
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
-    facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    mut facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    facets_pivot_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    max_facet_values: Option<usize>,
+    facet_stats_fields: Option<Vec<(FieldId, String)>>,
+    ranking_score_threshold: Option<f64>,
     filter: Option<FI>,
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
+    context: QTContext,
+    sticky_facets: bool,
+    exhaustive_nb_hits: bool,
+    cancelled: Option<&AtomicBool>,
     index: &Index,
 ) -> MResult<SortResult>
 where
@@ -59,28 +86,26 @@ where
             range,
             facets_docids,
             facet_count_docids,
+            facets_pivot_docids,
+            max_facet_values,
+            facet_stats_fields,
+            ranking_score_threshold,
             filter,
             distinct,
             distinct_size,
+            None,
             criteria,
             searchable_attrs,
+            context,
+            sticky_facets,
+            exhaustive_nb_hits,
+            cancelled,
             index,
         );
     }
 
     let mut result = SortResult::default();
 
-    let words_set = index.main.words_fst(reader)?;
-    let stop_words = index.main.stop_words_fst(reader)?;
-
-    let context = QTContext {
-        words_set,
-        stop_words,
-        synonyms: index.synonyms,
-        postings_lists: index.postings_lists,
-        prefix_postings_lists: index.prefix_postings_lists_cache,
-    };
-
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
@@ -100,6 +125,13 @@ where
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    if sticky_facets {
+        if let Some(f) = facet_count_docids.take() {
+            result.exhaustive_facets_count = Some(true);
+            result.facets = Some(sticky_facet_count(f, &docids, max_facet_values, filter.as_ref(), &mut HashMap::new()));
+        }
+    }
+
     if let Some(facets_docids) = facets_docids {
         let intersection = sdset::duo::OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
@@ -110,7 +142,24 @@ where
     if let Some(f) = facet_count_docids {
         // hardcoded value, until approximation optimization
         result.exhaustive_facets_count = Some(true);
-        result.facets = Some(facet_count(f, &docids));
+        result.facets = Some(facet_count(f, &docids, max_facet_values));
+    }
+
+    if let Some(p) = facets_pivot_docids {
+        result.facets_pivot = Some(pivot_count(p, &docids));
+    }
+
+    if let Some(fields) = facet_stats_fields {
+        let ranked_map = index.main.ranked_map(reader)?.unwrap_or_default();
+        result.facet_stats = Some(facet_stats(reader, index, &fields, &docids, &ranked_map)?);
+    }
+
+    // no document is requested, there is no need to sort the candidates,
+    // unless the hits are the candidates above the ranking score threshold
+    if range.is_empty() && ranking_score_threshold.is_none() {
+        result.nb_hits = docids.len();
+        result.exhaustive_nb_hit = true;
+        return Ok(result);
     }
 
     let before = Instant::now();
@@ -131,6 +180,21 @@ where
         before_raw_documents_building.elapsed(),
     );
 
+    // only the candidates above the threshold are hits, they are counted before being paginated
+    let nb_hits = match ranking_score_threshold {
+        Some(threshold) => {
+            retain_above_threshold(&mut raw_documents, threshold, &mapping, &arena);
+            raw_documents.len()
+        },
+        None => docids.len(),
+    };
+
+    if range.is_empty() {
+        result.nb_hits = nb_hits;
+        result.exhaustive_nb_hit = true;
+        return Ok(result);
+    }
+
     let before_criterion_loop = Instant::now();
     let proximity_count = AtomicUsize::new(0);
 
@@ -141,6 +205,7 @@ where
         let mut documents_seen = 0;
 
         for mut group in tmp_groups {
+            check_cancelled(cancelled)?;
             let before_criterion_preparation = Instant::now();
 
             let ctx = ContextMut {
@@ -182,13 +247,22 @@ where
 
     let schema = index.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
     let iter = raw_documents.into_iter().skip(range.start).take(range.len());
-    let iter = iter.map(|rd| Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
-    let documents = iter.collect();
+    let iter = iter.map(|mut rd| {
+        let score = relevance_score(&mut rd, &mapping, &arena);
+        let typo_corrected = is_typo_corrected(&rd);
+        let query_typos = query_words_typos(&rd, &mapping);
+        let document = Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+        Document { score, typo_corrected, query_typos, ..document }
+    });
+    let mut documents: Vec<_> = iter.collect();
+    follow_ranking_order(&mut documents);
 
     debug!("bucket sort took {:.02?}", before_bucket_sort.elapsed());
 
     result.documents = documents;
-    result.nb_hits = docids.len();
+    result.nb_hits = nb_hits;
+    // without filter nor distinct rule every candidate is a hit, the count is exact
+    result.exhaustive_nb_hit = true;
 
     Ok(result)
 }
@@ -199,12 +273,21 @@ pub fn bucket_sort_with_distinct<'c, FI, FD>(
     query: &str,
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
-    facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    mut facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    facets_pivot_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
+    max_facet_values: Option<usize>,
+    facet_stats_fields: Option<Vec<(FieldId, String)>>,
+    ranking_score_threshold: Option<f64>,
     filter: Option<FI>,
     distinct: FD,
     distinct_size: usize,
+    distinct_scan_limit: Option<usize>,
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
+    context: QTContext,
+    sticky_facets: bool,
+    exhaustive_nb_hits: bool,
+    cancelled: Option<&AtomicBool>,
     index: &Index,
 ) -> MResult<SortResult>
 where
@@ -213,17 +296,6 @@ where
 {
     let mut result = SortResult::default();
 
-    let words_set = index.main.words_fst(reader)?;
-    let stop_words = index.main.stop_words_fst(reader)?;
-
-    let context = QTContext {
-        words_set,
-        stop_words,
-        synonyms: index.synonyms,
-        postings_lists: index.postings_lists,
-        prefix_postings_lists: index.prefix_postings_lists_cache,
-    };
-
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
@@ -243,6 +315,16 @@ where
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    // the filter results are kept to be reused during the bucket sort
+    let mut filter_map = HashMap::new();
+
+    if sticky_facets {
+        if let Some(f) = facet_count_docids.take() {
+            result.exhaustive_facets_count = Some(true);
+            result.facets = Some(sticky_facet_count(f, &docids, max_facet_values, filter.as_ref(), &mut filter_map));
+        }
+    }
+
     if let Some(facets_docids) = facets_docids {
         let intersection = OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
@@ -253,7 +335,33 @@ where
     if let Some(f) = facet_count_docids {
         // hardcoded value, until approximation optimization
         result.exhaustive_facets_count = Some(true);
-        result.facets = Some(facet_count(f, &docids));
+        result.facets = Some(filtered_facet_count(f, &docids, max_facet_values, filter.as_ref(), &mut filter_map));
+    }
+
+    if let Some(p) = facets_pivot_docids {
+        let candidates = filtered_candidates(&docids, filter.as_ref(), &mut filter_map);
+        result.facets_pivot = Some(pivot_count(p, &candidates));
+    }
+
+    if let Some(fields) = facet_stats_fields {
+        let ranked_map = index.main.ranked_map(reader)?.unwrap_or_default();
+        let candidates = filtered_candidates(&docids, filter.as_ref(), &mut filter_map);
+        result.facet_stats = Some(facet_stats(reader, index, &fields, &candidates, &ranked_map)?);
+    }
+
+    // the candidates below the ranking score threshold are only known once scored
+    if exhaustive_nb_hits && ranking_score_threshold.is_none() {
+        let distinct_rule = Some((&distinct, distinct_size));
+        result.nb_hits = exhaustive_nb_hits_count(&docids, filter.as_ref(), &mut filter_map, distinct_rule);
+        result.exhaustive_nb_hit = true;
+    }
+
+    // no document is requested, there is no need to sort the candidates
+    if range.is_empty() && ranking_score_threshold.is_none() {
+        if !exhaustive_nb_hits {
+            result.nb_hits = docids.len();
+        }
+        return Ok(result);
     }
 
     let before = Instant::now();
@@ -272,16 +380,42 @@ where
         before_raw_documents_building.elapsed(),
     );
 
+    // only the candidates above the threshold are hits, they are counted before being paginated
+    let nb_candidates = match ranking_score_threshold {
+        Some(threshold) => {
+            retain_above_threshold(&mut raw_documents, threshold, &mapping, &arena);
+            if exhaustive_nb_hits {
+                let candidates = SetBuf::from_dirty(raw_documents.iter().map(|document| document.id).collect());
+                let distinct_rule = Some((&distinct, distinct_size));
+                result.nb_hits = exhaustive_nb_hits_count(&candidates, filter.as_ref(), &mut filter_map, distinct_rule);
+                result.exhaustive_nb_hit = true;
+            }
+            raw_documents.len()
+        },
+        None => docids.len(),
+    };
+
+    if range.is_empty() {
+        if !exhaustive_nb_hits {
+            result.nb_hits = nb_candidates;
+        }
+        return Ok(result);
+    }
+
     let mut groups = vec![raw_documents.as_mut_slice()];
     let mut key_cache = HashMap::new();
 
-    let mut filter_map = HashMap::new();
     // these two variables informs on the current distinct map and
     // on the raw offset of the start of the group where the
     // range.start bound is located according to the distinct function
     let mut distinct_map = DistinctMap::new(distinct_size);
     let mut distinct_raw_offset = 0;
 
+    // the distinct keys are read from the documents, the number of documents scanned is
+    // bounded, the documents not scanned once it is reached are ignored by every criterion
+    // and the page may be incomplete
+    let mut distinct_scan_limit_reached = false;
+
     'criteria: for criterion in criteria.as_ref() {
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut buf_distinct = BufferedDistinctMap::new(&mut distinct_map);
@@ -296,6 +430,7 @@ where
                 continue;
             }
 
+            check_cancelled(cancelled)?;
             let ctx = ContextMut {
                 reader,
                 postings_lists: &mut arena,
@@ -319,6 +454,7 @@ where
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
                 // we must compute the real distinguished len of this sub-group
                 for document in group.iter() {
+                    check_cancelled(cancelled)?;
                     let filter_accepted = match &filter {
                         Some(filter) => {
                             let entry = filter_map.entry(document.id);
@@ -328,6 +464,13 @@ where
                     };
 
                     if filter_accepted {
+                        if let Some(limit) = distinct_scan_limit {
+                            if key_cache.len() >= limit && !key_cache.contains_key(&document.id) {
+                                distinct_scan_limit_reached = true;
+                                continue;
+                            }
+                        }
+
                         let entry = key_cache.entry(document.id);
                         let key = entry.or_insert_with(|| (distinct)(document.id).map(Rc::new));
 
@@ -368,33 +511,130 @@ where
     let schema = index.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
 
     let mut documents = Vec::with_capacity(range.len());
-    for raw_document in raw_documents.into_iter().skip(distinct_raw_offset) {
+    for mut raw_document in raw_documents.into_iter().skip(distinct_raw_offset) {
         let filter_accepted = match &filter {
             Some(_) => filter_map.remove(&raw_document.id).unwrap(),
             None => true,
         };
 
         if filter_accepted {
-            let key = key_cache.remove(&raw_document.id).unwrap();
+            let key = match key_cache.remove(&raw_document.id) {
+                Some(key) => key,
+                // this document has not been scanned before the limit was reached
+                None if distinct_scan_limit_reached => continue,
+                None => unreachable!("the distinct key of a sorted document is known"),
+            };
             let distinct_accepted = match key {
                 Some(key) => seen.register(key),
                 None => seen.register_without_key(),
             };
 
             if distinct_accepted && seen.len() > range.start {
-                documents.push(Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
+                let score = relevance_score(&mut raw_document, &mapping, &arena);
+                let typo_corrected = is_typo_corrected(&raw_document);
+                let query_typos = query_words_typos(&raw_document, &mapping);
+                let document = Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+                documents.push(Document { score, typo_corrected, query_typos, ..document });
                 if documents.len() == range.len() {
                     break;
                 }
             }
         }
     }
+    follow_ranking_order(&mut documents);
     result.documents = documents;
-    result.nb_hits = docids.len();
+    if !exhaustive_nb_hits {
+        result.nb_hits = nb_candidates;
+    }
+    if distinct_scan_limit_reached {
+        result.exhaustive_nb_hit = false;
+    }
 
     Ok(result)
 }
 
+/// Computes the relevance of a document as the proportion of the query words it contains,
+/// every word being penalized by the number of typos it has been found with.
+fn relevance_score<'tag, 'txn>(
+    raw_document: &mut RawDocument<'_, 'tag>,
+    query_mapping: &HashMap<QueryId, Range<usize>>,
+    postings_lists: &SmallArena<'tag, PostingsListView<'txn>>,
+) -> f64
+{
+    let query_len = query_mapping.values().map(|range| range.end).max().unwrap_or(0);
+    if query_len == 0 {
+        return 1.0;
+    }
+
+    prepare_query_distances(std::slice::from_mut(raw_document), query_mapping, postings_lists);
+    prepared_relevance_score(raw_document, query_len)
+}
+
+/// Computes the relevance of a document whose query distances have been prepared.
+fn prepared_relevance_score(raw_document: &RawDocument, query_len: usize) -> f64 {
+    let words_score: f64 = raw_document.processed_distances
+        .iter()
+        .flatten()
+        .map(|&distance| 1.0 / (1.0 + distance as f64))
+        .sum();
+
+    words_score / query_len as f64
+}
+
+/// Removes the documents whose relevance is below the threshold, the relevance is the one of
+/// the document itself, not the score lowered afterward to follow the ranking order.
+fn retain_above_threshold<'tag, 'txn>(
+    raw_documents: &mut Vec<RawDocument<'_, 'tag>>,
+    threshold: f64,
+    query_mapping: &HashMap<QueryId, Range<usize>>,
+    postings_lists: &SmallArena<'tag, PostingsListView<'txn>>,
+) {
+    let query_len = query_mapping.values().map(|range| range.end).max().unwrap_or(0);
+    if query_len == 0 {
+        return;
+    }
+
+    prepare_query_distances(raw_documents, query_mapping, postings_lists);
+    raw_documents.retain(|document| prepared_relevance_score(document, query_len) >= threshold);
+}
+
+/// Returns whether the best match of a query word in the document has typos,
+/// the query distances must have been prepared by the relevance score.
+fn is_typo_corrected(raw_document: &RawDocument) -> bool {
+    raw_document.processed_distances.iter().flatten().any(|&distance| distance > 0)
+}
+
+/// Returns the number of typos of the best match of every query word, nothing for the words
+/// that are not found, the query distances must have been prepared by the relevance score.
+fn query_words_typos(
+    raw_document: &RawDocument,
+    query_mapping: &HashMap<QueryId, Range<usize>>,
+) -> Vec<Option<u8>>
+{
+    // the words of the query are mapped from the first ids to the positions of their
+    // alternatives, a word replaced by a longer synonym covers several positions
+    (0..)
+        .map(|id| query_mapping.get(&id))
+        .take_while(Option::is_some)
+        .flatten()
+        .map(|range| {
+            range.clone()
+                .filter_map(|position| raw_document.processed_distances.get(position).cloned().flatten())
+                .min()
+        })
+        .collect()
+}
+
+/// The ranking rules can rank a document before a more relevant one,
+/// the scores are lowered so that they never increase down the ranking.
+fn follow_ranking_order(documents: &mut [Document]) {
+    let mut max_score = 1.0;
+    for document in documents {
+        document.score = document.score.min(max_score);
+        max_score = document.score;
+    }
+}
+
 fn cleanup_bare_matches<'tag, 'txn>(
     arena: &mut SmallArena<'tag, PostingsListView<'txn>>,
     docids: &Set<DocumentId>,
@@ -593,15 +833,10 @@ pub fn placeholder_document_sort(
     document_ids: &mut [DocumentId],
     index: &store::Index,
     reader: &MainReader,
-    ranked_map: &RankedMap
+    ranked_map: &RankedMap,
+    missing_first: Option<bool>,
 ) -> MResult<()> {
     use crate::settings::RankingRule;
-    use std::cmp::Ordering;
-
-    enum SortOrder {
-        Asc,
-        Desc,
-    }
 
     if let Some(ranking_rules) = index.main.ranking_rules(reader)? {
         let schema = index.main.schema(reader)?
@@ -611,45 +846,223 @@ pub fn placeholder_document_sort(
         // containing a field_id
         let ranking_rules = ranking_rules.iter().filter_map(|r|
             match r {
-                RankingRule::Asc(name) => schema.id(name).map(|f| (f, SortOrder::Asc)),
-                RankingRule::Desc(name) => schema.id(name).map(|f| (f, SortOrder::Desc)),
+                RankingRule::Asc(name) => schema.id(name).map(|f| PlaceholderSortRule::Attribute(f, false)),
+                RankingRule::Desc(name) => schema.id(name).map(|f| PlaceholderSortRule::Attribute(f, true)),
                 _ => None,
             }).collect::<Vec<_>>();
 
-        document_ids.sort_unstable_by(|a, b| {
-            for (field_id, order) in &ranking_rules {
-                let a_value = ranked_map.get(*a, *field_id);
-                let b_value = ranked_map.get(*b, *field_id);
-                let (a, b) = match order {
-                    SortOrder::Asc => (a_value, b_value),
-                    SortOrder::Desc => (b_value, a_value),
-                };
-                match a.cmp(&b) {
-                    Ordering::Equal => continue,
-                    ordering => return ordering,
-                }
+        rules_document_sort(document_ids, index, reader, ranked_map, &ranking_rules, missing_first, None)?;
+    }
+    Ok(())
+}
+
+/// A rule sorting the documents of a placeholder search, the descending
+/// ones sort the higher values first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaceholderSortRule {
+    /// sorts on the values of a ranked attribute.
+    Attribute(FieldId, bool),
+    /// sorts on the distance between the `_geo` attribute and a point, the
+    /// documents without coordinates always come last.
+    GeoDistance((f64, f64), bool),
+}
+
+/// Sorts the documents with the given rules. The documents without value are placed
+/// first or last according to `missing_first`, last by default. The distances of the
+/// first geo rule are kept in `geo_distances`.
+pub fn rules_document_sort(
+    document_ids: &mut [DocumentId],
+    index: &store::Index,
+    reader: &MainReader,
+    ranked_map: &RankedMap,
+    rules: &[PlaceholderSortRule],
+    missing_first: Option<bool>,
+    geo_distances: Option<&GeoDistances>,
+) -> MResult<()> {
+    use std::cmp::Ordering;
+
+    // the distances of the documents are computed once, before being compared
+    let geo_field = index.main.schema(reader)?.and_then(|schema| schema.id(GEO_ATTRIBUTE));
+    let mut geo_distances = geo_distances;
+    let mut distances = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let mut rule_distances = HashMap::new();
+        if let (PlaceholderSortRule::GeoDistance(point, _), Some(field_id)) = (rule, geo_field) {
+            for &document_id in document_ids.iter() {
+                let distance = document_distance(reader, index.documents_fields, field_id, *point, document_id)?;
+                rule_distances.insert(document_id, distance);
             }
-            Ordering::Equal
-        });
+            if let Some(geo_distances) = geo_distances.take() {
+                geo_distances.borrow_mut().extend(&rule_distances);
+            }
+        }
+        distances.push(rule_distances);
     }
+
+    document_ids.sort_unstable_by(|a, b| {
+        for (rule, distances) in rules.iter().zip(&distances) {
+            let ordering = match *rule {
+                PlaceholderSortRule::Attribute(field_id, descending) => {
+                    let a_value = ranked_map.get(*a, field_id);
+                    let b_value = ranked_map.get(*b, field_id);
+                    compare_ranked_values(a_value, b_value, descending, missing_first)
+                }
+                PlaceholderSortRule::GeoDistance(_, descending) => {
+                    let a_distance = distances.get(a).copied().flatten();
+                    let b_distance = distances.get(b).copied().flatten();
+                    compare_distances(a_distance, b_distance, descending)
+                }
+            };
+            match ordering {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+        // the ties are broken by the document id to keep the pages consistent
+        a.cmp(b)
+    });
+
     Ok(())
 }
 
-/// For each entry in facet_docids, calculates the number of documents in the intersection with candidate_docids.
+/// Calculates the distribution of the sticky facets over the candidates accepted by the filter.
+/// The values of a sticky facet are already restricted by the facet filters that apply to it,
+/// the candidates are the ones found before applying the facet filters.
+pub fn sticky_facet_count<FI>(
+    facet_docids: HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>,
+    candidate_docids: &Set<DocumentId>,
+    max_values: Option<usize>,
+    filter: Option<&FI>,
+    filter_map: &mut HashMap<DocumentId, bool>,
+) -> HashMap<String, HashMap<String, usize>>
+where
+    FI: Fn(DocumentId) -> bool,
+{
+    filtered_facet_count(facet_docids, candidate_docids, max_values, filter, filter_map)
+}
+
+/// Calculates the facets distribution over the candidates accepted by the filter, the filter
+/// results are kept in `filter_map` to be reused afterward.
+fn filtered_facet_count<FI>(
+    facet_docids: HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>,
+    candidate_docids: &Set<DocumentId>,
+    max_values: Option<usize>,
+    filter: Option<&FI>,
+    filter_map: &mut HashMap<DocumentId, bool>,
+) -> HashMap<String, HashMap<String, usize>>
+where
+    FI: Fn(DocumentId) -> bool,
+{
+    let candidates = filtered_candidates(candidate_docids, filter, filter_map);
+    facet_count(facet_docids, &candidates, max_values)
+}
+
+/// Returns the candidates accepted by the filter, the filter results
+/// are kept in `filter_map` to be reused afterward.
+fn filtered_candidates<'a, FI>(
+    candidate_docids: &'a Set<DocumentId>,
+    filter: Option<&FI>,
+    filter_map: &mut HashMap<DocumentId, bool>,
+) -> Cow<'a, Set<DocumentId>>
+where
+    FI: Fn(DocumentId) -> bool,
+{
+    match filter {
+        Some(filter) => {
+            let filtered = candidate_docids
+                .iter()
+                .filter(|id| *filter_map.entry(**id).or_insert_with(|| (filter)(**id)))
+                .cloned()
+                .collect();
+            Cow::Owned(SetBuf::new_unchecked(filtered))
+        }
+        None => Cow::Borrowed(candidate_docids),
+    }
+}
+
+/// Counts the candidates accepted by both the filter and the distinct rule, every candidate
+/// must be checked so this is only done when an exact number of hits is requested.
+pub fn exhaustive_nb_hits_count<FI, FD>(
+    candidate_docids: &[DocumentId],
+    filter: Option<&FI>,
+    filter_map: &mut HashMap<DocumentId, bool>,
+    distinct: Option<(&FD, usize)>,
+) -> usize
+where
+    FI: Fn(DocumentId) -> bool,
+    FD: Fn(DocumentId) -> Option<u64>,
+{
+    let mut distinct_map = distinct.map(|(_, size)| DistinctMap::new(size));
+    let mut seen = distinct_map.as_mut().map(BufferedDistinctMap::new);
+
+    candidate_docids
+        .iter()
+        .filter(|id| match filter {
+            Some(filter) => *filter_map.entry(**id).or_insert_with(|| (filter)(**id)),
+            None => true,
+        })
+        .filter(|id| match (distinct, seen.as_mut()) {
+            (Some((distinct, _)), Some(seen)) => match (distinct)(**id) {
+                Some(key) => seen.register(key),
+                None => seen.register_without_key(),
+            },
+            _ => true,
+        })
+        .count()
+}
+
+/// Counts the candidates having each value of the facets. When the number of values is
+/// capped only the most frequent ones are kept, the lowest value first on equal counts.
 pub fn facet_count(
     facet_docids: HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>,
     candidate_docids: &Set<DocumentId>,
+    max_values: Option<usize>,
 ) -> HashMap<String, HashMap<String, usize>> {
     let mut facets_counts = HashMap::with_capacity(facet_docids.len());
     for (key, doc_map) in facet_docids {
-        let mut count_map = HashMap::with_capacity(doc_map.len());
-        for (value, docids) in doc_map {
+        let counts = doc_map.into_iter().map(|(value, docids)| {
             let mut counter = Counter::new();
             let op = OpBuilder::new(docids.as_ref(), candidate_docids).intersection();
             SetOperation::<DocumentId>::extend_collection(op, &mut counter);
-            count_map.insert(value, counter.0);
-        }
+            (value, counter.0)
+        });
+
+        let count_map = match max_values {
+            Some(max) => most_frequent_values(counts, max),
+            None => counts.collect(),
+        };
         facets_counts.insert(key, count_map);
     }
     facets_counts
 }
+
+/// Counts the candidates having each combination of the values of the two pivot facets,
+/// the combinations without any candidate are dropped.
+pub fn pivot_count(
+    pivot_docids: HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>,
+    candidate_docids: &Set<DocumentId>,
+) -> HashMap<String, HashMap<String, usize>> {
+    let mut pivot_counts = facet_count(pivot_docids, candidate_docids, None);
+    pivot_counts.retain(|_, counts| {
+        counts.retain(|_, count| *count > 0);
+        !counts.is_empty()
+    });
+    pivot_counts
+}
+
+/// Keeps the `max` most frequent values, only these values and the next one
+/// are held in memory while the counts are iterated.
+fn most_frequent_values(
+    counts: impl Iterator<Item = (String, usize)>,
+    max: usize,
+) -> HashMap<String, usize> {
+    // the heap top is the least frequent value, the highest one on equal counts
+    let mut heap = BinaryHeap::with_capacity(max + 1);
+    for (value, count) in counts {
+        heap.push(Reverse((count, Reverse(value))));
+        if heap.len() > max {
+            heap.pop();
+        }
+    }
+    heap.into_iter().map(|Reverse((count, Reverse(value)))| (value, count)).collect()
+}
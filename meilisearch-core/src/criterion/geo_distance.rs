@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use meilisearch_schema::{FieldId, Schema};
+use serde_json::Value;
+
+use crate::filters::{geo_point, GEO_ATTRIBUTE};
+use crate::store::DocumentsFields;
+use crate::{DocumentId, MainT, MResult, RawDocument};
+use super::{Criterion, Context, ContextMut, SortByAttrError};
+
+const EARTH_RADIUS_IN_METERS: f64 = 6_371_000.0;
+
+/// The distances between the documents and a point, in meters.
+pub type GeoDistances = Rc<RefCell<HashMap<DocumentId, Option<f64>>>>;
+
+/// Sorts the documents by the distance between their `_geo` attribute and a given point.
+///
+/// Documents without valid coordinates are always placed after the other ones.
+pub struct GeoDistance {
+    documents_fields: DocumentsFields,
+    field_id: FieldId,
+    point: (f64, f64),
+    reversed: bool,
+    distances: GeoDistances,
+}
+
+impl GeoDistance {
+    pub fn nearest_first(
+        documents_fields: DocumentsFields,
+        schema: &Schema,
+        point: (f64, f64),
+    ) -> Result<GeoDistance, SortByAttrError> {
+        GeoDistance::new(documents_fields, schema, point, false)
+    }
+
+    pub fn farthest_first(
+        documents_fields: DocumentsFields,
+        schema: &Schema,
+        point: (f64, f64),
+    ) -> Result<GeoDistance, SortByAttrError> {
+        GeoDistance::new(documents_fields, schema, point, true)
+    }
+
+    /// shares the distances computed while ranking, they can be read once the search is done.
+    pub fn with_distances(mut self, distances: GeoDistances) -> GeoDistance {
+        self.distances = distances;
+        self
+    }
+
+    fn new(
+        documents_fields: DocumentsFields,
+        schema: &Schema,
+        point: (f64, f64),
+        reversed: bool,
+    ) -> Result<GeoDistance, SortByAttrError> {
+        let field_id = match schema.id(GEO_ATTRIBUTE) {
+            Some(field_id) => field_id,
+            None => return Err(SortByAttrError::AttributeNotFound),
+        };
+
+        Ok(GeoDistance {
+            documents_fields,
+            field_id,
+            point,
+            reversed,
+            distances: GeoDistances::default(),
+        })
+    }
+}
+
+/// returns the distance in meters between the `_geo` attribute of a document and a point,
+/// nothing is returned when the document has no valid coordinates.
+pub(crate) fn document_distance(
+    reader: &heed::RoTxn<MainT>,
+    documents_fields: DocumentsFields,
+    field_id: FieldId,
+    point: (f64, f64),
+    document_id: DocumentId,
+) -> MResult<Option<f64>> {
+    let bytes = documents_fields.document_attribute(reader, document_id, field_id)?;
+    let distance = bytes
+        .and_then(|bytes| serde_json::from_slice::<Value>(bytes).ok())
+        .and_then(|value| geo_point(&value))
+        .map(|document_point| haversine_distance(point, document_point));
+    Ok(distance)
+}
+
+/// compares two distances, the ones that are missing are always placed last.
+pub(crate) fn compare_distances(lhs: Option<f64>, rhs: Option<f64>, reversed: bool) -> Ordering {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => {
+            let order = lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal);
+            if reversed {
+                order.reverse()
+            } else {
+                order
+            }
+        }
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// returns the distance in meters between two points, using the haversine formula.
+pub fn haversine_distance((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_lat = lat2 - lat1;
+    let delta_lng = (lng2 - lng1).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+impl Criterion for GeoDistance {
+    fn name(&self) -> &str {
+        "geo distance"
+    }
+
+    fn prepare<'h, 'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        ctx: ContextMut<'h, 'p, 'tag, 'txn, 'q>,
+        documents: &mut [RawDocument<'r, 'tag>],
+    ) -> MResult<()>
+    {
+        // the coordinates are read once per document, the comparisons only use the cache
+        let mut distances = self.distances.borrow_mut();
+        for document in documents.iter() {
+            if distances.contains_key(&document.id) {
+                continue;
+            }
+            let distance = document_distance(ctx.reader, self.documents_fields, self.field_id, self.point, document.id)?;
+            distances.insert(document.id, distance);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let distances = self.distances.borrow();
+        let lhs = distances.get(&lhs.id).copied().flatten();
+        let rhs = distances.get(&rhs.id).copied().flatten();
+        compare_distances(lhs, rhs, self.reversed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn haversine() {
+        let paris = (48.8566, 2.3522);
+        let lyon = (45.7640, 4.8357);
+        let distance = haversine_distance(paris, lyon);
+        assert!((390_000.0..395_000.0).contains(&distance), "{}", distance);
+        assert_eq!(haversine_distance(paris, paris), 0.0);
+    }
+}
@@ -19,6 +19,7 @@ mod words_position;
 mod exactness;
 mod document_id;
 mod sort_by_attr;
+mod geo_distance;
 
 pub use self::typo::Typo;
 pub use self::words::Words;
@@ -27,7 +28,10 @@ pub use self::attribute::Attribute;
 pub use self::words_position::WordsPosition;
 pub use self::exactness::Exactness;
 pub use self::document_id::DocumentId;
-pub use self::sort_by_attr::SortByAttr;
+pub use self::sort_by_attr::{SortByAttr, SortByAttrError};
+pub(crate) use self::sort_by_attr::compare_ranked_values;
+pub use self::geo_distance::{GeoDistance, GeoDistances, haversine_distance};
+pub(crate) use self::geo_distance::{compare_distances, document_distance};
 
 pub trait Criterion {
     fn name(&self) -> &str;
@@ -137,7 +141,7 @@ impl<'a> AsRef<[Box<dyn Criterion + 'a>]> for Criteria<'a> {
     }
 }
 
-fn prepare_query_distances<'a, 'tag, 'txn>(
+pub(crate) fn prepare_query_distances<'a, 'tag, 'txn>(
     documents: &mut [RawDocument<'a, 'tag>],
     query_mapping: &HashMap<QueryId, Range<usize>>,
     postings_lists: &SmallArena<'tag, PostingsListView<'txn>>,
@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 use meilisearch_schema::{Schema, FieldId};
-use crate::{RankedMap, RawDocument};
+use crate::{Number, RankedMap, RawDocument};
 use super::{Criterion, Context};
 
 /// An helper struct that permit to sort documents by
@@ -12,11 +12,11 @@ use super::{Criterion, Context};
 ///
 /// If a document cannot be deserialized it will be considered [`None`][].
 ///
-/// Deserialized documents are compared like `Some(doc0).cmp(&Some(doc1))`,
-/// so you must check the [`Ord`] of `Option` implementation.
+/// The documents without value are placed after the other ones, whatever the
+/// order, unless [`with_missing_first`] places them first.
 ///
 /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
-/// [`Ord`]: https://doc.rust-lang.org/std/option/enum.Option.html#impl-Ord
+/// [`with_missing_first`]: SortByAttr::with_missing_first
 ///
 /// # Example
 ///
@@ -43,6 +43,8 @@ pub struct SortByAttr<'a> {
     ranked_map: &'a RankedMap,
     field_id: FieldId,
     reversed: bool,
+    missing_first: Option<bool>,
+    name: String,
 }
 
 impl<'a> SortByAttr<'a> {
@@ -77,36 +79,60 @@ impl<'a> SortByAttr<'a> {
             return Err(SortByAttrError::AttributeNotRegisteredForRanking);
         }
 
+        let name = format!("{}({})", if reversed { "desc" } else { "asc" }, attr_name);
+
         Ok(SortByAttr {
             ranked_map,
             field_id,
             reversed,
+            missing_first: None,
+            name,
         })
     }
+
+    /// places the documents without value before or after the other ones whatever
+    /// the order, they are placed after them by default.
+    pub fn with_missing_first(mut self, missing_first: Option<bool>) -> SortByAttr<'a> {
+        self.missing_first = missing_first;
+        self
+    }
+}
+
+/// Compares two ranked values, the missing ones are placed before or after the other
+/// values whatever the order, after them by default.
+pub(crate) fn compare_ranked_values(
+    lhs: Option<Number>,
+    rhs: Option<Number>,
+    reversed: bool,
+    missing_first: Option<bool>,
+) -> Ordering {
+    let missing_first = missing_first.unwrap_or(false);
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => {
+            let order = lhs.cmp(&rhs);
+            if reversed {
+                order.reverse()
+            } else {
+                order
+            }
+        }
+        (None, Some(_)) if missing_first => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) if missing_first => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
 }
 
 impl Criterion for SortByAttr<'_> {
     fn name(&self) -> &str {
-        "sort by attribute"
+        &self.name
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
         let lhs = self.ranked_map.get(lhs.id, self.field_id);
         let rhs = self.ranked_map.get(rhs.id, self.field_id);
-
-        match (lhs, rhs) {
-            (Some(lhs), Some(rhs)) => {
-                let order = lhs.cmp(&rhs);
-                if self.reversed {
-                    order.reverse()
-                } else {
-                    order
-                }
-            }
-            (None, Some(_)) => Ordering::Greater,
-            (Some(_), None) => Ordering::Less,
-            (None, None) => Ordering::Equal,
-        }
+        compare_ranked_values(lhs, rhs, self.reversed, self.missing_first)
     }
 }
 
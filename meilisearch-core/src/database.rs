@@ -128,8 +128,10 @@ fn update_awaiter(
             let result = update::update_task(&mut main_writer, &index, update_id, update);
             let status = break_try!(result, "update task failed");
 
-            // commit the main transaction if the update was successful, abort it otherwise
+            // commit the main transaction if the update was successful, abort it otherwise,
+            // the last update time of the index is written along with the update itself
             if status.error.is_none() {
+                break_try!(index.main.put_updated_at(&mut main_writer), "updating the index update time failed");
                 break_try!(main_writer.commit(), "commit nested transaction failed");
             } else {
                 break_try!(main_writer.abort(), "abborting nested transaction failed");
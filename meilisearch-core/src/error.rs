@@ -16,6 +16,7 @@ pub type MResult<T> = Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
     Bincode(bincode::Error),
+    Cancelled,
     Deserializer(DeserializerError),
     FacetError(FacetError),
     FilterParseError(PestError<Rule>),
@@ -49,6 +50,7 @@ impl ErrorCode for Error {
             WordIndexMissing
             | SchemaMissing => Code::InvalidState,
             Heed(_)
+            | Cancelled
             | Fst(_)
             | SerdeJson(_)
             | Bincode(_)
@@ -144,6 +146,7 @@ impl fmt::Display for Error {
         use self::Error::*;
         match self {
             Bincode(e) => write!(f, "bincode error; {}", e),
+            Cancelled => write!(f, "the search has been cancelled"),
             Deserializer(e) => write!(f, "deserializer error; {}", e),
             FacetError(e) => write!(f, "error processing facet filter: {}", e),
             FilterParseError(e) => write!(f, "error parsing filter; {}", e),
@@ -186,7 +189,6 @@ impl fmt::Display for FilterParseError {
 
 #[derive(Debug)]
 pub enum FacetError {
-    EmptyArray,
     ParsingError(String),
     UnexpectedToken { expected: &'static [&'static str], found: String },
     InvalidFormat(String),
@@ -194,6 +196,7 @@ pub enum FacetError {
     AttributeNotSet { expected: Vec<String>, found: String },
     InvalidDocumentAttribute(String),
     NoAttributesForFaceting,
+    InvalidElement { clause: usize, element: Option<usize>, error: Box<FacetError> },
 }
 
 impl FacetError {
@@ -204,6 +207,12 @@ impl FacetError {
     pub fn attribute_not_set(expected: Vec<String>, found: impl ToString) -> FacetError {
         FacetError::AttributeNotSet{ expected, found: found.to_string() }
     }
+
+    /// attaches to the error the position of the faulty clause, and element if any, in the
+    /// facet filter array.
+    pub fn at_position(self, clause: usize, element: Option<usize>) -> FacetError {
+        FacetError::InvalidElement { clause, element, error: Box::new(self) }
+    }
 }
 
 impl fmt::Display for FacetError {
@@ -211,7 +220,6 @@ impl fmt::Display for FacetError {
         use FacetError::*;
 
         match self {
-            EmptyArray => write!(f, "empty array in facet filter is unspecified behavior"),
             ParsingError(msg) => write!(f, "parsing error: {}", msg),
             UnexpectedToken { expected, found } => write!(f, "unexpected token {}, expected {}", found, expected.join("or")),
             InvalidFormat(found) => write!(f, "invalid facet: {}, facets should be \"facetName:facetValue\"", found),
@@ -219,6 +227,8 @@ impl fmt::Display for FacetError {
             AttributeNotSet { found, expected } => write!(f, "`{}` is not set as a faceted attribute. available facet attributes: {}", found, expected.join(", ")),
             InvalidDocumentAttribute(attr) => write!(f, "invalid document attribute {}, accepted types: String and [String]", attr),
             NoAttributesForFaceting => write!(f, "impossible to perform faceted search, no attributes for faceting are set"),
+            InvalidElement { clause, element: Some(element), error } => write!(f, "{} (element {} of clause {})", error, element, clause),
+            InvalidElement { clause, element: None, error } => write!(f, "{} (clause {})", error, clause),
         }
     }
 }
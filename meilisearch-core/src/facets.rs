@@ -6,15 +6,19 @@ use std::ops::Deref;
 use cow_utils::CowUtils;
 use either::Either;
 use heed::types::{Str, OwnedType};
-use indexmap::IndexMap;
+use serde::Serialize;
 use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
 
 use meilisearch_schema::{FieldId, Schema};
 use meilisearch_types::DocumentId;
 
 use crate::database::MainT;
 use crate::error::{FacetError, MResult};
-use crate::store::BEU16;
+use crate::settings::{FacetNormalization, DEFAULT_FACET_NORMALIZATION};
+use crate::store::{self, BEU16};
+use crate::update::value_to_number;
+use crate::RankedMap;
 
 /// Data structure used to represent a boolean expression in the form of nested arrays.
 /// Values in the outer array are and-ed together, values in the inner arrays are or-ed together.
@@ -34,43 +38,81 @@ impl FacetFilter {
         s: &str,
         schema: &Schema,
         attributes_for_faceting: &[FieldId],
+        normalization: &[FacetNormalization],
     ) -> MResult<FacetFilter> {
+        Self::parse(s, schema, attributes_for_faceting, normalization, false).map(|(filter, _)| filter)
+    }
+
+    /// parses the facet filter like `from_str` but the elements on unknown attributes are dropped
+    /// instead of failing, they are returned with the filter. A clause is dropped with all its
+    /// elements, the filter is then empty when every clause has been dropped.
+    pub fn from_str_lenient(
+        s: &str,
+        schema: &Schema,
+        attributes_for_faceting: &[FieldId],
+        normalization: &[FacetNormalization],
+    ) -> MResult<(FacetFilter, Vec<FacetError>)> {
+        Self::parse(s, schema, attributes_for_faceting, normalization, true)
+    }
+
+    fn parse(
+        s: &str,
+        schema: &Schema,
+        attributes_for_faceting: &[FieldId],
+        normalization: &[FacetNormalization],
+        lenient: bool,
+    ) -> MResult<(FacetFilter, Vec<FacetError>)> {
         if attributes_for_faceting.is_empty() {
             return Err(FacetError::NoAttributesForFaceting.into());
         }
         let parsed = serde_json::from_str::<Value>(s).map_err(|e| FacetError::ParsingError(e.to_string()))?;
         let mut filter = Vec::new();
+        let mut dropped = Vec::new();
         match parsed {
+            // the empty arrays do not filter anything out
             Value::Array(and_exprs) => {
-                if and_exprs.is_empty() {
-                    return Err(FacetError::EmptyArray.into());
-                }
-                for expr in and_exprs {
+                for (i, expr) in and_exprs.into_iter().enumerate() {
                     match expr {
                         Value::String(s) => {
-                            let key = FacetKey::from_str( &s, schema, attributes_for_faceting)?;
-                            filter.push(Either::Right(key));
+                            match FacetKey::from_str( &s, schema, attributes_for_faceting, normalization) {
+                                Ok(key) => filter.push(Either::Right(key)),
+                                Err(e @ FacetError::AttributeNotFound(_)) if lenient => {
+                                    dropped.push(e.at_position(i, None));
+                                }
+                                Err(e) => return Err(e.at_position(i, None).into()),
+                            }
                         }
                         Value::Array(or_exprs) => {
-                            if or_exprs.is_empty() {
-                                return Err(FacetError::EmptyArray.into());
-                            }
                             let mut inner = Vec::new();
-                            for expr in or_exprs {
+                            for (j, expr) in or_exprs.into_iter().enumerate() {
                                 match expr {
                                     Value::String(s) => {
-                                        let key = FacetKey::from_str( &s, schema, attributes_for_faceting)?;
-                                        inner.push(key);
+                                        match FacetKey::from_str( &s, schema, attributes_for_faceting, normalization) {
+                                            Ok(key) => inner.push(key),
+                                            Err(e @ FacetError::AttributeNotFound(_)) if lenient => {
+                                                dropped.push(e.at_position(i, Some(j)));
+                                            }
+                                            Err(e) => return Err(e.at_position(i, Some(j)).into()),
+                                        }
+                                    }
+                                    bad_value => {
+                                        let error = FacetError::unexpected_token(&["String"], bad_value);
+                                        return Err(error.at_position(i, Some(j)).into());
                                     }
-                                    bad_value => return Err(FacetError::unexpected_token(&["String"], bad_value).into()),
                                 }
                             }
-                            filter.push(Either::Left(inner));
+                            // the or-ed elements may all have been dropped, or never given
+                            if !inner.is_empty() {
+                                filter.push(Either::Left(inner));
+                            }
+                        }
+                        bad_value => {
+                            let error = FacetError::unexpected_token(&["Array", "String"], bad_value);
+                            return Err(error.at_position(i, None).into());
                         }
-                        bad_value => return Err(FacetError::unexpected_token(&["Array", "String"], bad_value).into()),
                     }
                 }
-                Ok(Self(filter))
+                Ok((Self(filter), dropped))
             }
             bad_value => Err(FacetError::unexpected_token(&["Array"], bad_value).into()),
         }
@@ -90,6 +132,11 @@ impl FacetKey {
         Self(field_id, value)
     }
 
+    /// creates a key from a value that first goes through the given normalization steps.
+    pub fn normalized(field_id: FieldId, value: String, normalization: &[FacetNormalization]) -> Self {
+        Self::new(field_id, normalize_facet_value(value, normalization))
+    }
+
     pub fn key(&self) -> FieldId {
         self.0
     }
@@ -103,6 +150,7 @@ impl FacetKey {
         s: &str,
         schema: &Schema,
         attributes_for_faceting: &[FieldId],
+        normalization: &[FacetNormalization],
     ) -> Result<Self, FacetError> {
         let mut split = s.splitn(2, ':');
         let key = split
@@ -133,7 +181,7 @@ impl FacetKey {
             (Some((s, '\"')), Some((e, '\"'))) => value[s + 1..e].to_string(),
             _ => value.to_string(),
         };
-        Ok(Self::new(field_id, value))
+        Ok(Self::normalized(field_id, value, normalization))
     }
 }
 
@@ -163,11 +211,70 @@ impl<'a> heed::BytesDecode<'a> for FacetKey {
     }
 }
 
+/// applies the normalization steps to a facet value, in order.
+pub fn normalize_facet_value(value: String, normalization: &[FacetNormalization]) -> String {
+    normalization.iter().fold(value, |value, step| match step {
+        FacetNormalization::Trim => match value.trim() {
+            trimmed if trimmed.len() == value.len() => value,
+            trimmed => trimmed.to_string(),
+        },
+        FacetNormalization::Nfc => value.nfc().collect(),
+    })
+}
+
+/// The range and the average of the numeric values of a facet over the candidates of a search.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FacetStats {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
+/// Computes the stats of the given fields over the candidates, the values of the ranked
+/// fields are read from the ranked map and the ones of the other fields from the stored
+/// documents. The fields without any numeric value among the candidates are skipped.
+pub fn facet_stats(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    fields: &[(FieldId, String)],
+    candidates: &[DocumentId],
+    ranked_map: &RankedMap,
+) -> MResult<HashMap<String, FacetStats>> {
+    let schema = index.main.schema(reader)?;
+    let mut stats = HashMap::with_capacity(fields.len());
+    for (field_id, name) in fields {
+        let ranked = schema.as_ref().map_or(false, |schema| schema.is_ranked(*field_id));
+
+        let mut accumulator: Option<(f64, f64, f64, usize)> = None;
+        for id in candidates {
+            let value = if ranked {
+                ranked_map.get(*id, *field_id).and_then(|n| n.as_f64())
+            } else {
+                index.document_attribute::<Value>(reader, *id, *field_id)?
+                    .and_then(|value| value_to_number(&value))
+                    .and_then(|n| n.as_f64())
+            };
+            if let Some(value) = value {
+                accumulator = Some(match accumulator {
+                    Some((min, max, sum, count)) => (min.min(value), max.max(value), sum + value, count + 1),
+                    None => (value, value, value, 1),
+                });
+            }
+        }
+
+        if let Some((min, max, sum, count)) = accumulator {
+            stats.insert(name.clone(), FacetStats { min, max, avg: sum / count as f64 });
+        }
+    }
+    Ok(stats)
+}
+
 pub fn add_to_facet_map(
     facet_map: &mut HashMap<FacetKey, Vec<DocumentId>>,
     field_id: FieldId,
     value: Value,
     document_id: DocumentId,
+    normalization: &[FacetNormalization],
 ) -> Result<(), FacetError> {
     let value = match value {
         Value::String(s) => s,
@@ -175,8 +282,13 @@ pub fn add_to_facet_map(
         Value::Null => return Ok(()),
         value => return Err(FacetError::InvalidDocumentAttribute(value.to_string())),
     };
-    let key = FacetKey::new(field_id, value);
-    facet_map.entry(key).or_insert_with(Vec::new).push(document_id);
+    let key = FacetKey::normalized(field_id, value, normalization);
+    let document_ids = facet_map.entry(key).or_insert_with(Vec::new);
+    // the values of a document are added together, a value repeated in the
+    // document, or normalized to the same key, must count it once
+    if document_ids.last() != Some(&document_id) {
+        document_ids.push(document_id);
+    }
     Ok(())
 }
 
@@ -186,54 +298,85 @@ pub fn facet_map_from_docids(
     document_ids: &[DocumentId],
     attributes_for_facetting: &[FieldId],
 ) -> MResult<HashMap<FacetKey, Vec<DocumentId>>> {
+    let normalization = index.main
+        .facet_normalization(rtxn)?
+        .unwrap_or_else(|| DEFAULT_FACET_NORMALIZATION.to_vec());
+    let max_length = index.main.max_facet_value_length(rtxn)?;
     let mut facet_map = HashMap::new();
+    let schema = match index.main.schema(rtxn)? {
+        Some(schema) => schema,
+        None => return Ok(facet_map),
+    };
+
+    // the facets are read from the attribute they are named after, the ones named after a
+    // dotted path, like `variants.color`, are also read in the attribute the path starts with
+    let mut facet_sources: HashMap<FieldId, Vec<(FieldId, Vec<&str>)>> = HashMap::new();
+    for &facet_id in attributes_for_facetting {
+        facet_sources.entry(facet_id).or_insert_with(Vec::new).push((facet_id, Vec::new()));
+        let name = schema.name(facet_id).unwrap_or_default();
+        let mut path = name.split('.');
+        if let (Some(root), true) = (path.next(), name.contains('.')) {
+            if let Some(root_id) = schema.id(root) {
+                facet_sources.entry(root_id).or_insert_with(Vec::new).push((facet_id, path.collect()));
+            }
+        }
+    }
+
     for document_id in document_ids {
         for result in index
             .documents_fields
             .document_fields(rtxn, *document_id)?
         {
             let (field_id, bytes) = result?;
-            if attributes_for_facetting.contains(&field_id) {
-                match serde_json::from_slice(bytes)? {
-                    Value::Array(values) => {
-                        for v in values {
-                            add_to_facet_map(&mut facet_map, field_id, v, *document_id)?;
+            if let Some(sources) = facet_sources.get(&field_id) {
+                let value: Value = serde_json::from_slice(bytes)?;
+                for (facet_id, path) in sources {
+                    let mut values = Vec::new();
+                    nested_values(&value, path, &mut values);
+                    for v in values {
+                        if exceeds_max_length(v, max_length) {
+                            log::warn!(
+                                "facet value of attribute {} in document {} is longer than {} characters, it is not indexed",
+                                schema.name(*facet_id).unwrap_or_default(),
+                                document_id.0,
+                                max_length.unwrap_or_default(),
+                            );
+                            continue;
                         }
+                        add_to_facet_map(&mut facet_map, *facet_id, v.clone(), *document_id, &normalization)?;
                     }
-                    v => add_to_facet_map(&mut facet_map, field_id, v, *document_id)?,
-                };
+                }
             }
         }
     }
     Ok(facet_map)
 }
 
-pub fn facet_map_from_docs(
-    schema: &Schema,
-    documents: &HashMap<DocumentId, IndexMap<String, Value>>,
-    attributes_for_facetting: &[FieldId],
-) -> MResult<HashMap<FacetKey, Vec<DocumentId>>> {
-    let mut facet_map = HashMap::new();
-    let attributes_for_facetting = attributes_for_facetting
-        .iter()
-        .filter_map(|&id| schema.name(id).map(|name| (id, name)))
-        .collect::<Vec<_>>();
-
-    for (id, document) in documents {
-        for (field_id, name) in &attributes_for_facetting {
-            if let Some(value) = document.get(*name) {
-                match value {
-                    Value::Array(values) => {
-                        for v in values {
-                            add_to_facet_map(&mut facet_map, *field_id, v.clone(), *id)?;
-                        }
-                    }
-                    v => add_to_facet_map(&mut facet_map, *field_id, v.clone(), *id)?,
-                }
+/// Whether a facet value is too long to be indexed, only the strings can be facet values.
+fn exceeds_max_length(value: &Value, max_length: Option<usize>) -> bool {
+    match (value, max_length) {
+        (Value::String(s), Some(max_length)) => s.chars().count() > max_length,
+        _ => false,
+    }
+}
+
+/// Collects the values found at the end of a path of object keys, the arrays met
+/// on the way are walked into, the values of an array are collected one by one.
+fn nested_values<'a>(value: &'a Value, path: &[&str], values: &mut Vec<&'a Value>) {
+    match (value, path) {
+        (Value::Array(array), _) => {
+            for value in array {
+                nested_values(value, path, values);
+            }
+        }
+        (value, []) => values.push(value),
+        (Value::Object(object), [key, rest @ ..]) => {
+            if let Some(value) = object.get(*key) {
+                nested_values(value, rest, values);
             }
         }
+        _ => (),
     }
-    Ok(facet_map)
 }
 
 #[cfg(test)]
@@ -247,38 +390,73 @@ mod test {
         let id = schema.insert_and_index("hello").unwrap();
         let facet_list = [schema.id("hello").unwrap()];
         assert_eq!(
-            FacetKey::from_str("hello:12", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:12", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "12".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:\"foo bar\"", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:\"foo bar\"", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "foo bar".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:'foo bar'", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:'foo bar'", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "foo bar".to_string())
         );
         // weird case
         assert_eq!(
-            FacetKey::from_str("hello:blabla:machin", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:blabla:machin", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "blabla:machin".to_string())
         );
 
         assert_eq!(
-            FacetKey::from_str("hello:\"\"", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:\"\"", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "".to_string())
         );
 
         assert_eq!(
-            FacetKey::from_str("hello:'", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:'", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "'".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:''", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:''", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetKey::new(id, "".to_string())
         );
-        assert!(FacetKey::from_str("hello", &schema, &facet_list).is_err());
-        assert!(FacetKey::from_str("toto:12", &schema, &facet_list).is_err());
+        assert!(FacetKey::from_str("hello", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+        assert!(FacetKey::from_str("toto:12", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+    }
+
+    #[test]
+    fn test_facet_value_normalization() {
+        let mut schema = Schema::new();
+        let id = schema.insert_and_index("genre").unwrap();
+        let facet_list = [id];
+
+        let mut facet_map = HashMap::new();
+        let values = ["Horror", "Horror ", " horror", "Caf\u{e9}", "Cafe\u{301}"];
+        for (i, value) in values.iter().enumerate() {
+            let value = Value::String(value.to_string());
+            add_to_facet_map(&mut facet_map, id, value, DocumentId(i as u32), &DEFAULT_FACET_NORMALIZATION).unwrap();
+        }
+        assert_eq!(facet_map.len(), 2);
+        assert_eq!(facet_map[&FacetKey::new(id, "horror".to_string())], vec![DocumentId(0), DocumentId(1), DocumentId(2)]);
+        assert_eq!(facet_map[&FacetKey::new(id, "caf\u{e9}".to_string())], vec![DocumentId(3), DocumentId(4)]);
+
+        // the facet filters are normalized the same way
+        assert_eq!(
+            FacetKey::from_str("genre:'Horror '", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
+            FacetKey::new(id, "horror".to_string())
+        );
+        assert_eq!(
+            FacetKey::from_str("genre:Cafe\u{301}", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
+            FacetKey::new(id, "caf\u{e9}".to_string())
+        );
+
+        // without any normalization step the values are kept as is
+        let mut facet_map = HashMap::new();
+        for (i, value) in values.iter().enumerate() {
+            let value = Value::String(value.to_string());
+            add_to_facet_map(&mut facet_map, id, value, DocumentId(i as u32), &[]).unwrap();
+        }
+        assert_eq!(facet_map.len(), 5);
     }
 
     #[test]
@@ -288,22 +466,22 @@ mod test {
         let _id = schema.insert_and_index("hello").unwrap();
         let facet_list = [schema.id("hello").unwrap()];
         assert_eq!(
-            FacetFilter::from_str("[[\"hello:12\"]]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[[\"hello:12\"]]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetFilter(vec![Left(vec![FacetKey(FieldId(0), "12".to_string())])])
         );
         assert_eq!(
-            FacetFilter::from_str("[\"hello:12\"]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[\"hello:12\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetFilter(vec![Right(FacetKey(FieldId(0), "12".to_string()))])
         );
         assert_eq!(
-            FacetFilter::from_str("[\"hello:12\", \"hello:13\"]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[\"hello:12\", \"hello:13\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetFilter(vec![
                 Right(FacetKey(FieldId(0), "12".to_string())),
                 Right(FacetKey(FieldId(0), "13".to_string()))
             ])
         );
         assert_eq!(
-            FacetFilter::from_str("[[\"hello:12\", \"hello:13\"]]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[[\"hello:12\", \"hello:13\"]]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap(),
             FacetFilter(vec![Left(vec![
                 FacetKey(FieldId(0), "12".to_string()),
                 FacetKey(FieldId(0), "13".to_string())
@@ -313,7 +491,8 @@ mod test {
             FacetFilter::from_str(
                 "[[\"hello:12\", \"hello:13\"], \"hello:14\"]",
                 &schema,
-                &facet_list
+                &facet_list,
+                &DEFAULT_FACET_NORMALIZATION,
             )
             .unwrap(),
             FacetFilter(vec![
@@ -329,27 +508,85 @@ mod test {
         assert!(FacetFilter::from_str(
             "[[[\"hello:12\", \"hello:13\"], \"hello:14\"]]",
             &schema,
-            &facet_list
+            &facet_list,
+            &DEFAULT_FACET_NORMALIZATION,
         )
         .is_err());
         assert!(FacetFilter::from_str(
             "[[[\"hello:12\", \"hello:13\"]], \"hello:14\"]]",
             &schema,
-            &facet_list
+            &facet_list,
+            &DEFAULT_FACET_NORMALIZATION,
         )
         .is_err());
-        assert!(FacetFilter::from_str("\"hello:14\"", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("\"hello:14\"", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
 
         // unexisting key
-        assert!(FacetFilter::from_str("[\"foo:12\"]", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("[\"foo:12\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
 
         // invalid facet key
-        assert!(FacetFilter::from_str("[\"foo=12\"]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"foo12\"]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"\"]", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("[\"foo=12\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+        assert!(FacetFilter::from_str("[\"foo12\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+        assert!(FacetFilter::from_str("[\"\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+
+        // empty arrays do not filter anything
+        let filter = FacetFilter::from_str("[]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap();
+        assert_eq!(filter, FacetFilter(Vec::new()));
+        let filter = FacetFilter::from_str("[[]]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap();
+        assert_eq!(filter, FacetFilter(Vec::new()));
+        let filter = FacetFilter::from_str("[\"hello:12\", []]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap();
+        assert_eq!(
+            filter,
+            FacetFilter(vec![Either::Right(FacetKey::new(schema.id("hello").unwrap(), "12".to_string()))]),
+        );
+    }
+
+    #[test]
+    fn test_facet_filter_error_position() {
+        let mut schema = Schema::new();
+        let _id = schema.insert_and_index("hello").unwrap();
+        let facet_list = [schema.id("hello").unwrap()];
+
+        let error = FacetFilter::from_str("[\"hello:12\", [\"hello:13\", 14]]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap_err();
+        assert!(error.to_string().contains("(element 1 of clause 1)"), "{}", error);
+
+        let error = FacetFilter::from_str("[[\"hello:12\"], \"foo:13\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap_err();
+        assert!(error.to_string().contains("(clause 1)"), "{}", error);
+
+        let error = FacetFilter::from_str("[\"hello:12\", 13]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).unwrap_err();
+        assert!(error.to_string().contains("(clause 1)"), "{}", error);
+    }
+
+    #[test]
+    fn test_lenient_facet_filter() {
+        let mut schema = Schema::new();
+        let _id = schema.insert_and_index("hello").unwrap();
+        let _id = schema.insert_and_index("world").unwrap();
+        let facet_list = [schema.id("hello").unwrap()];
+
+        let (filter, dropped) = FacetFilter::from_str_lenient(
+            "[\"hello:12\", \"foo:13\", [\"hello:14\", \"bar:15\"], [\"baz:16\"]]",
+            &schema,
+            &facet_list,
+            &DEFAULT_FACET_NORMALIZATION,
+        ).unwrap();
+        assert_eq!(
+            filter,
+            FacetFilter(vec![
+                Either::Right(FacetKey::new(schema.id("hello").unwrap(), "12".to_string())),
+                Either::Left(vec![FacetKey::new(schema.id("hello").unwrap(), "14".to_string())]),
+            ])
+        );
+        let dropped: Vec<_> = dropped.iter().map(ToString::to_string).collect();
+        assert_eq!(dropped, vec![
+            "unknown \"foo\" attribute (clause 1)",
+            "unknown \"bar\" attribute (element 1 of clause 2)",
+            "unknown \"baz\" attribute (element 0 of clause 3)",
+        ]);
 
-        // empty array error
-        assert!(FacetFilter::from_str("[]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"hello:12\", []]", &schema, &facet_list).is_err());
+        // the attributes that are not faceted are still rejected
+        assert!(FacetFilter::from_str_lenient("[\"world:12\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
+        // the strict mode rejects the unknown attributes
+        assert!(FacetFilter::from_str("[\"hello:12\", \"foo:13\"]", &schema, &facet_list, &DEFAULT_FACET_NORMALIZATION).is_err());
     }
 }
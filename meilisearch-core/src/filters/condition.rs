@@ -2,13 +2,15 @@ use std::str::FromStr;
 use std::cmp::Ordering;
 
 use crate::error::Error;
-use crate::{store::Index, DocumentId, MainT};
+use crate::update::value_to_number;
+use crate::{store::Index, DocumentId, MainT, RankedMap};
 use heed::RoTxn;
 use meilisearch_schema::{FieldId, Schema};
 use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::Pair;
 use serde_json::{Value, Number};
 use super::parser::Rule;
+use super::resolve_field;
 
 #[derive(Debug, PartialEq)]
 enum ConditionType {
@@ -66,25 +68,50 @@ pub struct Condition<'a> {
     value: ConditionValue<'a>
 }
 
+fn field_id(schema: &Schema, key: &Pair<Rule>) -> Result<FieldId, Error> {
+    resolve_field(schema, key.as_str(), key.as_span())
+}
+
+/// returns the attribute of a rule that only has a key, like `exists`
+pub fn get_field(schema: &Schema, pair: Pair<Rule>) -> Result<FieldId, Error> {
+    // lexing ensures that we at least have a key
+    let key = pair.into_inner().next().unwrap();
+    field_id(schema, &key)
+}
+
 fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, ConditionValue<'a>), Error> {
     let mut items = pair.into_inner();
     // lexing ensures that we at least have a key
     let key = items.next().unwrap();
-    let field = schema
-        .id(key.as_str())
-        .ok_or_else(|| PestError::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!(
-                                 "attribute `{}` not found, available attributes are: {}",
-                                 key.as_str(),
-                                 schema.names().collect::<Vec<_>>().join(", ")
-                             ),
-                },
-                key.as_span()))?;
+    let field = field_id(schema, &key)?;
     let value = ConditionValue::new(&items.next().unwrap());
     Ok((field, value))
 }
 
+/// returns the attribute and the value of an ordering comparison, the value must be a number.
+fn get_field_number<'a>(schema: &Schema, pair: Pair<'a, Rule>, operator: &str) -> Result<(FieldId, ConditionValue<'a>), Error> {
+    let mut items = pair.into_inner();
+    // lexing ensures that we at least have a key and a value
+    let key = items.next().unwrap();
+    let field = field_id(schema, &key)?;
+    let value_pair = items.next().unwrap();
+    let value = ConditionValue::new(&value_pair);
+    if value.as_number().is_none() {
+        let error = PestError::new_from_span(
+            ErrorVariant::CustomError {
+                message: format!(
+                    "the `{}` operator only compares numbers, `{}` is not a number",
+                    operator,
+                    value.as_str(),
+                ),
+            },
+            value_pair.as_span(),
+        );
+        return Err(error.into());
+    }
+    Ok((field, value))
+}
+
 // undefined behavior with big numbers
 fn compare_numbers(lhs: &Number, rhs: &Number) -> Option<Ordering> {
     match (lhs.as_i64(), lhs.as_u64(), lhs.as_f64(),
@@ -102,7 +129,7 @@ impl<'a> Condition<'a> {
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
+        let (field, value) = get_field_number(schema, item, "<")?;
         let condition = ConditionType::Less;
         Ok(Self { field, condition, value })
     }
@@ -111,7 +138,7 @@ impl<'a> Condition<'a> {
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
+        let (field, value) = get_field_number(schema, item, ">")?;
         let condition = ConditionType::Greater;
         Ok(Self { field, condition, value })
     }
@@ -129,7 +156,7 @@ impl<'a> Condition<'a> {
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
+        let (field, value) = get_field_number(schema, item, ">=")?;
         let condition = ConditionType::GreaterEqual;
         Ok(Self { field, condition, value })
     }
@@ -138,7 +165,7 @@ impl<'a> Condition<'a> {
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
+        let (field, value) = get_field_number(schema, item, "<=")?;
         let condition = ConditionType::LessEqual;
         Ok(Self { field, condition, value })
     }
@@ -152,20 +179,61 @@ impl<'a> Condition<'a> {
         Ok(Self { field, condition, value })
     }
 
+    /// returns an equality condition for every value of an `IN` list
+    pub fn in_list(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Vec<Self>, Error> {
+        let mut items = item.into_inner();
+        // lexing ensures that we at least have a key and a value
+        let key = items.next().unwrap();
+        let field = field_id(schema, &key)?;
+        let conditions = items
+            .map(|value| {
+                let value = ConditionValue::new(&value);
+                Self { field, condition: ConditionType::Equal, value }
+            })
+            .collect();
+        Ok(conditions)
+    }
+
     pub fn test(
         &self,
         reader: &RoTxn<MainT>,
         index: &Index,
+        ranked_map: &RankedMap,
         document_id: DocumentId,
     ) -> Result<bool, Error> {
+        // the ordering comparisons on the ranked attributes are evaluated
+        // on the ranked map, without reading the document
+        if self.is_ordering() {
+            if let Some(number) = ranked_map.get(document_id, self.field).and_then(|n| n.as_f64()) {
+                return Ok(self.match_number(number));
+            }
+        }
+
         match index.document_attribute::<Value>(reader, document_id, self.field)? {
             Some(Value::Array(values)) => Ok(values.iter().any(|v| self.match_value(Some(v)))),
             other => Ok(self.match_value(other.as_ref())),
         }
     }
 
+    fn match_number(&self, number: f64) -> bool {
+        let value = self.value.as_number().and_then(Number::as_f64);
+        value
+            .and_then(|value| number.partial_cmp(&value))
+            .map_or(false, |ord| self.match_ordering(ord))
+    }
+
     fn match_value(&self, value: Option<&Value>) -> bool {
         match value {
+            // the strings and booleans are coerced like the values of the ranked map are,
+            // the comparisons match the same documents whether the attribute is ranked or not
+            Some(value @ Value::String(_)) | Some(value @ Value::Bool(_)) if self.is_ordering() => {
+                value_to_number(value)
+                    .and_then(|n| n.as_f64())
+                    .map_or(false, |number| self.match_number(number))
+            },
             Some(Value::String(s)) => {
                 let value = self.value.as_str();
                 match self.condition {
@@ -177,15 +245,7 @@ impl<'a> Condition<'a> {
             Some(Value::Number(n)) => { 
                 if let Some(value) = self.value.as_number() {
                     if let Some(ord) = compare_numbers(&n, value) {
-                        let res =  match self.condition {
-                            ConditionType::Equal => ord == Ordering::Equal,
-                            ConditionType::NotEqual => ord != Ordering::Equal,
-                            ConditionType::GreaterEqual => ord != Ordering::Less,
-                            ConditionType::LessEqual => ord != Ordering::Greater,
-                            ConditionType::Greater => ord == Ordering::Greater,
-                            ConditionType::Less => ord == Ordering::Less,
-                        };
-                        return res
+                        return self.match_ordering(ord)
                     } 
                 } 
                 false
@@ -206,6 +266,26 @@ impl<'a> Condition<'a> {
             _ => self.condition == ConditionType::NotEqual,
         }
     }
+
+    fn is_ordering(&self) -> bool {
+        match self.condition {
+            ConditionType::Greater | ConditionType::Less => true,
+            ConditionType::GreaterEqual | ConditionType::LessEqual => true,
+            ConditionType::Equal | ConditionType::NotEqual => false,
+        }
+    }
+
+    /// returns whether the ordering of the document value compared to the condition value is accepted
+    fn match_ordering(&self, ord: Ordering) -> bool {
+        match self.condition {
+            ConditionType::Equal => ord == Ordering::Equal,
+            ConditionType::NotEqual => ord != Ordering::Equal,
+            ConditionType::GreaterEqual => ord != Ordering::Less,
+            ConditionType::LessEqual => ord != Ordering::Greater,
+            ConditionType::Greater => ord == Ordering::Greater,
+            ConditionType::Less => ord == Ordering::Less,
+        }
+    }
 }
 
 #[cfg(test)]
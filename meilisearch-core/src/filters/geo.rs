@@ -0,0 +1,186 @@
+use crate::criterion::haversine_distance;
+use crate::error::Error;
+use crate::{store::Index, DocumentId, MainT};
+use heed::RoTxn;
+use meilisearch_schema::{FieldId, Schema};
+use pest::error::{Error as PestError, ErrorVariant};
+use pest::iterators::Pair;
+use serde_json::Value;
+use super::parser::Rule;
+use super::resolve_field;
+
+/// The attribute holding the coordinates of a document, in the form `{ "lat": 45.4, "lng": 9.1 }`.
+pub const GEO_ATTRIBUTE: &str = "_geo";
+
+/// A rectangle delimited by its top left and bottom right corners. When the left longitude
+/// is greater than the right one, the rectangle crosses the antimeridian.
+#[derive(Debug)]
+pub struct GeoBoundingBox {
+    field: FieldId,
+    top: f64,
+    left: f64,
+    bottom: f64,
+    right: f64,
+}
+
+fn custom_error(message: String, pair: &Pair<Rule>) -> Error {
+    PestError::new_from_span(ErrorVariant::CustomError { message }, pair.as_span()).into()
+}
+
+impl GeoBoundingBox {
+    pub fn new(item: Pair<Rule>, schema: &Schema) -> Result<Self, Error> {
+        let field = resolve_field(schema, GEO_ATTRIBUTE, item.as_span())?;
+
+        // lexing ensures that we have exactly four coordinates
+        let mut coordinates = Vec::with_capacity(4);
+        for pair in item.clone().into_inner() {
+            match pair.as_str().parse::<f64>() {
+                Ok(coordinate) => coordinates.push(coordinate),
+                Err(e) => return Err(custom_error(format!("invalid coordinate: {}", e), &pair)),
+            }
+        }
+        let (top, left, bottom, right) = (coordinates[0], coordinates[1], coordinates[2], coordinates[3]);
+
+        if [top, bottom].iter().any(|lat| !(-90.0..=90.0).contains(lat)) {
+            return Err(custom_error("latitudes must be between -90 and 90".to_string(), &item));
+        }
+        if [left, right].iter().any(|lng| !(-180.0..=180.0).contains(lng)) {
+            return Err(custom_error("longitudes must be between -180 and 180".to_string(), &item));
+        }
+        if top < bottom {
+            return Err(custom_error("the top latitude must be greater than the bottom one".to_string(), &item));
+        }
+
+        Ok(Self { field, top, left, bottom, right })
+    }
+
+    pub fn test(
+        &self,
+        reader: &RoTxn<MainT>,
+        index: &Index,
+        document_id: DocumentId,
+    ) -> Result<bool, Error> {
+        match index.document_attribute::<Value>(reader, document_id, self.field)? {
+            Some(value) => match geo_point(&value) {
+                Some((lat, lng)) => Ok(self.contains(lat, lng)),
+                None => Ok(false),
+            },
+            None => Ok(false),
+        }
+    }
+
+    fn contains(&self, lat: f64, lng: f64) -> bool {
+        let lat_match = self.bottom <= lat && lat <= self.top;
+        let lng_match = if self.left <= self.right {
+            self.left <= lng && lng <= self.right
+        } else {
+            // the box crosses the antimeridian
+            self.left <= lng || lng <= self.right
+        };
+        lat_match && lng_match
+    }
+}
+
+/// A circle delimited by its center and its radius in meters.
+#[derive(Debug)]
+pub struct GeoRadius {
+    field: FieldId,
+    center: (f64, f64),
+    radius: f64,
+}
+
+impl GeoRadius {
+    pub fn new(item: Pair<Rule>, schema: &Schema) -> Result<Self, Error> {
+        let field = resolve_field(schema, GEO_ATTRIBUTE, item.as_span())?;
+
+        // lexing ensures that we have exactly three numbers
+        let mut numbers = Vec::with_capacity(3);
+        for pair in item.clone().into_inner() {
+            match pair.as_str().parse::<f64>() {
+                Ok(number) => numbers.push(number),
+                Err(e) => return Err(custom_error(format!("invalid coordinate: {}", e), &pair)),
+            }
+        }
+        let (lat, lng, radius) = (numbers[0], numbers[1], numbers[2]);
+
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(custom_error("latitudes must be between -90 and 90".to_string(), &item));
+        }
+        if !(-180.0..=180.0).contains(&lng) {
+            return Err(custom_error("longitudes must be between -180 and 180".to_string(), &item));
+        }
+        if radius < 0.0 {
+            return Err(custom_error("the radius must be positive".to_string(), &item));
+        }
+
+        Ok(Self { field, center: (lat, lng), radius })
+    }
+
+    /// returns the point the distances are computed from.
+    pub fn center(&self) -> (f64, f64) {
+        self.center
+    }
+
+    pub fn test(
+        &self,
+        reader: &RoTxn<MainT>,
+        index: &Index,
+        document_id: DocumentId,
+    ) -> Result<bool, Error> {
+        match index.document_attribute::<Value>(reader, document_id, self.field)? {
+            Some(value) => match geo_point(&value) {
+                Some(point) => Ok(haversine_distance(self.center, point) <= self.radius),
+                None => Ok(false),
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+/// returns the latitude and the longitude of a `_geo` attribute value.
+pub fn geo_point(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::Object(point) => {
+            let lat = point.get("lat").and_then(coordinate)?;
+            let lng = point.get("lng").and_then(coordinate)?;
+            Some((lat, lng))
+        }
+        _ => None,
+    }
+}
+
+/// coordinates can either be given as numbers or as strings
+fn coordinate(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bounding_box(top: f64, left: f64, bottom: f64, right: f64) -> GeoBoundingBox {
+        GeoBoundingBox { field: FieldId(0), top, left, bottom, right }
+    }
+
+    #[test]
+    fn contains_points() {
+        // around Paris
+        let paris = bounding_box(49.0, 2.0, 48.5, 2.7);
+        assert!(paris.contains(48.85, 2.35));
+        assert!(!paris.contains(45.76, 4.83));
+        assert!(!paris.contains(48.85, -2.35));
+
+        // around Fiji, crossing the antimeridian
+        let fiji = bounding_box(-12.0, 176.0, -21.0, -178.0);
+        assert!(fiji.contains(-17.7, 178.0));
+        assert!(fiji.contains(-16.5, -179.9));
+        assert!(fiji.contains(-18.0, 180.0));
+        assert!(!fiji.contains(-17.7, 170.0));
+        assert!(!fiji.contains(-17.7, -170.0));
+        assert!(!fiji.contains(-30.0, 178.0));
+    }
+}
@@ -1,51 +1,86 @@
 mod parser;
 mod condition;
+mod geo;
 
 pub(crate) use parser::Rule;
+pub use geo::{geo_point, GEO_ATTRIBUTE};
 
 use std::ops::Not;
 
-use condition::Condition;
+use condition::{get_field, Condition};
+use geo::{GeoBoundingBox, GeoRadius};
 use crate::error::Error;
-use crate::{DocumentId, MainT, store::Index};
+use crate::{DocumentId, MainT, RankedMap, store::Index};
 use heed::RoTxn;
-use meilisearch_schema::Schema;
+use meilisearch_schema::{FieldId, Schema};
 use parser::{PREC_CLIMBER, FilterParser};
+use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::{Pair, Pairs};
-use pest::Parser;
+use pest::{Parser, Span};
+use sdset::Set;
 
 type FilterResult<'a> = Result<Filter<'a>, Error>;
 
 #[derive(Debug)]
 pub enum Filter<'a> {
     Condition(Condition<'a>),
+    Exists(FieldId),
+    GeoBoundingBox(GeoBoundingBox),
+    GeoRadius(GeoRadius),
     Or(Box<Self>, Box<Self>),
     And(Box<Self>, Box<Self>),
     Not(Box<Self>),
 }
 
 impl<'a> Filter<'a> {
-    pub fn parse(expr: &'a str, schema: &'a Schema) -> FilterResult<'a> {
+    /// parses a filter expression, when `filterable` is given the
+    /// filter can only use the attributes it contains.
+    pub fn parse(
+        expr: &'a str,
+        schema: &'a Schema,
+        filterable: Option<&Set<FieldId>>,
+    ) -> FilterResult<'a> {
         let mut lexed = FilterParser::parse(Rule::prgm, expr)?;
+        if let Some(filterable) = filterable {
+            check_filterable(lexed.clone(), schema, filterable)?;
+        }
         Self::build(lexed.next().unwrap().into_inner(), schema)
     }
 
+    /// tests the filter on a document, the numeric attributes found in
+    /// the ranked map are compared without reading the document.
     pub fn test(
         &self,
         reader: &RoTxn<MainT>,
         index: &Index,
+        ranked_map: &RankedMap,
         document_id: DocumentId,
     ) -> Result<bool, Error> {
         use Filter::*;
         match self {
-            Condition(c) => c.test(reader, index, document_id),
+            Condition(c) => c.test(reader, index, ranked_map, document_id),
+            Exists(field) => Ok(index.document_attribute_bytes(reader, document_id, *field)?.is_some()),
+            GeoBoundingBox(bounding_box) => bounding_box.test(reader, index, document_id),
+            GeoRadius(radius) => radius.test(reader, index, document_id),
             Or(lhs, rhs) => Ok(
-                lhs.test(reader, index, document_id)? || rhs.test(reader, index, document_id)?
+                lhs.test(reader, index, ranked_map, document_id)?
+                    || rhs.test(reader, index, ranked_map, document_id)?
             ),
             And(lhs, rhs) => Ok(
-                lhs.test(reader, index, document_id)? && rhs.test(reader, index, document_id)?
+                lhs.test(reader, index, ranked_map, document_id)?
+                    && rhs.test(reader, index, ranked_map, document_id)?
             ),
-            Not(op) => op.test(reader, index, document_id).map(bool::not),
+            Not(op) => op.test(reader, index, ranked_map, document_id).map(bool::not),
+        }
+    }
+
+    /// returns the center of the first `_geoRadius` condition the documents must match,
+    /// the ones that are negated are ignored.
+    pub fn geo_radius_center(&self) -> Option<(f64, f64)> {
+        match self {
+            Filter::GeoRadius(radius) => Some(radius.center()),
+            Filter::And(lhs, rhs) => lhs.geo_radius_center().or_else(|| rhs.geo_radius_center()),
+            _ => None,
         }
     }
 
@@ -59,6 +94,22 @@ impl<'a> Filter<'a> {
                 Rule::neq => Ok(Filter::Condition(Condition::neq(pair, schema)?)),
                 Rule::geq => Ok(Filter::Condition(Condition::geq(pair, schema)?)),
                 Rule::leq => Ok(Filter::Condition(Condition::leq(pair, schema)?)),
+                Rule::exists => Ok(Filter::Exists(get_field(schema, pair)?)),
+                Rule::not_exists => Ok(Filter::Not(Box::new(Filter::Exists(get_field(schema, pair)?)))),
+                Rule::geo_bounding_box => Ok(Filter::GeoBoundingBox(GeoBoundingBox::new(pair, schema)?)),
+                Rule::geo_radius => Ok(Filter::GeoRadius(GeoRadius::new(pair, schema)?)),
+                Rule::in_list => {
+                    // `field IN [a, b]` is evaluated as `field = a OR field = b`
+                    let filter = Condition::in_list(pair, schema)?
+                        .into_iter()
+                        .map(Filter::Condition)
+                        .fold(None, |acc, rhs| match acc {
+                            Some(lhs) => Some(Filter::Or(Box::new(lhs), Box::new(rhs))),
+                            None => Some(rhs),
+                        });
+                    // lexing ensures that the list is never empty
+                    Ok(filter.unwrap())
+                },
                 Rule::prgm => Self::build(pair.into_inner(), schema),
                 Rule::term => Self::build(pair.into_inner(), schema),
                 Rule::not => Ok(Filter::Not(Box::new(Self::build(
@@ -76,6 +127,64 @@ impl<'a> Filter<'a> {
     }
 }
 
+/// returns the id of an attribute used by the filter, every form of condition reports
+/// an unknown attribute with the same error, located at the given span.
+fn resolve_field(schema: &Schema, name: &str, span: Span) -> Result<FieldId, Error> {
+    schema.id(name).ok_or_else(|| {
+        let error = PestError::new_from_span(
+            ErrorVariant::CustomError {
+                message: format!(
+                    "attribute `{}` not found, available attributes are: {}",
+                    name,
+                    schema.names().collect::<Vec<_>>().join(", "),
+                ),
+            },
+            span,
+        );
+        error.into()
+    })
+}
+
+/// ensures that every attribute used by the filter is filterable, unknown
+/// attributes are reported later on, when the filter is built.
+fn check_filterable(pairs: Pairs<Rule>, schema: &Schema, filterable: &Set<FieldId>) -> Result<(), Error> {
+    for pair in pairs.flatten() {
+        match pair.as_rule() {
+            Rule::eq | Rule::greater | Rule::less | Rule::neq | Rule::geq | Rule::leq
+            | Rule::exists | Rule::not_exists | Rule::in_list | Rule::geo_bounding_box | Rule::geo_radius => {
+                let key = match pair.as_rule() {
+                    Rule::geo_bounding_box | Rule::geo_radius => pair,
+                    // lexing ensures that we at least have a key
+                    _ => pair.into_inner().next().unwrap(),
+                };
+                let name = match key.as_rule() {
+                    Rule::geo_bounding_box | Rule::geo_radius => GEO_ATTRIBUTE,
+                    _ => key.as_str(),
+                };
+                match schema.id(name) {
+                    Some(field) if !filterable.contains(&field) => {
+                        let filterable_names: Vec<_> = filterable.iter().filter_map(|&id| schema.name(id)).collect();
+                        let error = PestError::new_from_span(
+                            ErrorVariant::CustomError {
+                                message: format!(
+                                    "attribute `{}` is not filterable, filterable attributes are: {}",
+                                    name,
+                                    filterable_names.join(", "),
+                                ),
+                            },
+                            key.as_span(),
+                        );
+                        return Err(error.into());
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -97,6 +206,25 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, "hello world=1").is_err());
         assert!(FilterParser::parse(Rule::prgm, "").is_err());
         assert!(FilterParser::parse(Rule::prgm, r#"((((((hello=world)))))"#).is_err());
+        assert!(FilterParser::parse(Rule::prgm, "fieldEXISTS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field EXISTS 10").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "EXISTS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field EXISTS NOT").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN []").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN [1, ]").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN 1, 2").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN [1 2]").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "IN [1, 2]").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoBoundingBox([1, 2])").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoBoundingBox([1, 2], [3])").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoBoundingBox([1, a], [3, 4])").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoBoundingBox(1, 2, 3, 4)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoRadius(1, 2)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoRadius([1, 2], 3)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "(a=1 OR (b=2 AND c=3)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "(a=1 OR b=2) AND (c=3))").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "(a=1 OR b=2) (c=3)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "() AND a=1").is_err());
     }
 
     #[test]
@@ -123,5 +251,106 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' <= 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' != 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"image EXISTS"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"image NOT EXISTS"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT image EXISTS"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' EXISTS AND bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"field IN [1]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"field IN [1, 2, 3]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"field IN ["hello world", 'foo', bar]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT field IN [a,b] AND bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoBoundingBox([45.5, 9], [45.4, 9.3])"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoBoundingBox([-12,176],[-21,-178]) AND bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT _geoBoundingBox([+45.5, 9.0], [45.4, 9.3])"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoRadius(45.47, 9.18, 2000)"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"(a=1 OR b=2) AND c=3"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"((a=1 OR b=2) AND (c=3 OR (d=4 AND NOT e=5)))"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"NOT (a=1 OR (b=2 AND c IN [3, 4]))"#).is_ok());
+    }
+
+    /// renders a filter made of `EXISTS` conditions with every operation parenthesized
+    fn render(filter: &Filter, schema: &Schema) -> String {
+        match filter {
+            Filter::Exists(field) => schema.name(*field).unwrap().to_string(),
+            Filter::Or(lhs, rhs) => format!("({} OR {})", render(lhs, schema), render(rhs, schema)),
+            Filter::And(lhs, rhs) => format!("({} AND {})", render(lhs, schema), render(rhs, schema)),
+            Filter::Not(op) => format!("NOT {}", render(op, schema)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn parenthesized_precedence() {
+        let mut schema = Schema::new();
+        for name in &["a", "b", "c", "d"] {
+            schema.insert_and_index(name).unwrap();
+        }
+
+        let filters = [
+            // AND binds tighter than OR
+            ("a EXISTS OR b EXISTS AND c EXISTS", "(a OR (b AND c))"),
+            ("a EXISTS AND b EXISTS OR c EXISTS", "((a AND b) OR c)"),
+            ("(a EXISTS OR b EXISTS) AND c EXISTS", "((a OR b) AND c)"),
+            ("a EXISTS AND (b EXISTS OR c EXISTS)", "(a AND (b OR c))"),
+            ("a EXISTS OR b EXISTS OR c EXISTS", "((a OR b) OR c)"),
+            ("((a EXISTS))", "a"),
+            ("NOT (a EXISTS OR b EXISTS) AND c EXISTS", "(NOT (a OR b) AND c)"),
+            ("NOT a EXISTS OR b EXISTS", "(NOT a OR b)"),
+            (
+                "(a EXISTS OR (b EXISTS AND (c EXISTS OR d EXISTS))) AND NOT (a EXISTS AND d EXISTS)",
+                "((a OR (b AND (c OR d))) AND NOT (a AND d))",
+            ),
+        ];
+        for (filter, expected) in &filters {
+            let parsed = Filter::parse(filter, &schema, None).unwrap();
+            assert_eq!(&render(&parsed, &schema), expected, "{}", filter);
+        }
+    }
+
+    #[test]
+    fn unknown_attribute() {
+        let mut schema = Schema::new();
+        schema.insert_and_index("field").unwrap();
+
+        let filters = [
+            ("unknown = 1", "unknown"),
+            ("unknown != 1", "unknown"),
+            ("unknown > 1", "unknown"),
+            ("unknown <= 1", "unknown"),
+            ("NOT unknown = 1", "unknown"),
+            ("unknown EXISTS", "unknown"),
+            ("unknown NOT EXISTS", "unknown"),
+            ("unknown IN [1, 2]", "unknown"),
+            ("field = 1 AND unknown = 1", "unknown"),
+            ("_geoBoundingBox([45.5, 9], [45.4, 9.3])", "_geo"),
+            ("_geoRadius(45.47, 9.18, 2000)", "_geo"),
+        ];
+        for (filter, name) in &filters {
+            let error = Filter::parse(filter, &schema, None).unwrap_err().to_string();
+            let expected = format!("attribute `{}` not found, available attributes are: field", name);
+            assert!(error.contains(&expected), "{}: {}", filter, error);
+        }
+    }
+
+    #[test]
+    fn ordering_comparison_on_a_string() {
+        let mut schema = Schema::new();
+        schema.insert_and_index("price").unwrap();
+
+        for filter in &["price > 10", "price >= 10.5", "price < -3", "price <= 0"] {
+            assert!(Filter::parse(filter, &schema, None).is_ok(), "{}", filter);
+        }
+
+        let filters = [
+            ("price > cheap", ">", "cheap"),
+            ("price >= 'ten'", ">=", "ten"),
+            ("price < 10a", "<", "10a"),
+            ("price <= \"\"", "<=", ""),
+        ];
+        for (filter, operator, value) in &filters {
+            let error = Filter::parse(filter, &schema, None).unwrap_err().to_string();
+            let expected = format!("the `{}` operator only compares numbers, `{}` is not a number", operator, value);
+            assert!(error.contains(&expected), "{}: {}", filter, error);
+        }
     }
 }
@@ -28,10 +28,12 @@ pub mod settings;
 pub mod store;
 pub mod update;
 
+pub use self::bucket_sort::PlaceholderSortRule;
 pub use self::database::{BoxUpdateFn, Database, DatabaseOptions, MainT, UpdateT, MainWriter, MainReader, UpdateWriter, UpdateReader};
 pub use self::error::{Error, HeedError, FstError, MResult, pest_error, FacetError};
-pub use self::filters::Filter;
+pub use self::filters::{Filter, geo_point, GEO_ATTRIBUTE};
 pub use self::number::{Number, ParseNumberError};
+pub use self::query_builder::QueryExplanation;
 pub use self::ranked_map::RankedMap;
 pub use self::raw_document::RawDocument;
 pub use self::store::Index;
@@ -54,10 +56,17 @@ use crate::reordered_attrs::ReorderedAttrs;
 type FstSetCow<'a> = fst::Set<Cow<'a, [u8]>>;
 type FstMapCow<'a> = fst::Map<Cow<'a, [u8]>>;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Document {
     pub id: DocumentId,
     pub highlights: Vec<Highlight>,
+    /// The relevance of the document for the query, between 0.0 and 1.0
+    pub score: f64,
+    /// Whether some query words only match the document once corrected by typos
+    pub typo_corrected: bool,
+    /// The number of typos of the best match of every query word, in the order of the query,
+    /// nothing for the words the document does not contain
+    pub query_typos: Vec<Option<u8>>,
 
     #[cfg(test)]
     pub matches: Vec<crate::bucket_sort::SimpleMatch>,
@@ -120,12 +129,12 @@ fn highlights_from_raw_document<'a, 'tag, 'txn>(
 impl Document {
     #[cfg(not(test))]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned() }
+        Document { id, highlights: highlights.to_owned(), score: 1.0, typo_corrected: false, query_typos: Vec::new() }
     }
 
     #[cfg(test)]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned(), matches: Vec::new() }
+        Document { id, highlights: highlights.to_owned(), score: 1.0, typo_corrected: false, query_typos: Vec::new(), matches: Vec::new() }
     }
 
     #[cfg(not(test))]
@@ -145,7 +154,7 @@ impl Document {
             schema,
         );
 
-        Document { id: raw_document.id, highlights }
+        Document { id: raw_document.id, highlights, score: 1.0, typo_corrected: false, query_typos: Vec::new() }
     }
 
     #[cfg(test)]
@@ -186,7 +195,7 @@ impl Document {
         }
         matches.sort_unstable();
 
-        Document { id: raw_document.id, highlights, matches }
+        Document { id: raw_document.id, highlights, score: 1.0, typo_corrected: false, query_typos: Vec::new(), matches }
     }
 }
 
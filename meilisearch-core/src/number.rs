@@ -47,6 +47,18 @@ impl FromStr for Number {
     }
 }
 
+impl Number {
+    /// returns the value as a float, or nothing when it is null.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Number::Unsigned(n) => Some(n as f64),
+            Number::Signed(n) => Some(n as f64),
+            Number::Float(n) => Some(n.into_inner()),
+            Number::Null => None,
+        }
+    }
+}
+
 impl PartialEq for Number {
     fn eq(&self, other: &Number) -> bool {
         self.cmp(other) == Ordering::Equal
@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ops::{Deref, Range};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 
 use either::Either;
@@ -8,23 +10,81 @@ use sdset::{SetOperation, SetBuf, Set};
 
 use meilisearch_schema::FieldId;
 
-use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, SortResult, placeholder_document_sort, facet_count};
+use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, check_cancelled, SortResult, placeholder_document_sort, rules_document_sort, PlaceholderSortRule, facet_count, sticky_facet_count, pivot_count, exhaustive_nb_hits_count};
 use crate::database::MainT;
-use crate::facets::FacetFilter;
+use crate::facets::{facet_stats, FacetFilter};
+use crate::query_tree::{create_query_tree, normalized_query, suggested_query, words_candidates, Context as QTContext};
+use crate::settings::Locale;
 use crate::distinct_map::{DistinctMap, BufferedDistinctMap};
 use crate::Document;
-use crate::{criterion::Criteria, DocumentId};
+use crate::{criterion::{Criteria, GeoDistances}, DocumentId};
 use crate::{reordered_attrs::ReorderedAttrs, store, MResult, MainReader};
 
+/// The number of hits under which a query finds too few documents, a corrected
+/// query is only suggested below it.
+const SUGGESTED_QUERY_MAX_NB_HITS: usize = 10;
+
+/// Builds the context the query trees are created in, the stop words of the
+/// index are kept in the queries when they must not be removed.
+fn query_tree_context<'a>(
+    reader: &'a MainReader,
+    index: &store::Index,
+    locale: Option<&'a Locale>,
+    remove_stop_words: bool,
+) -> MResult<QTContext<'a>> {
+    let stop_words = if remove_stop_words {
+        index.main.stop_words_fst(reader)?
+    } else {
+        fst::Set::default().map_data(Cow::Owned).unwrap()
+    };
+
+    Ok(QTContext {
+        words_set: index.main.words_fst(reader)?,
+        stop_words,
+        synonyms: index.synonyms,
+        postings_lists: index.postings_lists,
+        prefix_postings_lists: index.prefix_postings_lists_cache,
+        locale,
+    })
+}
+
+/// The estimated cost of a search, computed without ranking nor filtering the documents.
+#[derive(Debug, Clone)]
+pub struct QueryExplanation {
+    /// the names of the criteria ranking the documents, in order
+    pub criteria: Vec<String>,
+    /// the number of documents containing each word of the query or one of its alternatives,
+    /// the documents to rank are the union of these documents
+    pub words_candidates: Vec<(String, usize)>,
+    /// the number of documents matching the facet filter
+    pub facet_candidates: Option<usize>,
+    /// the number of documents to rank, before the filter is applied
+    pub nb_candidates: usize,
+}
+
 pub struct QueryBuilder<'c, 'f, 'd, 'i> {
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
     filter: Option<Box<dyn Fn(DocumentId) -> bool + 'f>>,
     distinct: Option<(Box<dyn Fn(DocumentId) -> Option<u64> + 'd>, usize)>,
+    distinct_scan_limit: Option<usize>,
     timeout: Option<Duration>,
     index: &'i store::Index,
     facet_filter: Option<FacetFilter>,
     facets: Option<Vec<(FieldId, String)>>,
+    facets_pivot: Option<(FieldId, FieldId)>,
+    max_facet_values: Option<usize>,
+    facet_stats: Option<Vec<(FieldId, String)>>,
+    ranking_score_threshold: Option<f64>,
+    placeholder_sort: Option<Vec<PlaceholderSortRule>>,
+    geo_distances: Option<GeoDistances>,
+    sort_missing_first: Option<bool>,
+    locale: Option<Locale>,
+    remove_stop_words: bool,
+    suggest_query: bool,
+    sticky_facets: bool,
+    exhaustive_nb_hits: bool,
+    cancelled: Option<Arc<AtomicBool>>,
 }
 
 impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
@@ -42,16 +102,149 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.facets = facets;
     }
 
+    /// sets the two facet attributes for which to return the count of each values combination
+    pub fn set_facets_pivot(&mut self, facets_pivot: Option<(FieldId, FieldId)>) {
+        self.facets_pivot = facets_pivot;
+    }
+
+    /// sets the maximum number of values counted for each facet, the most frequent are kept
+    pub fn set_max_facet_values(&mut self, max_facet_values: Option<usize>) {
+        self.max_facet_values = max_facet_values;
+    }
+
+    /// sets the attributes for which to return the min, max and average numeric values
+    pub fn set_facet_stats(&mut self, facet_stats: Option<Vec<(FieldId, String)>>) {
+        self.facet_stats = facet_stats;
+    }
+
+    /// sets the relevance score under which the documents are not returned nor counted as hits
+    pub fn set_ranking_score_threshold(&mut self, threshold: Option<f64>) {
+        self.ranking_score_threshold = threshold;
+    }
+
+    /// sets the rules sorting the documents of a placeholder search, the ranking rules
+    /// are used by default.
+    pub fn set_placeholder_sort(&mut self, rules: Option<Vec<PlaceholderSortRule>>) {
+        self.placeholder_sort = rules;
+    }
+
+    /// sets where the distances computed by the first geo rule of the placeholder sort are kept.
+    pub fn set_geo_distances(&mut self, geo_distances: Option<GeoDistances>) {
+        self.geo_distances = geo_distances;
+    }
+
+    /// sets whether the documents without value for the placeholder sort fields are placed first
+    /// or last, they are placed last by default.
+    pub fn set_sort_missing_first(&mut self, sort_missing_first: Option<bool>) {
+        self.sort_missing_first = sort_missing_first;
+    }
+
+    /// sets whether the distribution of a facet must ignore the facet filters on this same facet
+    pub fn set_sticky_facets(&mut self, sticky_facets: bool) {
+        self.sticky_facets = sticky_facets;
+    }
+
+    /// sets whether the number of hits must account for the filter and the distinct rule
+    pub fn set_exhaustive_nb_hits(&mut self, exhaustive_nb_hits: bool) {
+        self.exhaustive_nb_hits = exhaustive_nb_hits;
+    }
+
+    /// sets the flag checked while ranking the documents, the search
+    /// stops and returns an error once it has been raised
+    pub fn set_cancellation_flag(&mut self, cancelled: Option<Arc<AtomicBool>>) {
+        self.cancelled = cancelled;
+    }
+
+    /// sets the language hint used to tokenize the query
+    pub fn set_locale(&mut self, locale: Option<Locale>) {
+        self.locale = locale;
+    }
+
+    /// sets whether the stop words of the index are removed from the query
+    pub fn set_remove_stop_words(&mut self, remove_stop_words: bool) {
+        self.remove_stop_words = remove_stop_words;
+    }
+
+    /// returns the words of the query tree built for the query, without the stop words that
+    /// are removed, every word followed by its synonyms
+    pub fn normalized_query(&self, reader: &MainReader, query: &str) -> MResult<Vec<Vec<String>>> {
+        let context = self.query_tree_context(reader)?;
+        let (operation, _mapping) = create_query_tree(reader, &context, query)?;
+        Ok(normalized_query(&operation))
+    }
+
+    /// sets whether a query with its unknown words replaced by the closest known words is
+    /// returned, it is only computed when the query finds few documents
+    pub fn set_suggest_query(&mut self, suggest_query: bool) {
+        self.suggest_query = suggest_query;
+    }
+
+    /// estimates the number of documents a query has to rank, the criteria and the facet
+    /// filter are the ones of the builder
+    pub fn explain(&self, reader: &MainReader, query: Option<&str>) -> MResult<QueryExplanation> {
+        let criteria = self.criteria.as_ref().iter().map(|c| c.name().to_string()).collect();
+        let facet_docids = self.facets_docids(reader)?;
+
+        let words = match query {
+            Some(query) => {
+                let context = self.query_tree_context(reader)?;
+                let (operation, _mapping) = create_query_tree(reader, &context, query)?;
+                words_candidates(reader, &context, &operation)?
+            }
+            None => Vec::new(),
+        };
+
+        // the documents containing any of the words are ranked, the words rule drops the others
+        let query_docids = if words.is_empty() {
+            None
+        } else {
+            let sets: Vec<&Set<DocumentId>> = words.iter().map(|(_, docids)| docids.deref()).collect();
+            Some(sdset::multi::Union::new(sets).into_set_buf())
+        };
+
+        let nb_candidates = match (&facet_docids, &query_docids) {
+            (Some(facet), Some(query)) => sdset::duo::Intersection::new(facet, query).into_set_buf().len(),
+            (Some(facet), None) => facet.len(),
+            (None, Some(query)) => query.len(),
+            (None, None) => self.index.main.internal_docids(reader)?.len(),
+        };
+
+        Ok(QueryExplanation {
+            criteria,
+            words_candidates: words.into_iter().map(|(word, docids)| (word, docids.len())).collect(),
+            facet_candidates: facet_docids.map(|docids| docids.len()),
+            nb_candidates,
+        })
+    }
+
+    fn query_tree_context<'a>(&'a self, reader: &'a MainReader) -> MResult<QTContext<'a>> {
+        query_tree_context(reader, self.index, self.locale.as_ref(), self.remove_stop_words)
+    }
+
     pub fn with_criteria(index: &'i store::Index, criteria: Criteria<'c>) -> Self {
         QueryBuilder {
             criteria,
             searchable_attrs: None,
             filter: None,
             distinct: None,
+            distinct_scan_limit: None,
             timeout: None,
             index,
             facet_filter: None,
             facets: None,
+            facets_pivot: None,
+            max_facet_values: None,
+            facet_stats: None,
+            ranking_score_threshold: None,
+            placeholder_sort: None,
+            geo_distances: None,
+            sort_missing_first: None,
+            locale: None,
+            remove_stop_words: true,
+            suggest_query: false,
+            sticky_facets: false,
+            exhaustive_nb_hits: false,
+            cancelled: None,
         }
     }
 
@@ -73,6 +266,12 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.distinct = Some((Box::new(function), size))
     }
 
+    /// sets the maximum number of documents the distinct rule reads to fill the page,
+    /// the page may be incomplete and the number of hits is then not exhaustive
+    pub fn set_distinct_scan_limit(&mut self, limit: Option<usize>) {
+        self.distinct_scan_limit = limit;
+    }
+
     pub fn add_searchable_attribute(&mut self, attribute: u16) {
         let reorders = self.searchable_attrs.get_or_insert_with(ReorderedAttrs::new);
         reorders.insert_attribute(attribute);
@@ -81,11 +280,31 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
     /// returns the documents ids associated with a facet filter by computing the union and
     /// intersection of the document sets
     fn facets_docids(&self, reader: &MainReader) -> MResult<Option<SetBuf<DocumentId>>> {
+        self.facets_docids_excluding(reader, None)
+    }
+
+    /// returns the documents ids associated with a facet filter, ignoring the clauses that only
+    /// concern the `excluded` attribute.
+    fn facets_docids_excluding(
+        &self,
+        reader: &MainReader,
+        excluded: Option<FieldId>,
+    ) -> MResult<Option<SetBuf<DocumentId>>> {
         let facet_docids = match self.facet_filter {
             Some(ref facets) => {
                 let mut ands = Vec::with_capacity(facets.len());
                 let mut ors = Vec::new();
                 for f in facets.deref() {
+                    let is_excluded = match (f, excluded) {
+                        (Either::Left(keys), Some(field_id)) => keys.iter().all(|k| k.key() == field_id),
+                        (Either::Right(key), Some(field_id)) => key.key() == field_id,
+                        (_, None) => false,
+                    };
+
+                    if is_excluded {
+                        continue;
+                    }
+
                     match f {
                         Either::Left(keys) => {
                             ors.reserve(keys.len());
@@ -113,6 +332,12 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                         }
                     };
                 }
+
+                // every clause has been excluded, there is no restriction
+                if ands.is_empty() {
+                    return Ok(None);
+                }
+
                 let ands: Vec<_> = ands.iter().map(Cow::deref).collect();
                 Some(
                     sdset::multi::OpBuilder::from_vec(ands)
@@ -127,13 +352,16 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
 
     fn standard_query(self, reader: &MainReader, query: &str, range: Range<usize>) -> MResult<SortResult> {
         let facets_docids = match self.facets_docids(reader)? {
-            Some(ids) if ids.is_empty() => return Ok(SortResult::default()),
+            // the sticky facets distribution must be computed even if there is no candidates
+            Some(ids) if ids.is_empty() && !self.sticky_facets => return Ok(SortResult::default()),
             other => other
         };
         // for each field to retrieve the count for, create an HashMap associating the attribute
         // value to a set of matching documents. The HashMaps are them collected in another
         // HashMap, associating each HashMap to it's field.
         let facet_count_docids = self.facet_count_docids(reader)?;
+        let facets_pivot_docids = self.facets_pivot_docids(reader)?;
+        let context = query_tree_context(reader, self.index, self.locale.as_ref(), self.remove_stop_words)?;
 
         match self.distinct {
             Some((distinct, distinct_size)) => bucket_sort_with_distinct(
@@ -142,11 +370,20 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 range,
                 facets_docids,
                 facet_count_docids,
+                facets_pivot_docids,
+                self.max_facet_values,
+                self.facet_stats,
+                self.ranking_score_threshold,
                 self.filter,
                 distinct,
                 distinct_size,
+                self.distinct_scan_limit,
                 self.criteria,
                 self.searchable_attrs,
+                context,
+                self.sticky_facets,
+                self.exhaustive_nb_hits,
+                self.cancelled.as_deref(),
                 self.index,
             ),
             None => bucket_sort(
@@ -155,9 +392,17 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 range,
                 facets_docids,
                 facet_count_docids,
+                facets_pivot_docids,
+                self.max_facet_values,
+                self.facet_stats,
+                self.ranking_score_threshold,
                 self.filter,
                 self.criteria,
                 self.searchable_attrs,
+                context,
+                self.sticky_facets,
+                self.exhaustive_nb_hits,
+                self.cancelled.as_deref(),
                 self.index,
             ),
         }
@@ -169,17 +414,45 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 // We sort the docids from facets according to the criteria set by the user
                 let mut sorted_docids = docids.clone().into_vec();
                 let mut sort_result = match self.index.main.ranked_map(reader)? {
-                    Some(ranked_map) => {
-                        placeholder_document_sort(&mut sorted_docids, self.index, reader, &ranked_map)?;
+                    // no need to sort the documents when none is requested
+                    Some(ranked_map) if !range.is_empty() => {
+                        match &self.placeholder_sort {
+                            Some(rules) => rules_document_sort(
+                                &mut sorted_docids,
+                                self.index,
+                                reader,
+                                &ranked_map,
+                                rules,
+                                self.sort_missing_first,
+                                self.geo_distances.as_ref(),
+                            )?,
+                            None => placeholder_document_sort(&mut sorted_docids, self.index, reader, &ranked_map, self.sort_missing_first)?,
+                        }
                         self.sort_result_from_docids(&sorted_docids, range)
                     },
                     // if we can't perform a sort, we return documents unordered
-                    None => self.sort_result_from_docids(&docids, range),
+                    _ => self.sort_result_from_docids(&docids, range),
                 };
 
                 if let Some(f) = self.facet_count_docids(reader)? {
                     sort_result.exhaustive_facets_count = Some(true);
-                    sort_result.facets = Some(facet_count(f, &docids));
+                    let facets = if self.sticky_facets {
+                        let all_docids = self.index.main.internal_docids(reader)?;
+                        sticky_facet_count(f, &all_docids, self.max_facet_values, self.filter.as_ref(), &mut HashMap::new())
+                    } else {
+                        facet_count(f, &self.filter_candidates(&docids), self.max_facet_values)
+                    };
+                    sort_result.facets = Some(facets);
+                }
+
+                if let Some(p) = self.facets_pivot_docids(reader)? {
+                    sort_result.facets_pivot = Some(pivot_count(p, &self.filter_candidates(&docids)));
+                }
+
+                if let Some(fields) = &self.facet_stats {
+                    let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
+                    let candidates = self.filter_candidates(&docids);
+                    sort_result.facet_stats = Some(facet_stats(reader, self.index, fields, &candidates, &ranked_map)?);
                 }
 
                 Ok(sort_result)
@@ -188,13 +461,44 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 match self.index.main.sorted_document_ids_cache(reader)? {
                     // build result from cached document ids
                     Some(docids) => {
-                        let mut sort_result = self.sort_result_from_docids(&docids, range);
-
-                        if let Some(f) = self.facet_count_docids(reader)? {
-                            sort_result.exhaustive_facets_count = Some(true);
+                        // the cached documents ids are sorted according to the ranking rules
+                        let mut sort_result = match (&self.placeholder_sort, self.index.main.ranked_map(reader)?) {
+                            (Some(rules), Some(ranked_map)) if !range.is_empty() => {
+                                let mut sorted_docids = docids.to_vec();
+                                rules_document_sort(
+                                    &mut sorted_docids,
+                                    self.index,
+                                    reader,
+                                    &ranked_map,
+                                    rules,
+                                    self.sort_missing_first,
+                                    self.geo_distances.as_ref(),
+                                )?;
+                                self.sort_result_from_docids(&sorted_docids, range)
+                            },
+                            _ => self.sort_result_from_docids(&docids, range),
+                        };
+
+                        let facet_count_docids = self.facet_count_docids(reader)?;
+                        let facets_pivot_docids = self.facets_pivot_docids(reader)?;
+                        if facet_count_docids.is_some() || facets_pivot_docids.is_some() || self.facet_stats.is_some() {
                             // document ids are not sorted in natural order, we need to construct a new set
                             let document_set = SetBuf::from_dirty(Vec::from(docids));
-                            sort_result.facets = Some(facet_count(f, &document_set));
+                            let candidates = self.filter_candidates(&document_set);
+
+                            if let Some(f) = facet_count_docids {
+                                sort_result.exhaustive_facets_count = Some(true);
+                                sort_result.facets = Some(facet_count(f, &candidates, self.max_facet_values));
+                            }
+
+                            if let Some(p) = facets_pivot_docids {
+                                sort_result.facets_pivot = Some(pivot_count(p, &candidates));
+                            }
+
+                            if let Some(fields) = &self.facet_stats {
+                                let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
+                                sort_result.facet_stats = Some(facet_stats(reader, self.index, fields, &candidates, &ranked_map)?);
+                            }
                         }
 
                         Ok(sort_result)
@@ -207,21 +511,81 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
     }
 
     fn facet_count_docids<'a>(&self, reader: &'a MainReader) -> MResult<Option<HashMap<String, HashMap<String, Cow<'a, Set<DocumentId>>>>>> {
-        match self.facets {
-            Some(ref field_ids) => {
-                let mut facet_count_map = HashMap::new();
-                for (field_id, field_name) in field_ids {
-                    let mut key_map = HashMap::new();
-                    for pair in self.index.facets.field_document_ids(reader, *field_id)? {
-                        let (facet_key, document_ids) = pair?;
-                        let value = facet_key.value();
-                        key_map.insert(value.to_string(), document_ids);
+        if self.facets.is_none() {
+            return Ok(None);
+        }
+
+        let mut facet_count_map = HashMap::new();
+        for (field_id, field_name) in self.facets.iter().flatten() {
+            // sticky facets values are only restricted by the filters on the other facets
+            let restriction = if self.sticky_facets {
+                self.facets_docids_excluding(reader, Some(*field_id))?
+            } else {
+                None
+            };
+
+            let mut key_map = HashMap::new();
+            for pair in self.index.facets.field_document_ids(reader, *field_id)? {
+                let (facet_key, document_ids) = pair?;
+                let document_ids = match restriction {
+                    Some(ref restriction) => {
+                        let docids = sdset::duo::OpBuilder::new(document_ids.as_ref(), restriction.as_set())
+                            .intersection()
+                            .into_set_buf();
+                        Cow::Owned(docids)
                     }
-                    facet_count_map.insert(field_name.clone(), key_map);
+                    None => document_ids,
+                };
+                let value = facet_key.value();
+                key_map.insert(value.to_string(), document_ids);
+            }
+            facet_count_map.insert(field_name.clone(), key_map);
+        }
+
+        Ok(Some(facet_count_map))
+    }
+
+    /// associates each value of the first pivot facet to the document ids of each value of
+    /// the second one, only the combinations that some documents have are kept.
+    fn facets_pivot_docids<'a>(&self, reader: &'a MainReader) -> MResult<Option<HashMap<String, HashMap<String, Cow<'a, Set<DocumentId>>>>>> {
+        let (first, second) = match self.facets_pivot {
+            Some(pivot) => pivot,
+            None => return Ok(None),
+        };
+
+        let second_values = self.index.facets
+            .field_document_ids(reader, second)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut pivot_map = HashMap::new();
+        for pair in self.index.facets.field_document_ids(reader, first)? {
+            let (first_key, first_docids) = pair?;
+            let mut key_map = HashMap::new();
+            for (second_key, second_docids) in &second_values {
+                let docids = sdset::duo::OpBuilder::new(first_docids.as_ref(), second_docids.as_ref())
+                    .intersection()
+                    .into_set_buf();
+                if !docids.is_empty() {
+                    key_map.insert(second_key.value().to_string(), Cow::Owned(docids));
                 }
-                Ok(Some(facet_count_map))
             }
-            None => Ok(None),
+            if !key_map.is_empty() {
+                pivot_map.insert(first_key.value().to_string(), key_map);
+            }
+        }
+
+        Ok(Some(pivot_map))
+    }
+
+    /// returns the documents ids accepted by the filter, the facets distribution must only
+    /// account for these documents.
+    fn filter_candidates<'a>(&self, docids: &'a Set<DocumentId>) -> Cow<'a, Set<DocumentId>> {
+        match self.filter {
+            Some(ref filter) => {
+                let docids = docids.iter().filter(|id| (filter)(**id)).cloned().collect();
+                Cow::Owned(SetBuf::new_unchecked(docids))
+            }
+            None => Cow::Borrowed(docids),
         }
     }
 
@@ -258,7 +622,13 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         }
 
         sort_result.documents = result;
-        sort_result.nb_hits = docids.len();
+        if self.exhaustive_nb_hits {
+            let distinct = self.distinct.as_ref().map(|(distinct, size)| (distinct, *size));
+            sort_result.nb_hits = exhaustive_nb_hits_count(docids, self.filter.as_ref(), &mut HashMap::new(), distinct);
+            sort_result.exhaustive_nb_hit = true;
+        } else {
+            sort_result.nb_hits = docids.len();
+        }
         sort_result
     }
 
@@ -268,10 +638,46 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         query: Option<&str>,
         range: Range<usize>,
     ) -> MResult<SortResult> {
-        match query {
-            Some(query) => self.standard_query(reader, query, range),
-            None => self.placeholder_query(reader, range),
+        check_cancelled(self.cancelled.as_deref())?;
+        let facets_names: Option<Vec<String>> = self.facets
+            .as_ref()
+            .map(|facets| facets.iter().map(|(_, name)| name.clone()).collect());
+        // the suggestion depends on the number of hits, it is computed after the search
+        let suggestion_context = if self.suggest_query {
+            Some((self.index, self.locale.clone(), self.remove_stop_words))
+        } else {
+            None
+        };
+        let mut result = match query {
+            // an empty query browses the documents like a placeholder search
+            Some(query) if !query.trim().is_empty() => self.standard_query(reader, query, range)?,
+            _ => self.placeholder_query(reader, range)?,
+        };
+
+        if let (Some((index, locale, remove_stop_words)), Some(query)) = (suggestion_context, query) {
+            if result.nb_hits < SUGGESTED_QUERY_MAX_NB_HITS {
+                let context = query_tree_context(reader, index, locale.as_ref(), remove_stop_words)?;
+                result.suggested_query = suggested_query(reader, &context, query)?;
+            }
         }
+
+        match facets_names {
+            // the requested facets are always part of the distribution, even
+            // when the index is empty or there is no candidate documents
+            Some(names) => {
+                let facets = result.facets.get_or_insert_with(HashMap::new);
+                for name in names {
+                    facets.entry(name).or_default();
+                }
+                result.exhaustive_facets_count.get_or_insert(true);
+            }
+            None => {
+                result.facets = None;
+                result.exhaustive_facets_count = None;
+            }
+        }
+
+        Ok(result)
     }
 }
 
@@ -1403,4 +1809,53 @@ mod tests {
         });
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn query_words_typos() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_index(0, 0)][..]),
+            ("world", &[doc_index(0, 1)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, Some("hello warld"), 0..20).unwrap();
+        let mut iter = documents.into_iter();
+
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), query_typos, typo_corrected: true, .. }) => {
+            assert_eq!(query_typos, vec![Some(0), Some(1)]);
+        });
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn cancelled_query() {
+        let store = TempDatabase::from_iter(vec![
+            ("search", &[doc_index(0, 0)][..]),
+            ("search", &[doc_index(1, 0)][..]),
+            ("search", &[doc_index(2, 0)][..]),
+            ("search", &[doc_index(3, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        // the flag is raised while the first document is being filtered
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let filtered = std::cell::Cell::new(0);
+
+        let mut builder = store.query_builder();
+        builder.set_cancellation_flag(Some(cancelled.clone()));
+        builder.with_filter(|_| {
+            filtered.set(filtered.get() + 1);
+            cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            false
+        });
+
+        let result = builder.query(&reader, Some("search"), 0..20);
+        assert_matches!(result, Err(crate::Error::Cancelled));
+        assert_eq!(filtered.get(), 1);
+    }
 }
@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::time::Instant;
@@ -12,8 +12,9 @@ use sdset::{Set, SetBuf, SetOperation};
 use log::debug;
 
 use crate::database::MainT;
+use crate::settings::Locale;
 use crate::{store, DocumentId, DocIndex, MResult, FstSetCow};
-use crate::automaton::{normalize_str, build_dfa, build_prefix_dfa, build_exact_dfa};
+use crate::automaton::{normalize_str, build_dfa, build_prefix_dfa, build_exact_dfa, build_suggestion_dfa};
 use crate::QueryWordsMapper;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -118,6 +119,7 @@ pub struct Context<'a> {
     pub synonyms: store::Synonyms,
     pub postings_lists: store::PostingsLists,
     pub prefix_postings_lists: store::PrefixPostingsListsCache,
+    pub locale: Option<&'a Locale>,
 }
 
 fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'a str) -> MResult<Option<(&'a str, &'a str)>> {
@@ -174,6 +176,128 @@ where I: IntoIterator<Item=Operation>,
 
 const MAX_NGRAM: usize = 3;
 
+/// Returns the original words of the query tree in the query order, every word is associated
+/// to the operation searching for it and its alternatives.
+fn word_groups(operation: &Operation) -> BTreeMap<QueryId, (&str, &Operation)> {
+    // the original words are the only tolerant queries, they are grouped with their alternatives
+    fn recurs_operation<'o>(groups: &mut BTreeMap<QueryId, (&'o str, &'o Operation)>, operation: &'o Operation) {
+        match operation {
+            Operation::And(ops) => ops.iter().for_each(|op| recurs_operation(groups, op)),
+            Operation::Or(ops) => {
+                let original = ops.iter().find_map(|op| match op {
+                    Operation::Query(Query { id, kind: QueryKind::Tolerant(word), .. }) => Some((*id, word.as_str())),
+                    _ => None,
+                });
+                match original {
+                    Some((id, word)) => { groups.entry(id).or_insert((word, operation)); },
+                    None => ops.iter().for_each(|op| recurs_operation(groups, op)),
+                }
+            },
+            Operation::Query(Query { id, kind: QueryKind::Tolerant(word), .. }) => {
+                groups.entry(*id).or_insert((word.as_str(), operation));
+            },
+            Operation::Query(_) => (),
+        }
+    }
+
+    let mut groups = BTreeMap::new();
+    recurs_operation(&mut groups, operation);
+    groups
+}
+
+/// Returns the words searched by the query tree in the query order, every word is followed
+/// by its synonyms. The stop words removed from the query are not part of the tree.
+pub fn normalized_query(operation: &Operation) -> Vec<Vec<String>> {
+    // the synonyms are the non tolerant alternatives of a word, the phrases are its splits
+    fn synonym(operation: &Operation) -> Option<String> {
+        match operation {
+            Operation::Query(Query { kind: QueryKind::NonTolerant(word), .. }) => Some(word.clone()),
+            Operation::And(ops) => {
+                let words: Option<Vec<_>> = ops.iter().map(synonym).collect();
+                words.map(|words| words.join(" "))
+            },
+            _ => None,
+        }
+    }
+
+    word_groups(operation)
+        .into_iter()
+        .map(|(_, (word, group))| {
+            let synonyms: Vec<_> = match group {
+                Operation::Or(ops) => ops.iter().filter_map(synonym).collect(),
+                _ => Vec::new(),
+            };
+            once(word.to_string()).chain(synonyms).collect()
+        })
+        .collect()
+}
+
+/// Returns the query where the words missing from the index vocabulary are replaced by
+/// the closest word of the vocabulary, the most frequent one on equal distance.
+///
+/// The last word is kept as it is, it is searched as a prefix and may not be complete.
+/// Nothing is returned when all the words are known or when no close word exists.
+pub fn suggested_query(
+    reader: &heed::RoTxn<MainT>,
+    ctx: &Context,
+    query: &str,
+) -> MResult<Option<String>>
+{
+    let words: Vec<_> = split_query_string(query).map(str::to_lowercase).collect();
+    let mut corrected = false;
+    let mut suggestion = Vec::with_capacity(words.len());
+
+    for (i, word) in words.iter().enumerate() {
+        let is_last = i + 1 == words.len();
+        if is_last || ctx.stop_words.contains(word) || ctx.words_set.contains(word) {
+            suggestion.push(word.clone());
+            continue;
+        }
+
+        let dfa = build_suggestion_dfa(word);
+        let mut stream = ctx.words_set.search(&dfa).into_stream();
+
+        let mut best: Option<(u8, cmp::Reverse<usize>, String)> = None;
+        while let Some(input) = stream.next() {
+            let distance = dfa.eval(input).to_u8();
+            let frequency = match ctx.postings_lists.postings_list(reader, input)? {
+                Some(result) => result.docids.len(),
+                None => continue,
+            };
+            let candidate = (distance, cmp::Reverse(frequency), String::from_utf8_lossy(input).into_owned());
+            if best.as_ref().map_or(true, |best| candidate < *best) {
+                best = Some(candidate);
+            }
+        }
+
+        match best {
+            Some((_, _, closest)) => {
+                corrected = true;
+                suggestion.push(closest);
+            }
+            None => suggestion.push(word.clone()),
+        }
+    }
+
+    Ok(if corrected { Some(suggestion.join(" ")) } else { None })
+}
+
+/// Returns the documents containing each word of the query tree or one of its alternatives,
+/// in the query order. The alternatives are the ones the word is searched with.
+pub fn words_candidates(
+    reader: &heed::RoTxn<MainT>,
+    ctx: &Context,
+    operation: &Operation,
+) -> MResult<Vec<(String, SetBuf<DocumentId>)>>
+{
+    let mut candidates = Vec::new();
+    for (_, (word, group)) in word_groups(operation) {
+        let QueryResult { docids, .. } = traverse_query_tree(reader, ctx, group)?;
+        candidates.push((word.to_string(), docids.into_owned()));
+    }
+    Ok(candidates)
+}
+
 pub fn create_query_tree(
     reader: &heed::RoTxn<MainT>,
     ctx: &Context,
@@ -208,7 +332,15 @@ pub fn create_query_tree(
                         let mut idgen = ((id + 1) * 100)..;
                         let range = (*id)..id+1;
 
-                        let phrase = split_best_frequency(reader, ctx, word)?
+                        // without any language hint we try to split every word
+                        let split_compound_words = ctx.locale.map_or(true, Locale::splits_compound_words);
+                        let best_split = if split_compound_words {
+                            split_best_frequency(reader, ctx, word)?
+                        } else {
+                            None
+                        };
+
+                        let phrase = best_split
                             .map(|ws| {
                                 let id = idgen.next().unwrap();
                                 idgen.next().unwrap();
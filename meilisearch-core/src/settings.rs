@@ -2,17 +2,26 @@ use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::str::FromStr;
 use std::iter::IntoIterator;
 
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use once_cell::sync::Lazy;
 
 use self::RankingRule::*;
 
 pub const DEFAULT_RANKING_RULES: [RankingRule; 6] = [Typo, Words, Proximity, Attribute, WordsPosition, Exactness];
 
+pub const DEFAULT_FACET_NORMALIZATION: [FacetNormalization; 2] = [FacetNormalization::Trim, FacetNormalization::Nfc];
+
 static RANKING_RULE_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
     regex::Regex::new(r"(asc|desc)\(([a-zA-Z0-9-_]*)\)").unwrap()
 });
 
+static LOCALE_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"^([a-zA-Z]{2,3})([-_][a-zA-Z0-9]{2,8})*$").unwrap()
+});
+
+/// Languages in which words are commonly glued together to form compound words.
+const COMPOUNDING_LANGUAGES: [&str; 7] = ["da", "de", "fi", "is", "nl", "no", "sv"];
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct Settings {
@@ -30,6 +39,22 @@ pub struct Settings {
     pub synonyms: Option<Option<BTreeMap<String, Vec<String>>>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     pub attributes_for_faceting: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub locale: Option<Option<Locale>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub filterable_attributes: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub default_attributes_to_highlight: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub facet_normalization: Option<Option<Vec<FacetNormalization>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub payload_size_limit: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub default_hits_per_page: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub min_query_length: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub max_facet_value_length: Option<Option<usize>>,
 }
 
 // Any value that is present is considered Some value, including null.
@@ -59,6 +84,14 @@ impl Settings {
             stop_words: settings.stop_words.into(),
             synonyms: settings.synonyms.into(),
             attributes_for_faceting: settings.attributes_for_faceting.into(),
+            locale: settings.locale.into(),
+            filterable_attributes: settings.filterable_attributes.into(),
+            default_attributes_to_highlight: settings.default_attributes_to_highlight.into(),
+            facet_normalization: settings.facet_normalization.into(),
+            payload_size_limit: settings.payload_size_limit.into(),
+            default_hits_per_page: settings.default_hits_per_page.into(),
+            min_query_length: settings.min_query_length.into(),
+            max_facet_value_length: settings.max_facet_value_length.into(),
         })
     }
 }
@@ -155,6 +188,74 @@ impl RankingRule {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct LocaleConversionError(String);
+
+impl std::fmt::Display for LocaleConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid locale {:?}, expected a language code such as \"en\" or \"de-CH\"", self.0)
+    }
+}
+
+/// A language hint, in the form of a BCP 47 language tag (`en`, `de-CH`...), used to adapt
+/// the way the query is tokenized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(String);
+
+impl Locale {
+    /// returns the primary language subtag, lowercased.
+    pub fn language(&self) -> &str {
+        self.0.split(|c| c == '-' || c == '_').next().unwrap_or(&self.0)
+    }
+
+    /// returns whether query words must be tried as compounds of two indexed words.
+    pub fn splits_compound_words(&self) -> bool {
+        COMPOUNDING_LANGUAGES.contains(&self.language())
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = LocaleConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if LOCALE_REGEX.is_match(s) {
+            Ok(Locale(s.to_lowercase()))
+        } else {
+            Err(LocaleConversionError(s.to_string()))
+        }
+    }
+}
+
+impl Serialize for Locale {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Locale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Locale::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+/// A normalization step applied to the facet values, the same steps are applied
+/// when indexing the documents and when parsing the facet filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetNormalization {
+    /// removes the leading and trailing whitespaces.
+    Trim,
+    /// applies the Unicode canonical composition (NFC).
+    Nfc,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsUpdate {
     pub ranking_rules: UpdateState<Vec<RankingRule>>,
@@ -165,6 +266,14 @@ pub struct SettingsUpdate {
     pub stop_words: UpdateState<BTreeSet<String>>,
     pub synonyms: UpdateState<BTreeMap<String, Vec<String>>>,
     pub attributes_for_faceting: UpdateState<Vec<String>>,
+    pub locale: UpdateState<Locale>,
+    pub filterable_attributes: UpdateState<Vec<String>>,
+    pub default_attributes_to_highlight: UpdateState<Vec<String>>,
+    pub facet_normalization: UpdateState<Vec<FacetNormalization>>,
+    pub payload_size_limit: UpdateState<usize>,
+    pub default_hits_per_page: UpdateState<usize>,
+    pub min_query_length: UpdateState<usize>,
+    pub max_facet_value_length: UpdateState<usize>,
 }
 
 impl Default for SettingsUpdate {
@@ -178,6 +287,14 @@ impl Default for SettingsUpdate {
             stop_words: UpdateState::Nothing,
             synonyms: UpdateState::Nothing,
             attributes_for_faceting: UpdateState::Nothing,
+            locale: UpdateState::Nothing,
+            filterable_attributes: UpdateState::Nothing,
+            default_attributes_to_highlight: UpdateState::Nothing,
+            facet_normalization: UpdateState::Nothing,
+            payload_size_limit: UpdateState::Nothing,
+            default_hits_per_page: UpdateState::Nothing,
+            min_query_length: UpdateState::Nothing,
+            max_facet_value_length: UpdateState::Nothing,
         }
     }
 }
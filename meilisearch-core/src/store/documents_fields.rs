@@ -6,6 +6,10 @@ use meilisearch_schema::FieldId;
 use super::DocumentFieldStoredKey;
 use crate::DocumentId;
 
+/// The version of a document is stored after its fields, under
+/// a field id that is not given to the attributes.
+const DOCUMENT_VERSION_FIELD: FieldId = FieldId::max();
+
 #[derive(Copy, Clone)]
 pub struct DocumentsFields {
     pub(crate) documents_fields: heed::Database<OwnedType<DocumentFieldStoredKey>, ByteSlice>,
@@ -23,6 +27,32 @@ impl DocumentsFields {
         self.documents_fields.put(writer, &key, value)
     }
 
+    pub fn put_document_version(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        version: u64,
+    ) -> ZResult<()> {
+        let key = DocumentFieldStoredKey::new(document_id, DOCUMENT_VERSION_FIELD);
+        self.documents_fields.put(writer, &key, &version.to_be_bytes())
+    }
+
+    pub fn document_version(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+    ) -> ZResult<Option<u64>> {
+        let key = DocumentFieldStoredKey::new(document_id, DOCUMENT_VERSION_FIELD);
+        match self.documents_fields.get(reader, &key)? {
+            Some(bytes) => {
+                let mut version = [0; 8];
+                version.copy_from_slice(bytes);
+                Ok(Some(u64::from_be_bytes(version)))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn del_all_document_fields(
         self,
         writer: &mut heed::RwTxn<MainT>,
@@ -52,9 +82,10 @@ impl DocumentsFields {
         reader: &'txn heed::RoTxn<MainT>,
         document_id: DocumentId,
     ) -> ZResult<DocumentFieldsIter<'txn>> {
+        // the document version is not one of its fields
         let start = DocumentFieldStoredKey::new(document_id, FieldId::min());
-        let end = DocumentFieldStoredKey::new(document_id, FieldId::max());
-        let iter = self.documents_fields.range(reader, &(start..=end))?;
+        let end = DocumentFieldStoredKey::new(document_id, DOCUMENT_VERSION_FIELD);
+        let iter = self.documents_fields.range(reader, &(start..end))?;
         Ok(DocumentFieldsIter { iter })
     }
 }
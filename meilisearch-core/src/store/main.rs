@@ -9,19 +9,29 @@ use sdset::Set;
 
 use crate::database::MainT;
 use crate::{RankedMap, MResult};
-use crate::settings::RankingRule;
+use crate::settings::{FacetNormalization, Locale, RankingRule};
 use crate::{FstSetCow, FstMapCow};
 use super::{CowSet, DocumentsIds};
 
 const ATTRIBUTES_FOR_FACETING_KEY: &str = "attributes-for-faceting";
 const CREATED_AT_KEY: &str = "created-at";
 const CUSTOMS_KEY: &str = "customs";
+const DEFAULT_ATTRIBUTES_TO_HIGHLIGHT_KEY: &str = "default-attributes-to-highlight";
+const DEFAULT_HITS_PER_PAGE_KEY: &str = "default-hits-per-page";
 const DISTINCT_ATTRIBUTE_KEY: &str = "distinct-attribute";
 const EXTERNAL_DOCIDS_KEY: &str = "external-docids";
+const FACET_NORMALIZATION_KEY: &str = "facet-normalization";
 const FIELDS_DISTRIBUTION_KEY: &str = "fields-distribution";
+const FILTERABLE_ATTRIBUTES_KEY: &str = "filterable-attributes";
 const INTERNAL_DOCIDS_KEY: &str = "internal-docids";
+const LOCALE_KEY: &str = "locale";
+const MAX_FACET_VALUE_LENGTH_KEY: &str = "max-facet-value-length";
+const MIN_QUERY_LENGTH_KEY: &str = "min-query-length";
 const NAME_KEY: &str = "name";
 const NUMBER_OF_DOCUMENTS_KEY: &str = "number-of-documents";
+const PAYLOAD_SIZE_LIMIT_KEY: &str = "payload-size-limit";
+const PRIMARY_KEY_CANDIDATES_KEY: &str = "primary-key-candidates";
+const PRIMARY_KEY_COMPONENTS_KEY: &str = "primary-key-components";
 const RANKED_MAP_KEY: &str = "ranked-map";
 const RANKING_RULES_KEY: &str = "ranking-rules";
 const SCHEMA_KEY: &str = "schema";
@@ -283,6 +293,30 @@ impl Main {
         Ok(self.main.delete::<_, Str>(writer, ATTRIBUTES_FOR_FACETING_KEY)?)
     }
 
+    pub fn filterable_attributes<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> MResult<Option<Cow<'txn, Set<FieldId>>>> {
+        Ok(self.main.get::<_, Str, CowSet<FieldId>>(reader, FILTERABLE_ATTRIBUTES_KEY)?)
+    }
+
+    pub fn put_filterable_attributes(self, writer: &mut heed::RwTxn<MainT>, attributes: &Set<FieldId>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, CowSet<FieldId>>(writer, FILTERABLE_ATTRIBUTES_KEY, attributes)?)
+    }
+
+    pub fn delete_filterable_attributes(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, FILTERABLE_ATTRIBUTES_KEY)?)
+    }
+
+    pub fn default_attributes_to_highlight<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> MResult<Option<Cow<'txn, Set<FieldId>>>> {
+        Ok(self.main.get::<_, Str, CowSet<FieldId>>(reader, DEFAULT_ATTRIBUTES_TO_HIGHLIGHT_KEY)?)
+    }
+
+    pub fn put_default_attributes_to_highlight(self, writer: &mut heed::RwTxn<MainT>, attributes: &Set<FieldId>) -> MResult<()> {
+        Ok(self.main.put::<_, Str, CowSet<FieldId>>(writer, DEFAULT_ATTRIBUTES_TO_HIGHLIGHT_KEY, attributes)?)
+    }
+
+    pub fn delete_default_attributes_to_highlight(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, DEFAULT_ATTRIBUTES_TO_HIGHLIGHT_KEY)?)
+    }
+
     pub fn ranking_rules(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Vec<RankingRule>>> {
         Ok(self.main.get::<_, Str, SerdeBincode<Vec<RankingRule>>>(reader, RANKING_RULES_KEY)?)
     }
@@ -310,6 +344,109 @@ impl Main {
         Ok(self.main.delete::<_, Str>(writer, DISTINCT_ATTRIBUTE_KEY)?)
     }
 
+    pub fn facet_normalization(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Vec<FacetNormalization>>> {
+        Ok(self.main.get::<_, Str, SerdeBincode<Vec<FacetNormalization>>>(reader, FACET_NORMALIZATION_KEY)?)
+    }
+
+    pub fn put_facet_normalization(self, writer: &mut heed::RwTxn<MainT>, value: &[FacetNormalization]) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<Vec<FacetNormalization>>>(writer, FACET_NORMALIZATION_KEY, &value.to_vec())?)
+    }
+
+    pub fn delete_facet_normalization(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, FACET_NORMALIZATION_KEY)?)
+    }
+
+    pub fn locale(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Locale>> {
+        Ok(self.main.get::<_, Str, SerdeBincode<Locale>>(reader, LOCALE_KEY)?)
+    }
+
+    pub fn put_locale(self, writer: &mut heed::RwTxn<MainT>, value: &Locale) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<Locale>>(writer, LOCALE_KEY, value)?)
+    }
+
+    pub fn delete_locale(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, LOCALE_KEY)?)
+    }
+
+    /// the maximum size in bytes of the documents payloads, overriding the global one.
+    pub fn payload_size_limit(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<usize>> {
+        let limit = self.main.get::<_, Str, OwnedType<u64>>(reader, PAYLOAD_SIZE_LIMIT_KEY)?;
+        Ok(limit.map(|limit| limit as usize))
+    }
+
+    pub fn put_payload_size_limit(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> MResult<()> {
+        Ok(self.main.put::<_, Str, OwnedType<u64>>(writer, PAYLOAD_SIZE_LIMIT_KEY, &(value as u64))?)
+    }
+
+    pub fn delete_payload_size_limit(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, PAYLOAD_SIZE_LIMIT_KEY)?)
+    }
+
+    /// the number of hits returned by the search requests that do not specify a limit.
+    pub fn default_hits_per_page(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<usize>> {
+        let limit = self.main.get::<_, Str, OwnedType<u64>>(reader, DEFAULT_HITS_PER_PAGE_KEY)?;
+        Ok(limit.map(|limit| limit as usize))
+    }
+
+    pub fn put_default_hits_per_page(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> MResult<()> {
+        Ok(self.main.put::<_, Str, OwnedType<u64>>(writer, DEFAULT_HITS_PER_PAGE_KEY, &(value as u64))?)
+    }
+
+    pub fn delete_default_hits_per_page(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, DEFAULT_HITS_PER_PAGE_KEY)?)
+    }
+
+    /// the number of characters under which the queries are not run.
+    pub fn min_query_length(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<usize>> {
+        let length = self.main.get::<_, Str, OwnedType<u64>>(reader, MIN_QUERY_LENGTH_KEY)?;
+        Ok(length.map(|length| length as usize))
+    }
+
+    pub fn put_min_query_length(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> MResult<()> {
+        Ok(self.main.put::<_, Str, OwnedType<u64>>(writer, MIN_QUERY_LENGTH_KEY, &(value as u64))?)
+    }
+
+    pub fn delete_min_query_length(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, MIN_QUERY_LENGTH_KEY)?)
+    }
+
+    /// the number of characters over which the facet values are not indexed.
+    pub fn max_facet_value_length(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<usize>> {
+        let length = self.main.get::<_, Str, OwnedType<u64>>(reader, MAX_FACET_VALUE_LENGTH_KEY)?;
+        Ok(length.map(|length| length as usize))
+    }
+
+    pub fn put_max_facet_value_length(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> MResult<()> {
+        Ok(self.main.put::<_, Str, OwnedType<u64>>(writer, MAX_FACET_VALUE_LENGTH_KEY, &(value as u64))?)
+    }
+
+    pub fn delete_max_facet_value_length(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, MAX_FACET_VALUE_LENGTH_KEY)?)
+    }
+
+    /// the attributes that can become the primary key when the first documents are added,
+    /// by order of preference.
+    pub fn primary_key_candidates(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Vec<String>>> {
+        Ok(self.main.get::<_, Str, SerdeBincode<Vec<String>>>(reader, PRIMARY_KEY_CANDIDATES_KEY)?)
+    }
+
+    pub fn put_primary_key_candidates(self, writer: &mut heed::RwTxn<MainT>, value: &[String]) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<Vec<String>>>(writer, PRIMARY_KEY_CANDIDATES_KEY, &value.to_vec())?)
+    }
+
+    pub fn delete_primary_key_candidates(self, writer: &mut heed::RwTxn<MainT>) -> MResult<bool> {
+        Ok(self.main.delete::<_, Str>(writer, PRIMARY_KEY_CANDIDATES_KEY)?)
+    }
+
+    /// the attributes whose values are joined to form the primary key of the documents.
+    pub fn primary_key_components(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<Vec<String>>> {
+        Ok(self.main.get::<_, Str, SerdeBincode<Vec<String>>>(reader, PRIMARY_KEY_COMPONENTS_KEY)?)
+    }
+
+    pub fn put_primary_key_components(self, writer: &mut heed::RwTxn<MainT>, value: &[String]) -> MResult<()> {
+        Ok(self.main.put::<_, Str, SerdeBincode<Vec<String>>>(writer, PRIMARY_KEY_COMPONENTS_KEY, &value.to_vec())?)
+    }
+
     pub fn put_customs(self, writer: &mut heed::RwTxn<MainT>, customs: &[u8]) -> MResult<()> {
         Ok(self.main.put::<_, Str, ByteSlice>(writer, CUSTOMS_KEY, customs)?)
     }
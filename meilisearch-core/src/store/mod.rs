@@ -278,6 +278,33 @@ impl Index {
         }
     }
 
+    /// returns the version of the document, incremented each time it is added or updated.
+    pub fn document_version(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+    ) -> MResult<Option<u64>> {
+        Ok(self.documents_fields.document_version(reader, document_id)?)
+    }
+
+    /// returns the documents containing the exact word in the given indexed attribute.
+    pub fn word_attribute_docids(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        word: &str,
+        attribute: IndexedPos,
+    ) -> MResult<SetBuf<DocumentId>> {
+        let docids = match self.postings_lists.postings_list(reader, word.as_bytes())? {
+            Some(postings) => postings.matches
+                .iter()
+                .filter(|di| di.attribute == attribute.0)
+                .map(|di| di.document_id)
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(SetBuf::from_dirty(docids))
+    }
+
     pub fn customs_update(&self, writer: &mut heed::RwTxn<UpdateT>, customs: Vec<u8>) -> MResult<u64> {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         Ok(update::push_customs_update(writer, self.updates, self.updates_results, customs)?)
@@ -317,6 +344,11 @@ impl Index {
         update::push_clear_all(writer, self.updates, self.updates_results)
     }
 
+    pub fn facets_rebuild(&self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_facets_rebuild(writer, self.updates, self.updates_results)
+    }
+
     pub fn current_update_id(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<Option<u64>> {
         match self.updates.last_update(reader)? {
             Some((id, _)) => Ok(Some(id)),
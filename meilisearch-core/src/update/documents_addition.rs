@@ -15,7 +15,7 @@ use crate::facets;
 use crate::raw_indexer::RawIndexer;
 use crate::serde::Deserializer;
 use crate::store::{self, DocumentsFields, DocumentsFieldsCounts, DiscoverIds};
-use crate::update::helpers::{index_value, value_to_number, extract_document_id};
+use crate::update::helpers::{index_value, value_to_number, extract_document_id, composite_document_id};
 use crate::update::{apply_documents_deletion, compute_short_prefixes, next_update_id, Update};
 use crate::{Error, MResult, RankedMap};
 
@@ -164,6 +164,7 @@ pub fn apply_addition<'a, 'b>(
     let mut available_ids = DiscoverIds::new(&internal_docids);
 
     let primary_key = schema.primary_key().ok_or(Error::MissingPrimaryKey)?;
+    let primary_key_components = index.main.primary_key_components(writer)?;
 
     // 1. store documents ids for future deletion
     let mut documents_additions = HashMap::new();
@@ -171,6 +172,12 @@ pub fn apply_addition<'a, 'b>(
     let mut new_internal_docids = Vec::with_capacity(new_documents.len());
 
     for mut document in new_documents {
+        // the primary key of a document identified by several attributes is computed from them
+        if let Some(components) = &primary_key_components {
+            let docid = composite_document_id(&primary_key, components, &document)?;
+            document.insert(primary_key.to_string(), Value::String(docid));
+        }
+
         let external_docids_get = |docid: &str| {
             match (external_docids.get(docid), new_external_docids.get(docid)) {
                 (_, Some(&id))
@@ -209,6 +216,13 @@ pub fn apply_addition<'a, 'b>(
         documents_additions.insert(internal_docid, document);
     }
 
+    // the versions of the documents are kept when they are replaced
+    let mut documents_versions = HashMap::with_capacity(documents_additions.len());
+    for document_id in documents_additions.keys() {
+        let version = index.documents_fields.document_version(writer, *document_id)?;
+        documents_versions.insert(*document_id, version.unwrap_or(0) + 1);
+    }
+
     // 2. remove the documents postings lists
     let number_of_inserted_documents = documents_additions.len();
     let documents_ids = new_external_docids.iter().map(|(id, _)| id.clone()).collect();
@@ -241,6 +255,7 @@ pub fn apply_addition<'a, 'b>(
                 &value,
             )?;
         }
+        index.documents_fields.put_document_version(writer, *document_id, documents_versions[document_id])?;
     }
 
     write_documents_addition_index(
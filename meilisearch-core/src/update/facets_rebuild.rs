@@ -0,0 +1,30 @@
+use crate::database::{MainT, UpdateT};
+use crate::update::{next_update_id, Update};
+use crate::{facets, store, MResult};
+
+pub fn apply_facets_rebuild(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+) -> MResult<()> {
+    index.facets.clear(writer)?;
+
+    if let Some(attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
+        let docids = index.main.internal_docids(writer)?;
+        let facet_map = facets::facet_map_from_docids(writer, index, &docids, attributes_for_facetting.as_ref())?;
+        index.facets.add(writer, facet_map)?;
+    }
+
+    Ok(())
+}
+
+pub fn push_facets_rebuild(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+    let update = Update::facets_rebuild();
+    updates_store.put_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
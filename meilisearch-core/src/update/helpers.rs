@@ -119,6 +119,70 @@ where
     }
 }
 
+/// Extracts the document id of a document and checks its format,
+/// without resolving it to an internal id.
+pub fn validate_document_id(
+    primary_key: &str,
+    document: &IndexMap<String, Value>,
+) -> Result<String, SerializerError> {
+    let docid = match document.get(primary_key) {
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::String(string)) => string.clone(),
+        Some(_) => return Err(SerializerError::InvalidDocumentIdFormat),
+        None => return Err(SerializerError::DocumentIdNotFound),
+    };
+
+    if docid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_') {
+        Ok(docid)
+    } else {
+        Err(SerializerError::InvalidDocumentIdFormat)
+    }
+}
+
+/// Returns the id of a document identified by several attributes. The id is built from the
+/// attributes when the document contains all of them, an id carried by the document must
+/// then be the same, otherwise the document must carry its id, like the partial updates do.
+pub fn composite_document_id(
+    primary_key: &str,
+    components: &[String],
+    document: &IndexMap<String, Value>,
+) -> Result<String, SerializerError> {
+    if !components.iter().all(|component| document.contains_key(component)) {
+        return validate_document_id(primary_key, document);
+    }
+
+    let mut ids = Vec::with_capacity(components.len());
+    for component in components {
+        ids.push(escape_id_component(&validate_document_id(component, document)?));
+    }
+    let docid = ids.join("-");
+
+    match document.get(primary_key) {
+        Some(_) if validate_document_id(primary_key, document)? != docid => {
+            Err(SerializerError::Custom(format!(
+                "the primary key does not match the {} attributes it is composed of, expected {:?}",
+                components.join(", "),
+                docid,
+            )))
+        }
+        _ => Ok(docid),
+    }
+}
+
+/// Escapes the dashes and the underscores of a component of a composite id, the components
+/// are joined by a dash and two different lists of components must never give the same id.
+fn escape_id_component(id: &str) -> String {
+    let mut escaped = String::with_capacity(id.len());
+    for c in id.chars() {
+        match c {
+            '_' => escaped.push_str("__"),
+            '-' => escaped.push_str("_h"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 /// Extracts and validates the document id of a document.
 pub fn extract_document_id<F>(
     primary_key: &str,
@@ -2,6 +2,7 @@ mod clear_all;
 mod customs_update;
 mod documents_addition;
 mod documents_deletion;
+mod facets_rebuild;
 mod settings_update;
 mod helpers;
 
@@ -9,7 +10,8 @@ pub use self::clear_all::{apply_clear_all, push_clear_all};
 pub use self::customs_update::{apply_customs_update, push_customs_update};
 pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, DocumentsAddition};
 pub use self::documents_deletion::{apply_documents_deletion, DocumentsDeletion};
-pub use self::helpers::{index_value, value_to_string, value_to_number, discover_document_id, extract_document_id};
+pub use self::facets_rebuild::{apply_facets_rebuild, push_facets_rebuild};
+pub use self::helpers::{index_value, value_to_string, value_to_number, discover_document_id, extract_document_id, validate_document_id, composite_document_id};
 pub use self::settings_update::{apply_settings_update, push_settings_update};
 
 use std::cmp;
@@ -73,6 +75,13 @@ impl Update {
         }
     }
 
+    fn facets_rebuild() -> Update {
+        Update {
+            data: UpdateData::FacetsRebuild,
+            enqueued_at: Utc::now(),
+        }
+    }
+
     fn settings(data: SettingsUpdate) -> Update {
         Update {
             data: UpdateData::Settings(Box::new(data)),
@@ -88,6 +97,7 @@ pub enum UpdateData {
     DocumentsAddition(Vec<IndexMap<String, Value>>),
     DocumentsPartial(Vec<IndexMap<String, Value>>),
     DocumentsDeletion(Vec<String>),
+    FacetsRebuild,
     Settings(Box<SettingsUpdate>)
 }
 
@@ -105,6 +115,7 @@ impl UpdateData {
             UpdateData::DocumentsDeletion(deletion) => UpdateType::DocumentsDeletion {
                 number: deletion.len(),
             },
+            UpdateData::FacetsRebuild => UpdateType::FacetsRebuild,
             UpdateData::Settings(update) => UpdateType::Settings {
                 settings: update.clone(),
             },
@@ -120,6 +131,7 @@ pub enum UpdateType {
     DocumentsAddition { number: usize },
     DocumentsPartial { number: usize },
     DocumentsDeletion { number: usize },
+    FacetsRebuild,
     Settings { settings: Box<SettingsUpdate> },
 }
 
@@ -272,6 +284,14 @@ pub fn update_task<'a, 'b>(
 
             (update_type, result, start.elapsed())
         }
+        UpdateData::FacetsRebuild => {
+            let start = Instant::now();
+
+            let update_type = UpdateType::FacetsRebuild;
+            let result = apply_facets_rebuild(writer, index);
+
+            (update_type, result, start.elapsed())
+        }
         UpdateData::Settings(settings) => {
             let start = Instant::now();
 
@@ -379,6 +399,6 @@ fn cache_document_ids_sorted(
     index: &store::Index,
     document_ids: &mut [DocumentId],
 ) -> MResult<()> {
-    crate::bucket_sort::placeholder_document_sort(document_ids, index, writer, ranked_map)?;
+    crate::bucket_sort::placeholder_document_sort(document_ids, index, writer, ranked_map, None)?;
     index.main.put_sorted_document_ids_cache(writer, &document_ids)
 }
@@ -8,6 +8,7 @@ use meilisearch_schema::Schema;
 use crate::database::{MainT, UpdateT};
 use crate::settings::{UpdateState, SettingsUpdate, RankingRule};
 use crate::update::documents_addition::reindex_all_documents;
+use crate::update::facets_rebuild::apply_facets_rebuild;
 use crate::update::{next_update_id, Update};
 use crate::{store, MResult, Error};
 
@@ -109,6 +110,36 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
+    match settings.filterable_attributes {
+        UpdateState::Update(attrs) => {
+            let mut attribute_ids = Vec::new();
+            for name in attrs {
+                attribute_ids.push(schema.insert(&name)?);
+            }
+            let filterable_attributes = SetBuf::from_dirty(attribute_ids);
+            index.main.put_filterable_attributes(writer, &filterable_attributes)?;
+        },
+        UpdateState::Clear => {
+            index.main.delete_filterable_attributes(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.default_attributes_to_highlight {
+        UpdateState::Update(attrs) => {
+            let mut attribute_ids = Vec::new();
+            for name in attrs {
+                attribute_ids.push(schema.insert(&name)?);
+            }
+            let default_attributes_to_highlight = SetBuf::from_dirty(attribute_ids);
+            index.main.put_default_attributes_to_highlight(writer, &default_attributes_to_highlight)?;
+        },
+        UpdateState::Clear => {
+            index.main.delete_default_attributes_to_highlight(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
     index.main.put_schema(writer, &schema)?;
 
     match settings.stop_words {
@@ -131,8 +162,63 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
+    match settings.locale {
+        UpdateState::Update(locale) => index.main.put_locale(writer, &locale)?,
+        UpdateState::Clear => { index.main.delete_locale(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.payload_size_limit {
+        UpdateState::Update(limit) => index.main.put_payload_size_limit(writer, limit)?,
+        UpdateState::Clear => { index.main.delete_payload_size_limit(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.default_hits_per_page {
+        UpdateState::Update(limit) => index.main.put_default_hits_per_page(writer, limit)?,
+        UpdateState::Clear => { index.main.delete_default_hits_per_page(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.min_query_length {
+        UpdateState::Update(length) => index.main.put_min_query_length(writer, length)?,
+        UpdateState::Clear => { index.main.delete_min_query_length(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    // the facet values must be indexed again with the new normalization steps
+    match settings.facet_normalization {
+        UpdateState::Update(steps) => {
+            index.main.put_facet_normalization(writer, &steps)?;
+            must_reindex = true;
+        },
+        UpdateState::Clear => {
+            index.main.delete_facet_normalization(writer)?;
+            must_reindex = true;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    // the facet values skipped, or kept, by the previous length must be indexed again
+    let mut must_rebuild_facets = false;
+    let max_facet_value_length = index.main.max_facet_value_length(writer)?;
+    match settings.max_facet_value_length {
+        UpdateState::Update(length) if max_facet_value_length != Some(length) => {
+            index.main.put_max_facet_value_length(writer, length)?;
+            must_rebuild_facets = true;
+        },
+        UpdateState::Clear if max_facet_value_length.is_some() => {
+            index.main.delete_max_facet_value_length(writer)?;
+            must_rebuild_facets = true;
+        },
+        _ => (),
+    }
+
+    // the facets are indexed again along with the documents
     if must_reindex {
         reindex_all_documents(writer, index)?;
+    } else if must_rebuild_facets {
+        apply_facets_rebuild(writer, index)?;
     }
 
     Ok(())
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use meilisearch_core::{Database, DatabaseOptions};
 use sha2::Digest;
 
+use crate::helpers::facet_cache::FacetCache;
 use crate::index_update_callback;
 use crate::option::Opt;
 
@@ -28,6 +29,15 @@ pub struct DataInner {
     pub api_keys: ApiKeys,
     pub server_pid: u32,
     pub http_payload_size_limit: usize,
+    pub lenient_search_parameters: bool,
+    pub max_facet_filters: Option<usize>,
+    pub lenient_facet_filters: bool,
+    pub max_facets_distribution: Option<usize>,
+    pub skip_unreadable_documents: bool,
+    pub max_query_terms: Option<usize>,
+    pub distinct_scan_limit: Option<usize>,
+    pub disable_sort_fast_path: bool,
+    pub facet_cache: Arc<FacetCache>,
 }
 
 #[derive(Clone)]
@@ -65,6 +75,15 @@ impl Data {
         };
 
         let http_payload_size_limit = opt.http_payload_size_limit;
+        let lenient_search_parameters = opt.lenient_search_parameters;
+        let max_facet_filters = opt.max_facet_filters;
+        let lenient_facet_filters = opt.lenient_facet_filters;
+        let max_facets_distribution = opt.max_facets_distribution;
+        let skip_unreadable_documents = opt.skip_unreadable_documents;
+        let max_query_terms = opt.max_query_terms;
+        // a limit of zero would never return any hit, it disables the limit instead
+        let distinct_scan_limit = opt.distinct_scan_limit.filter(|limit| *limit != 0);
+        let disable_sort_fast_path = opt.disable_sort_fast_path;
 
         let db = Arc::new(Database::open_or_create(opt.db_path, db_opt)?);
 
@@ -82,6 +101,15 @@ impl Data {
             api_keys,
             server_pid,
             http_payload_size_limit,
+            lenient_search_parameters,
+            max_facet_filters,
+            lenient_facet_filters,
+            max_facets_distribution,
+            skip_unreadable_documents,
+            max_query_terms,
+            distinct_scan_limit,
+            disable_sort_fast_path,
+            facet_cache: Arc::new(FacetCache::default()),
         };
 
         let data = Data {
@@ -3,7 +3,7 @@ use std::fmt;
 
 use actix_http::ResponseBuilder;
 use actix_web as aweb;
-use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::error::{BlockingError, JsonPayloadError, QueryPayloadError};
 use actix_web::http::StatusCode;
 use serde_json::json;
 
@@ -11,7 +11,7 @@ use meilisearch_error::{ErrorCode, Code};
 
 #[derive(Debug)]
 pub struct ResponseError {
-    inner: Box<dyn ErrorCode>,
+    inner: Box<dyn ErrorCode + Send>,
 }
 
 impl error::Error for ResponseError {}
@@ -114,7 +114,7 @@ impl fmt::Display for FacetCountError {
         use FacetCountError::*;
 
         match self {
-            AttributeNotSet(attr) => write!(f, "attribute {} is not set as facet", attr),
+            AttributeNotSet(attr) => write!(f, "attribute {} is not set as facet, add it to the attributes for faceting to get its distribution", attr),
             SyntaxError(msg) => write!(f, "syntax error: {}", msg),
             UnexpectedToken { expected, found } => write!(f, "unexpected {} found, expected {:?}", found, expected),
             NoFacetSet => write!(f, "can't perform facet count, as no facet is set"),
@@ -242,6 +242,15 @@ impl From<FacetCountError> for ResponseError {
     }
 }
 
+impl From<BlockingError<ResponseError>> for ResponseError {
+    fn from(err: BlockingError<ResponseError>) -> ResponseError {
+        match err {
+            BlockingError::Error(err) => err,
+            BlockingError::Canceled => Error::internal("the blocking operation has been canceled").into(),
+        }
+    }
+}
+
 impl From<JsonPayloadError> for Error {
     fn from(err: JsonPayloadError) -> Error {
         match err {
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use indexmap::IndexMap;
+
+/// The maximum number of facets distributions kept, the cache is emptied when it is full.
+const MAX_FACET_CACHE_ENTRIES: usize = 10_000;
+
+type FacetDistribution = IndexMap<String, usize>;
+
+/// Caches the facets distributions of the search requests, a distribution is identified by
+/// the index, the signature of the parameters selecting the hits and the facet name.
+///
+/// The distributions are stored with the version of the index they have been computed
+/// with, they are never returned for another version.
+#[derive(Default)]
+pub struct FacetCache {
+    entries: Mutex<HashMap<(String, u64, String), (i64, FacetDistribution)>>,
+}
+
+impl FacetCache {
+    /// drops the cached distributions of the index, they are outdated after a write.
+    pub fn invalidate(&self, index_uid: &str) {
+        self.entries.lock().unwrap().retain(|(uid, _, _), _| uid != index_uid);
+    }
+
+    /// returns the distributions of all the given facets, or nothing if one of them is
+    /// missing or has been computed with another version of the index.
+    pub fn get(
+        &self,
+        index_uid: &str,
+        signature: u64,
+        version: i64,
+        facets: &[String],
+    ) -> Option<BTreeMap<String, FacetDistribution>> {
+        let entries = self.entries.lock().unwrap();
+        facets
+            .iter()
+            .map(|facet| {
+                let key = (index_uid.to_string(), signature, facet.clone());
+                match entries.get(&key) {
+                    Some((entry_version, distribution)) if *entry_version == version => {
+                        Some((facet.clone(), distribution.clone()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// stores the distributions computed with the given version of the index.
+    pub fn insert(
+        &self,
+        index_uid: &str,
+        signature: u64,
+        version: i64,
+        distributions: &BTreeMap<String, FacetDistribution>,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() + distributions.len() > MAX_FACET_CACHE_ENTRIES {
+            entries.clear();
+        }
+        for (facet, distribution) in distributions {
+            let key = (index_uid.to_string(), signature, facet.clone());
+            entries.insert(key, (version, distribution.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn facet_cache_reuse_and_invalidation() {
+        let cache = FacetCache::default();
+        let facets = vec!["color".to_string()];
+
+        let mut distributions = BTreeMap::new();
+        let mut colors = IndexMap::new();
+        colors.insert("blue".to_string(), 28);
+        colors.insert("green".to_string(), 25);
+        distributions.insert("color".to_string(), colors);
+
+        assert!(cache.get("test", 42, 1, &facets).is_none());
+        cache.insert("test", 42, 1, &distributions);
+
+        // an identical request reuses the distribution
+        assert_eq!(cache.get("test", 42, 1, &facets), Some(distributions.clone()));
+        // another request, facet or index is not cached
+        assert!(cache.get("test", 43, 1, &facets).is_none());
+        assert!(cache.get("test", 42, 1, &["gender".to_string()]).is_none());
+        assert!(cache.get("other", 42, 1, &facets).is_none());
+
+        // a write on the index changes its version
+        assert!(cache.get("test", 42, 2, &facets).is_none());
+
+        // a write on another index keeps the distribution
+        cache.invalidate("other");
+        assert!(cache.get("test", 42, 1, &facets).is_some());
+
+        cache.invalidate("test");
+        assert!(cache.get("test", 42, 1, &facets).is_none());
+    }
+}
@@ -1,23 +1,33 @@
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::cmp::{Ordering, Reverse};
+use std::convert::TryFrom;
+use std::fmt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
 use std::time::Instant;
 
-use indexmap::IndexMap;
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc::Sender;
+use futures::executor::block_on;
+use futures::SinkExt;
+use indexmap::{IndexMap, IndexSet};
 use log::error;
-use meilisearch_core::{Filter, MainReader};
-use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::{geo_point, Filter, MainReader, GEO_ATTRIBUTE};
+use meilisearch_core::facets::{FacetFilter, FacetStats};
 use meilisearch_core::criterion::*;
-use meilisearch_core::settings::RankingRule;
-use meilisearch_core::{Highlight, Index, RankedMap};
-use meilisearch_schema::{FieldId, Schema};
-use meilisearch_tokenizer::is_cjk;
+use meilisearch_core::settings::{Locale, RankingRule, DEFAULT_RANKING_RULES};
+use meilisearch_core::{Document, DocumentId, Highlight, Index, PlaceholderSortRule, RankedMap};
+use meilisearch_schema::{FieldId, IndexedPos, Schema};
+use meilisearch_tokenizer::{is_cjk, split_query_string, Tokenizer};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use siphasher::sip::SipHasher;
 use slice_group_by::GroupBy;
 
 use crate::error::{Error, ResponseError};
+use crate::helpers::score_expression::ScoreExpression;
 
 pub trait IndexSearchExt {
     fn new_search(&self, query: Option<String>) -> SearchBuilder;
@@ -33,10 +43,54 @@ impl IndexSearchExt for Index {
             attributes_to_crop: None,
             attributes_to_retrieve: None,
             attributes_to_highlight: None,
+            attribute_aliases: None,
             filters: None,
             matches: false,
             facet_filters: None,
             facets: None,
+            facets_pivot: None,
+            facet_stats: None,
+            locale: None,
+            include_primary_key: false,
+            sticky_facets: false,
+            snippet: false,
+            relevance_cutoff_sensitivity: None,
+            show_ranking_score: false,
+            ranking_score_threshold: None,
+            exhaustive_nb_hits: false,
+            within_document_ids: None,
+            join_filter: None,
+            highlight_whole_words: false,
+            highlight_format: HighlightFormat::Tags,
+            highlight_numbers: false,
+            warnings: Vec::new(),
+            sort: None,
+            nulls_order: None,
+            relevance_preset: None,
+            show_matched_terms: false,
+            show_version: false,
+            keep_stop_words: false,
+            highlight_merge_distance: None,
+            max_highlighted_matches: None,
+            debug: false,
+            suggest_query: false,
+            ranking_rules: None,
+            skip_unreadable_documents: false,
+            typo_summary: false,
+            crop_trim: true,
+            snippet_format: SnippetFormat::Text,
+            facet_values_order: FacetValuesOrder::Count,
+            max_values_per_facet: None,
+            matches_format: MatchesFormat::Object,
+            max_query_terms: None,
+            distinct_scan_limit: None,
+            sort_fast_path: true,
+            cancelled: None,
+            ids_only: false,
+            field_types: false,
+            score_expression: None,
+            hits_sender: None,
+            geo_distances: GeoDistances::default(),
         }
     }
 }
@@ -49,10 +103,55 @@ pub struct SearchBuilder<'a> {
     attributes_to_crop: Option<HashMap<String, usize>>,
     attributes_to_retrieve: Option<HashSet<String>>,
     attributes_to_highlight: Option<HashSet<String>>,
+    attribute_aliases: Option<HashMap<String, String>>,
     filters: Option<String>,
     matches: bool,
     facet_filters: Option<FacetFilter>,
-    facets: Option<Vec<(FieldId, String)>>
+    facets: Option<Vec<(FieldId, String)>>,
+    facets_pivot: Option<(FieldId, FieldId)>,
+    facet_stats: Option<Vec<String>>,
+    locale: Option<Locale>,
+    include_primary_key: bool,
+    sticky_facets: bool,
+    snippet: bool,
+    relevance_cutoff_sensitivity: Option<f64>,
+    show_ranking_score: bool,
+    ranking_score_threshold: Option<f64>,
+    exhaustive_nb_hits: bool,
+    within_document_ids: Option<Vec<String>>,
+    join_filter: Option<(Index, String)>,
+    highlight_whole_words: bool,
+    highlight_format: HighlightFormat,
+    highlight_numbers: bool,
+    warnings: Vec<String>,
+    sort: Option<Vec<SortRule>>,
+    nulls_order: Option<NullsOrder>,
+    relevance_preset: Option<RelevancePreset>,
+    show_matched_terms: bool,
+    show_version: bool,
+    keep_stop_words: bool,
+    highlight_merge_distance: Option<usize>,
+    max_highlighted_matches: Option<usize>,
+    debug: bool,
+    suggest_query: bool,
+    ranking_rules: Option<Vec<RankingRule>>,
+    skip_unreadable_documents: bool,
+    typo_summary: bool,
+    crop_trim: bool,
+    snippet_format: SnippetFormat,
+    facet_values_order: FacetValuesOrder,
+    max_values_per_facet: Option<usize>,
+    matches_format: MatchesFormat,
+    max_query_terms: Option<usize>,
+    distinct_scan_limit: Option<usize>,
+    sort_fast_path: bool,
+    cancelled: Option<Arc<AtomicBool>>,
+    ids_only: bool,
+    field_types: bool,
+    score_expression: Option<ScoreExpression>,
+    hits_sender: Option<Sender<SearchHit>>,
+    /// the distances to the point of the first geo sort rule, computed while ranking
+    geo_distances: GeoDistances,
 }
 
 impl<'a> SearchBuilder<'a> {
@@ -92,6 +191,12 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    /// renames the attributes of the hits, in the documents and their formatted version.
+    pub fn attribute_aliases(&mut self, value: HashMap<String, String>) -> &SearchBuilder {
+        self.attribute_aliases = Some(value);
+        self
+    }
+
     pub fn filters(&mut self, value: String) -> &SearchBuilder {
         self.filters = Some(value);
         self
@@ -107,34 +212,433 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
-    pub fn search(self, reader: &MainReader) -> Result<SearchResult, ResponseError> {
-        let schema = self
-            .index
-            .main
-            .schema(reader)?
-            .ok_or(Error::internal("missing schema"))?;
+    pub fn facets_pivot(&mut self, first: FieldId, second: FieldId) -> &SearchBuilder {
+        self.facets_pivot = Some((first, second));
+        self
+    }
+
+    pub fn facet_stats(&mut self, attributes: Vec<String>) -> &SearchBuilder {
+        self.facet_stats = Some(attributes);
+        self
+    }
+
+    pub fn locale(&mut self, value: Locale) -> &SearchBuilder {
+        self.locale = Some(value);
+        self
+    }
+
+    pub fn include_primary_key(&mut self) -> &SearchBuilder {
+        self.include_primary_key = true;
+        self
+    }
+
+    pub fn sticky_facets(&mut self) -> &SearchBuilder {
+        self.sticky_facets = true;
+        self
+    }
+
+    pub fn snippet(&mut self) -> &SearchBuilder {
+        self.snippet = true;
+        self
+    }
+
+    pub fn auto_relevance_cutoff(&mut self, sensitivity: f64) -> &SearchBuilder {
+        self.relevance_cutoff_sensitivity = Some(sensitivity);
+        self
+    }
+
+    pub fn show_ranking_score(&mut self) -> &SearchBuilder {
+        self.show_ranking_score = true;
+        self
+    }
+
+    pub fn ranking_score_threshold(&mut self, value: f64) -> &SearchBuilder {
+        self.ranking_score_threshold = Some(value);
+        self
+    }
+
+    /// re-ranks the best candidates by the value of the expression, the highest values first.
+    pub fn score_expression(&mut self, value: ScoreExpression) -> &SearchBuilder {
+        self.score_expression = Some(value);
+        self
+    }
+
+    pub fn exhaustive_nb_hits(&mut self) -> &SearchBuilder {
+        self.exhaustive_nb_hits = true;
+        self
+    }
+
+    pub fn within_document_ids(&mut self, value: Vec<String>) -> &SearchBuilder {
+        self.within_document_ids = Some(value);
+        self
+    }
+
+    /// only returns the documents whose id is a value of the `key` attribute of the documents
+    /// of the joined index, every document of the joined index is read for each search.
+    pub fn join_filter(&mut self, joined_index: Index, key: String) -> &SearchBuilder {
+        self.join_filter = Some((joined_index, key));
+        self
+    }
+
+    pub fn highlight_whole_words(&mut self) -> &SearchBuilder {
+        self.highlight_whole_words = true;
+        self
+    }
+
+    /// the numbers matching the query are highlighted in the formatted document, as strings.
+    pub fn highlight_numbers(&mut self) -> &SearchBuilder {
+        self.highlight_numbers = true;
+        self
+    }
+
+    pub fn add_warning(&mut self, warning: String) -> &SearchBuilder {
+        self.warnings.push(warning);
+        self
+    }
+
+    pub fn sort(&mut self, value: Vec<SortRule>) -> &SearchBuilder {
+        self.sort = Some(value);
+        self
+    }
+
+    /// sets where the documents without value for a sort attribute are placed.
+    pub fn nulls_order(&mut self, value: NullsOrder) -> &SearchBuilder {
+        self.nulls_order = Some(value);
+        self
+    }
+
+    pub fn show_matched_terms(&mut self) -> &SearchBuilder {
+        self.show_matched_terms = true;
+        self
+    }
+
+    /// the hits are returned with the version of their document.
+    pub fn show_version(&mut self) -> &SearchBuilder {
+        self.show_version = true;
+        self
+    }
+
+    /// the stop words of the index are searched like the other words of the query.
+    pub fn keep_stop_words(&mut self) -> &SearchBuilder {
+        self.keep_stop_words = true;
+        self
+    }
+
+    pub fn highlight_merge_distance(&mut self, value: usize) -> &SearchBuilder {
+        self.highlight_merge_distance = Some(value);
+        self
+    }
+
+    /// only the first matches of each attribute are highlighted, the others are left plain.
+    pub fn max_highlighted_matches(&mut self, value: usize) -> &SearchBuilder {
+        self.max_highlighted_matches = Some(value);
+        self
+    }
+
+    pub fn debug(&mut self) -> &SearchBuilder {
+        self.debug = true;
+        self
+    }
+
+    /// suggests a corrected query when some words of the query are unknown to the index.
+    pub fn suggest_query(&mut self) -> &SearchBuilder {
+        self.suggest_query = true;
+        self
+    }
+
+    /// overrides the ranking rules of the index for this search only.
+    pub fn ranking_rules(&mut self, value: Vec<RankingRule>) -> &SearchBuilder {
+        self.ranking_rules = Some(value);
+        self
+    }
+
+    /// replaces the built-in ranking rules of the index by the ones of the preset.
+    pub fn relevance_preset(&mut self, value: RelevancePreset) -> &SearchBuilder {
+        self.relevance_preset = Some(value);
+        self
+    }
+
+    /// skips the hits whose document cannot be read instead of failing the whole search.
+    pub fn skip_unreadable_documents(&mut self) -> &SearchBuilder {
+        self.skip_unreadable_documents = true;
+        self
+    }
+
+    pub fn typo_summary(&mut self) -> &SearchBuilder {
+        self.typo_summary = true;
+        self
+    }
+
+    /// returns the type of the values of every attribute of the hits, the schema does not type
+    /// the attributes and their type is inferred from the values of the returned hits.
+    pub fn field_types(&mut self) -> &SearchBuilder {
+        self.field_types = true;
+        self
+    }
+
+    /// sets whether the whitespaces surrounding the cropped texts are removed, they are by default.
+    pub fn crop_trim(&mut self, value: bool) -> &SearchBuilder {
+        self.crop_trim = value;
+        self
+    }
+
+    /// only returns the most frequent values of each facet of the distribution.
+    pub fn max_values_per_facet(&mut self, value: usize) -> &SearchBuilder {
+        self.max_values_per_facet = Some(value);
+        self
+    }
+
+    pub fn facet_values_order(&mut self, value: FacetValuesOrder) -> &SearchBuilder {
+        self.facet_values_order = value;
+        self
+    }
+
+    pub fn matches_format(&mut self, value: MatchesFormat) -> &SearchBuilder {
+        self.matches_format = value;
+        self
+    }
+
+    /// sets whether the cropped attributes are split around their first match.
+    pub fn snippet_format(&mut self, value: SnippetFormat) -> &SearchBuilder {
+        self.snippet_format = value;
+        self
+    }
+
+    pub fn highlight_format(&mut self, value: HighlightFormat) -> &SearchBuilder {
+        self.highlight_format = value;
+        self
+    }
+
+    /// only the first terms of the query are searched, the response reports the truncation.
+    pub fn max_query_terms(&mut self, value: usize) -> &SearchBuilder {
+        self.max_query_terms = Some(value);
+        self
+    }
+
+    /// bounds the documents read by the distinct rule, the number of hits is then not exhaustive.
+    pub fn distinct_scan_limit(&mut self, value: usize) -> &SearchBuilder {
+        self.distinct_scan_limit = Some(value);
+        self
+    }
+
+    /// the placeholder searches sorted on ranked attributes skip the ranking rules.
+    pub fn sort_fast_path(&mut self, value: bool) -> &SearchBuilder {
+        self.sort_fast_path = value;
+        self
+    }
+
+    /// the search stops with an error once the flag is raised, like when the client disconnects.
+    pub fn cancellation_flag(&mut self, cancelled: Arc<AtomicBool>) -> &SearchBuilder {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// sends every hit as soon as it is formatted instead of returning it, the search waits
+    /// while the channel is full and is cancelled when the receiver is dropped.
+    pub fn hits_sender(&mut self, sender: Sender<SearchHit>) -> &SearchBuilder {
+        self.hits_sender = Some(sender);
+        self
+    }
+
+    /// only the ids of the hits are returned, the documents are neither retrieved nor formatted.
+    pub fn ids_only(&mut self) -> &SearchBuilder {
+        self.ids_only = true;
+        self
+    }
+
+    /// returns a result without any hit, as if no document matched the search.
+    fn empty_result(self, reader: &MainReader) -> Result<SearchResult, ResponseError> {
+        Ok(SearchResult {
+            hits: Vec::new(),
+            offset: self.offset,
+            limit: self.limit,
+            nb_hits: 0,
+            exhaustive_nb_hits: true,
+            processing_time_ms: 0,
+            query: self.query.unwrap_or_default(),
+            facets_distribution: self.facets.as_ref().map(|facets| {
+                facets.iter().map(|(_, name)| (name.clone(), IndexMap::new())).collect()
+            }),
+            exhaustive_facets_count: self.facets.as_ref().map(|_| true),
+            facets_percentages: None,
+            facets_pivot: None,
+            facet_stats: self.facet_stats.as_ref().map(|_| BTreeMap::new()),
+            warnings: self.warnings,
+            debug: None,
+            suggested_query: None,
+            query_truncated: false,
+            query_terms: None,
+            typo_summary: None,
+            index_updated_at: self.index.main.updated_at(reader)?,
+            ids: if self.ids_only { Some(Vec::new()) } else { None },
+            field_types: if self.field_types { Some(BTreeMap::new()) } else { None },
+        })
+    }
+
+    pub fn search(mut self, reader: &MainReader) -> Result<SearchResult, ResponseError> {
+        let schema = match self.index.main.schema(reader)? {
+            Some(schema) => schema,
+            // An index that has not been populated yet may not have a schema,
+            // there is nothing to search in it so we return an empty result.
+            None => return self.empty_result(reader),
+        };
+
+        // the queries shorter than the minimum length of the index would match most of the
+        // documents, they are not run
+        let query_length = self.query.as_ref().map_or(0, |query| query.trim().chars().count());
+        if let Some(min_length) = self.index.main.min_query_length(reader)? {
+            if query_length > 0 && query_length < min_length {
+                self.warnings.push(format!(
+                    "the query is shorter than the {} characters required by the index, it has not been run",
+                    min_length,
+                ));
+                return self.empty_result(reader);
+            }
+        }
+
+        if let (Some(query), false) = (&self.query, self.keep_stop_words) {
+            let stop_words = self.index.main.stop_words(reader)?;
+            let mut words = split_query_string(query).map(str::to_lowercase).peekable();
+            if words.peek().is_some() && words.all(|word| stop_words.contains(&word)) {
+                self.warnings.push("the query only contains stop words".to_string());
+            }
+        }
 
         let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
 
-        // Change criteria
-        let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema)? {
-            Some(criteria) => self.index.query_builder_with_criteria(criteria),
-            None => self.index.query_builder(),
+        // the placeholder searches sorted on ranked attributes do not need the ranking rules,
+        // the filtered documents are directly sorted on the values of these attributes
+        let mut query_builder = match self.placeholder_sort(&schema) {
+            Some(rules) => {
+                let mut query_builder = self.index.query_builder();
+                query_builder.set_placeholder_sort(Some(rules));
+                query_builder.set_sort_missing_first(self.missing_first());
+                query_builder
+            }
+            None => {
+                let mut criteria_warnings = Vec::new();
+                let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema, &mut criteria_warnings)? {
+                    Some(criteria) => self.index.query_builder_with_criteria(criteria),
+                    None => self.index.query_builder(),
+                };
+                self.warnings.extend(criteria_warnings);
+                // the criteria do not rank the placeholder searches, their documents
+                // are sorted on the sort rules and the asc and desc ranking rules
+                if let Some(rules) = self.placeholder_ranked_sort(reader, &schema)? {
+                    query_builder.set_placeholder_sort(Some(rules));
+                    query_builder.set_sort_missing_first(self.missing_first());
+                }
+                query_builder
+            }
         };
+        query_builder.set_geo_distances(Some(self.geo_distances.clone()));
 
-        if let Some(filter_expression) = &self.filters {
-            let filter = Filter::parse(filter_expression, &schema)?;
+        // the `attribute:word` terms only match the documents containing the word in the attribute
+        let (query, scoped_terms) = match &self.query {
+            Some(query) => {
+                let (query, scoped_terms) = split_scoped_terms(query, &schema);
+                (Some(query), scoped_terms)
+            }
+            None => (None, Vec::new()),
+        };
+
+        let mut query_terms = None;
+        let query = match (query, self.max_query_terms) {
+            (Some(query), Some(max)) => {
+                let (query, nb_terms) = truncate_query(&query, max);
+                query_terms = nb_terms;
+                Some(query)
+            }
+            (query, _) => query,
+        };
+        let mut scoped_docids: Option<Vec<_>> = None;
+        for (attribute, word) in &scoped_terms {
+            let docids = self.index.word_attribute_docids(reader, word, *attribute)?.into_vec();
+            scoped_docids = Some(match scoped_docids.take() {
+                Some(mut previous) => {
+                    previous.retain(|id| docids.binary_search(id).is_ok());
+                    previous
+                }
+                None => docids,
+            });
+        }
+
+        // the external ids that do not match any document are ignored
+        let within_docids = match &self.within_document_ids {
+            Some(document_ids) => {
+                let mut docids = HashSet::with_capacity(document_ids.len());
+                for document_id in document_ids {
+                    if let Some(docid) = self.index.main.external_to_internal_docid(reader, document_id)? {
+                        docids.insert(docid);
+                    }
+                }
+                Some(docids)
+            }
+            None => None,
+        };
+
+        let joined_docids = match &self.join_filter {
+            Some((joined_index, key)) => Some(self.joined_docids(reader, joined_index, key)?),
+            None => None,
+        };
+
+        let filter = match &self.filters {
+            Some(filter_expression) => {
+                let filterable = self.index.main.filterable_attributes(reader)?;
+                Some(Filter::parse(filter_expression, &schema, filterable.as_deref())?)
+            }
+            None => None,
+        };
+        let geo_radius_center = filter.as_ref().and_then(Filter::geo_radius_center);
+
+        // counts the documents tested by the filter, only in debug mode
+        let new_counter = || if self.debug { Some(Arc::new(AtomicUsize::new(0))) } else { None };
+        let filter_evaluations = new_counter();
+        let filter_passed = new_counter();
+
+        if within_docids.is_some() || joined_docids.is_some() || scoped_docids.is_some() || filter.is_some() {
             let index = &self.index;
+            let ranked_map = &ranked_map;
+            let evaluations = filter_evaluations.clone();
+            let passed = filter_passed.clone();
             query_builder.with_filter(move |id| {
-                let reader = &reader;
-                let filter = &filter;
-                match filter.test(reader, index, id) {
-                    Ok(res) => res,
-                    Err(e) => {
-                        log::warn!("unexpected error during filtering: {}", e);
-                        false
+                if let Some(evaluations) = &evaluations {
+                    evaluations.fetch_add(1, AtomicOrdering::Relaxed);
+                }
+
+                if let Some(docids) = &within_docids {
+                    if !docids.contains(&id) {
+                        return false;
+                    }
+                }
+
+                if let Some(docids) = &joined_docids {
+                    if !docids.contains(&id) {
+                        return false;
+                    }
+                }
+
+                if let Some(docids) = &scoped_docids {
+                    if docids.binary_search(&id).is_err() {
+                        return false;
                     }
                 }
+
+                let reader = &reader;
+                let accepted = match &filter {
+                    Some(filter) => match filter.test(reader, index, ranked_map, id) {
+                        Ok(res) => res,
+                        Err(e) => {
+                            log::warn!("unexpected error during filtering: {}", e);
+                            false
+                        }
+                    },
+                    None => true,
+                };
+                if let (true, Some(passed)) = (accepted, &passed) {
+                    passed.fetch_add(1, AtomicOrdering::Relaxed);
+                }
+                accepted
             });
         }
 
@@ -150,16 +654,113 @@ impl<'a> SearchBuilder<'a> {
                     _ => None,
                 }
             });
+            query_builder.set_distinct_scan_limit(self.distinct_scan_limit);
         }
 
         query_builder.set_facet_filter(self.facet_filters);
         query_builder.set_facets(self.facets);
+        query_builder.set_max_facet_values(self.max_values_per_facet);
+        query_builder.set_facets_pivot(self.facets_pivot);
+
+        // the variables of the expression are read from the ranked map when re-ranking the hits,
+        // the expression would have no value for any candidate on an attribute that is not ranked
+        if let Some(expression) = &self.score_expression {
+            for name in expression.attributes() {
+                match schema.id(name) {
+                    Some(field_id) if schema.is_ranked(field_id) => (),
+                    _ => {
+                        let message = format!("attribute `{}` is not a ranked attribute", name);
+                        return Err(Error::bad_parameter("rankingScoreExpression", message).into());
+                    }
+                }
+            }
+        }
+
+        // the attributes without numeric value among the candidates are reported once the stats are computed
+        if let Some(attributes) = &self.facet_stats {
+            let fields = attributes
+                .iter()
+                .filter_map(|name| schema.id(name).map(|field_id| (field_id, name.clone())))
+                .collect();
+            query_builder.set_facet_stats(Some(fields));
+        }
 
+        // the locale given with the query takes precedence over the index one
+        let locale = match self.locale {
+            Some(locale) => Some(locale),
+            None => self.index.main.locale(reader)?,
+        };
+        query_builder.set_locale(locale);
+        query_builder.set_remove_stop_words(!self.keep_stop_words);
+        query_builder.set_sticky_facets(self.sticky_facets);
+        query_builder.set_exhaustive_nb_hits(self.exhaustive_nb_hits);
+        query_builder.set_ranking_score_threshold(self.ranking_score_threshold);
+        query_builder.set_cancellation_flag(self.cancelled.clone());
+
+        let normalized_query = match &query {
+            Some(query) if self.debug => {
+                Some(query_builder.normalized_query(reader, query).map_err(Error::search_documents)?)
+            }
+            _ => None,
+        };
+
+        query_builder.set_suggest_query(self.suggest_query);
+
+        // the expression re-ranks the best candidates and the cutoff looks for a drop of relevance
+        // from the first one, the requested page is taken from them afterward. One more candidate
+        // is fetched for the cutoff to find a drop right after the requested page.
+        let range = match (&self.score_expression, self.relevance_cutoff_sensitivity) {
+            (None, None) => self.offset..(self.offset + self.limit),
+            (Some(_), _) => 0..(self.offset + self.limit + 1).max(RERANKED_CANDIDATES),
+            (None, Some(_)) => 0..(self.offset + self.limit + 1),
+        };
+
+        let range_start = range.start;
         let start = Instant::now();
-        let result = query_builder.query(reader, self.query.as_deref(), self.offset..(self.offset + self.limit));
-        let search_result = result.map_err(Error::search_documents)?;
+        let result = query_builder.query(reader, query.as_deref(), range);
+        let mut search_result = result.map_err(Error::search_documents)?;
         let time_ms = start.elapsed().as_millis() as usize;
 
+        if let Some(expression) = &self.score_expression {
+            rerank_documents(&mut search_result.documents, expression, &schema, &ranked_map);
+        }
+
+        // the hits after a drop of relevance are not counted either, they are all known
+        if let Some(sensitivity) = self.relevance_cutoff_sensitivity {
+            let cutoff = relevance_cutoff(&search_result.documents, sensitivity);
+            if cutoff < search_result.documents.len() {
+                search_result.documents.truncate(cutoff);
+                search_result.nb_hits = cutoff;
+                search_result.exhaustive_nb_hit = true;
+            }
+        }
+
+        if range_start != self.offset {
+            search_result.documents.drain(..self.offset.min(search_result.documents.len()));
+            search_result.documents.truncate(self.limit);
+        }
+
+        // the ranking rules on attributes without any numeric value have no effect on the hits
+        if !search_result.documents.is_empty() {
+            let ranking_rules = match &self.ranking_rules {
+                Some(ranking_rules) => ranking_rules.clone(),
+                None => self.index.main.ranking_rules(reader)?.unwrap_or_default(),
+            };
+            for rule in ranking_rules {
+                // the rules on unknown or unranked attributes are already reported by the criteria builder
+                let field_id = match rule.field().and_then(|name| schema.id(name)) {
+                    Some(field_id) if schema.is_ranked(field_id) => field_id,
+                    _ => continue,
+                };
+                if search_result.documents.iter().all(|doc| ranked_map.get(doc.id, field_id).is_none()) {
+                    self.warnings.push(format!(
+                        "ranking rule `{}` has no effect, the attribute has no numeric value in the hits",
+                        rule,
+                    ));
+                }
+            }
+        }
+
         let mut all_attributes: HashSet<&str> = HashSet::new();
         let mut all_formatted: HashSet<&str> = HashSet::new();
 
@@ -187,15 +788,64 @@ impl<'a> SearchBuilder<'a> {
             },
         }
 
+        // the primary key is always retrieved when asked, even if it is not displayed
+        let primary_key = if self.include_primary_key { schema.primary_key() } else { None };
+        if let Some(primary_key) = primary_key {
+            all_attributes.insert(primary_key);
+        }
+
         let mut hits = Vec::with_capacity(self.limit);
+        let mut ids = Vec::new();
+        let mut nb_skipped = 0;
+        let mut typo_summary = TypoSummary::default();
+        // the types are gathered while the hits are formatted, they may be sent right away
+        let mut field_types = if self.field_types { Some(BTreeMap::new()) } else { None };
+        let primary_key_id = schema.primary_key().and_then(|name| schema.id(name));
+
+        // the hits give their distance to the point of the first geo sort rule, or else
+        // to the center of the geo radius filter
+        let geo_origin = self.sort.iter().flatten().find_map(|rule| match rule {
+            SortRule::GeoAsc(point) | SortRule::GeoDesc(point) => Some(*point),
+            _ => None,
+        });
+        let geo_origin = geo_origin.or(geo_radius_center);
+        let geo_field = geo_origin.and_then(|origin| Some((schema.id(GEO_ATTRIBUTE)?, origin)));
+        let geo_distances = self.geo_distances.clone();
+        let geo_distances = geo_distances.borrow();
+
         for doc in search_result.documents {
-            let mut document: IndexMap<String, Value> = self
+            if self.cancelled.as_ref().map_or(false, |c| c.load(AtomicOrdering::Relaxed)) {
+                return Err(meilisearch_core::Error::Cancelled.into());
+            }
+
+            // only the primary key of the document is read
+            if self.ids_only {
+                let id = match primary_key_id {
+                    Some(field_id) => self.index.document_attribute(reader, doc.id, field_id)?,
+                    None => None,
+                };
+                ids.push(id.unwrap_or(Value::Null));
+                continue;
+            }
+
+            let document = self
                 .index
                 .document(reader, Some(&all_attributes), doc.id)
-                .map_err(|e| Error::retrieve_document(doc.id.0, e))?
-                .ok_or(Error::internal(
+                .map_err(|e| Error::retrieve_document(doc.id.0, e))
+                .and_then(|document| document.ok_or(Error::internal(
                     "Impossible to retrieve the document; Corrupted data",
-                ))?;
+                )));
+
+            let mut document: IndexMap<String, Value> = match document {
+                Ok(document) => document,
+                Err(e) if self.skip_unreadable_documents => {
+                    error!("document {} skipped from the search results; {}", doc.id.0, e);
+                    self.warnings.push(format!("the document {} cannot be read and has been skipped", doc.id.0));
+                    nb_skipped += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
 
             let mut formatted = document.iter()
                 .filter(|(key, _)| all_formatted.contains(key.as_str()))
@@ -204,65 +854,367 @@ impl<'a> SearchBuilder<'a> {
 
             let mut matches = doc.highlights.clone();
 
+            // Selects the best snippet among the fields to crop
+            let snippet = match &self.attributes_to_crop {
+                Some(fields) if self.snippet => select_snippet(&document, &matches, &schema, fields, self.crop_trim),
+                _ => None,
+            };
+
             // Crops fields if needed
             if let Some(fields) = &self.attributes_to_crop {
-                crop_document(&mut formatted, &mut matches, &schema, fields);
+                crop_document(&mut formatted, &mut matches, &schema, fields, self.crop_trim);
+                // the parts are not highlighted, the match is already set apart
+                if self.snippet_format == SnippetFormat::Parts {
+                    split_cropped_document(&mut formatted, &matches, &schema, fields);
+                }
             }
 
             // Transform to readable matches
             if let Some(attributes_to_highlight) = &self.attributes_to_highlight {
-                let matches = calculate_matches(
+                let mut matches = calculate_matches(
                     &matches,
                     self.attributes_to_highlight.clone(),
                     &schema,
                 );
-                formatted = calculate_highlights(&formatted, &matches, attributes_to_highlight);
+                if let Some(distance) = self.highlight_merge_distance {
+                    merge_adjacent_matches(&mut matches, distance);
+                }
+                if self.highlight_numbers {
+                    stringify_matched_numbers(&mut formatted, &matches, attributes_to_highlight);
+                }
+                formatted = match self.highlight_format {
+                    HighlightFormat::Tags => calculate_highlights(
+                        &formatted,
+                        &matches,
+                        attributes_to_highlight,
+                        self.highlight_whole_words,
+                        self.max_highlighted_matches,
+                    ),
+                    HighlightFormat::Segments => calculate_highlight_segments(
+                        &formatted,
+                        &matches,
+                        attributes_to_highlight,
+                        self.highlight_whole_words,
+                        self.max_highlighted_matches,
+                    ),
+                };
             }
 
+            let matched_terms = if self.show_matched_terms {
+                Some(matched_terms(self.index, reader, &schema, &doc)?)
+            } else {
+                None
+            };
+
             let matches_info = if self.matches {
-                Some(calculate_matches(&matches, self.attributes_to_retrieve.clone(), &schema))
+                let matches = calculate_matches(&matches, self.attributes_to_retrieve.clone(), &schema);
+                Some(MatchesInfo::new(matches, self.matches_format))
             } else {
                 None
             };
 
             if let Some(attributes_to_retrieve) = &self.attributes_to_retrieve {
-                document.retain(|key, _| attributes_to_retrieve.contains(&key.to_string()))
+                document.retain(|key, _| {
+                    attributes_to_retrieve.contains(&key.to_string()) || Some(key.as_str()) == primary_key
+                })
+            }
+
+            let version = if self.show_version {
+                self.index.document_version(reader, doc.id)?
+            } else {
+                None
+            };
+
+            // the distances computed while ranking are not computed again
+            let geo_distance = match geo_field {
+                Some((field_id, origin)) => {
+                    let distance = match geo_distances.get(&doc.id) {
+                        Some(distance) => *distance,
+                        None => self.index
+                            .document_attribute::<Value>(reader, doc.id, field_id)?
+                            .and_then(|value| geo_point(&value))
+                            .map(|point| haversine_distance(origin, point)),
+                    };
+                    distance.map(|distance| distance.round() as u64)
+                }
+                None => None,
+            };
+
+            // the query words are the first word of each normalized group, in the query order
+            let term_typos = normalized_query.as_ref().map(|normalized| {
+                normalized.iter()
+                    .filter_map(|alternatives| alternatives.first())
+                    .zip(doc.query_typos.iter().chain(std::iter::repeat(&None)))
+                    .map(|(term, typos)| TermTypos { term: term.clone(), typos: *typos })
+                    .collect()
+            });
+
+            if let Some(aliases) = &self.attribute_aliases {
+                document = rename_attributes(document, aliases);
+                formatted = rename_attributes(formatted, aliases);
             }
 
             let hit = SearchHit {
                 document,
                 formatted,
                 matches_info,
+                snippet,
+                ranking_score: if self.show_ranking_score { Some(doc.score) } else { None },
+                matched_terms,
+                version,
+                term_typos,
+                geo_distance,
+                document_id: doc.id,
             };
 
-            hits.push(hit);
+            if doc.typo_corrected {
+                typo_summary.typo_corrected += 1;
+            } else {
+                typo_summary.exact += 1;
+            }
+            if let Some(types) = &mut field_types {
+                add_field_types(types, &hit.document);
+            }
+            match &mut self.hits_sender {
+                Some(sender) => {
+                    if block_on(sender.send(hit)).is_err() {
+                        return Err(meilisearch_core::Error::Cancelled.into());
+                    }
+                }
+                None => hits.push(hit),
+            }
         }
 
-        let results = SearchResult {
+        if let (Some(attributes), Some(stats)) = (&self.facet_stats, &search_result.facet_stats) {
+            for name in attributes.iter().filter(|name| !stats.contains_key(*name)) {
+                self.warnings.push(format!("attribute `{}` in facetStats has no numeric value", name));
+            }
+        }
+
+        let mut results = SearchResult {
             hits,
             offset: self.offset,
             limit: self.limit,
-            nb_hits: search_result.nb_hits,
+            nb_hits: search_result.nb_hits.saturating_sub(nb_skipped),
             exhaustive_nb_hits: search_result.exhaustive_nb_hit,
             processing_time_ms: time_ms,
             query: self.query.unwrap_or_default(),
-            facets_distribution: search_result.facets,
+            facets_distribution: search_result.facets.map(|facets| sort_facets_distribution(facets, self.facet_values_order)),
             exhaustive_facets_count: search_result.exhaustive_facets_count,
+            facets_percentages: None,
+            facets_pivot: search_result.facets_pivot.map(|pivot| truncate_facets_pivot(pivot, self.max_values_per_facet)),
+            facet_stats: search_result.facet_stats.map(|stats| stats.into_iter().collect()),
+            warnings: self.warnings,
+            debug: if self.debug {
+                let count = |counter: Option<Arc<AtomicUsize>>| counter.map_or(0, |c| c.load(AtomicOrdering::Relaxed));
+                Some(SearchDebug {
+                    filter_evaluations: count(filter_evaluations),
+                    filter_passed: count(filter_passed),
+                    normalized_query,
+                })
+            } else {
+                None
+            },
+            suggested_query: search_result.suggested_query,
+            query_truncated: query_terms.is_some(),
+            query_terms,
+            typo_summary: if self.typo_summary { Some(typo_summary) } else { None },
+            index_updated_at: self.index.main.updated_at(reader)?,
+            ids: if self.ids_only { Some(ids) } else { None },
+            field_types,
         };
+        results.warn_overshooting_offset();
 
         Ok(results)
     }
 
+    /// resolves the documents whose id is a value of the `key` attribute of the documents of
+    /// the joined index, the values that are not the id of a document are ignored.
+    ///
+    /// The attribute is read from every document of the joined index, the cost of a search
+    /// grows with the size of the joined index. The facets store cannot be used instead: the
+    /// facet values are lowercased and only the strings are indexed, while the document ids
+    /// are case sensitive and can be numbers.
+    fn joined_docids(
+        &self,
+        reader: &MainReader,
+        joined_index: &Index,
+        key: &str,
+    ) -> Result<HashSet<DocumentId>, ResponseError> {
+        let joined_schema = joined_index.main.schema(reader)?.unwrap_or_default();
+        let field_id = match joined_schema.id(key) {
+            Some(field_id) => field_id,
+            None => {
+                let message = format!("attribute `{}` not found in the joined index", key);
+                return Err(Error::bad_parameter("joinFilter", message).into());
+            }
+        };
+
+        let mut docids = HashSet::new();
+        for joined_docid in joined_index.main.internal_docids(reader)?.iter() {
+            let values = match joined_index.document_attribute::<Value>(reader, *joined_docid, field_id)? {
+                Some(Value::Array(values)) => values,
+                Some(value) => vec![value],
+                None => continue,
+            };
+            for value in values {
+                let external_id = match value {
+                    Value::String(s) => s,
+                    Value::Number(n) => n.to_string(),
+                    _ => continue,
+                };
+                if let Some(docid) = self.index.main.external_to_internal_docid(reader, &external_id)? {
+                    docids.insert(docid);
+                }
+            }
+        }
+        Ok(docids)
+    }
+
+    /// estimates the cost of the search without running it, the query, the filters and the
+    /// criteria are resolved but the documents are neither ranked nor filtered.
+    pub fn explain(mut self, reader: &MainReader) -> Result<SearchExplanation, ResponseError> {
+        let schema = self.index.main.schema(reader)?.unwrap_or_default();
+        let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
+
+        let mut criteria_warnings = Vec::new();
+        let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema, &mut criteria_warnings)? {
+            Some(criteria) => self.index.query_builder_with_criteria(criteria),
+            None => self.index.query_builder(),
+        };
+        self.warnings.extend(criteria_warnings);
+
+        // the filter is only parsed, it is evaluated on every ranked document
+        if let Some(filter_expression) = &self.filters {
+            let filterable = self.index.main.filterable_attributes(reader)?;
+            Filter::parse(filter_expression, &schema, filterable.as_deref())?;
+        }
+
+        let locale = match self.locale {
+            Some(locale) => Some(locale),
+            None => self.index.main.locale(reader)?,
+        };
+        query_builder.set_locale(locale);
+        query_builder.set_remove_stop_words(!self.keep_stop_words);
+        query_builder.set_facet_filter(self.facet_filters.take());
+
+        let query = self.query.as_deref().filter(|query| !query.trim().is_empty());
+        let explanation = query_builder.explain(reader, query).map_err(Error::search_documents)?;
+
+        Ok(SearchExplanation {
+            query: self.query.unwrap_or_default(),
+            criteria: explanation.criteria,
+            filters: self.filters,
+            words_candidates: explanation.words_candidates.into_iter().collect(),
+            facet_candidates: explanation.facet_candidates,
+            nb_candidates: explanation.nb_candidates,
+            warnings: self.warnings,
+        })
+    }
+
+    /// returns the rules sorting a placeholder search, when all its sort rules are
+    /// on ranked attributes or on the distance to a point.
+    fn placeholder_sort(&self, schema: &Schema) -> Option<Vec<PlaceholderSortRule>> {
+        let is_placeholder = self.query.as_deref().map_or(true, |query| query.trim().is_empty());
+        match &self.sort {
+            Some(sort_rules) if self.sort_fast_path && is_placeholder => {
+                sort_rules.iter().map(|rule| placeholder_sort_rule(rule, schema)).collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// returns whether the documents without value for a sort attribute are placed first or last,
+    /// they are placed last when no nulls order is given.
+    fn missing_first(&self) -> Option<bool> {
+        self.nulls_order.map(|order| order == NullsOrder::First)
+    }
+
+    /// returns the rules sorting a placeholder search that cannot take the fast path, the
+    /// sort rules come first, followed by the asc and desc ranking rules.
+    fn placeholder_ranked_sort(&self, reader: &MainReader, schema: &Schema) -> Result<Option<Vec<PlaceholderSortRule>>, ResponseError> {
+        let is_placeholder = self.query.as_deref().map_or(true, |query| query.trim().is_empty());
+        // the documents sorted in advance with the ranking rules place the missing values last
+        if !is_placeholder || (self.sort.is_none() && self.nulls_order.is_none()) {
+            return Ok(None);
+        }
+        let sort_rules = self.sort.as_deref().unwrap_or_default();
+        let ranking_rules = match &self.ranking_rules {
+            Some(ranking_rules) => ranking_rules.clone(),
+            None => self.index.main.ranking_rules(reader)?.unwrap_or_default(),
+        };
+
+        let ranking_sort_rules = ranking_rules.into_iter().filter_map(|rule| match rule {
+            RankingRule::Asc(name) => Some(SortRule::Asc(name)),
+            RankingRule::Desc(name) => Some(SortRule::Desc(name)),
+            _ => None,
+        });
+
+        let rules = sort_rules
+            .iter()
+            .cloned()
+            .chain(ranking_sort_rules)
+            .filter_map(|rule| placeholder_sort_rule(&rule, schema))
+            .collect();
+        Ok(Some(rules))
+    }
+
+    /// returns the criteria built from the ranking rules and the sort rules, the ranking rules
+    /// that cannot be applied are reported in `warnings`.
     pub fn get_criteria(
         &self,
         reader: &MainReader,
         ranked_map: &'a RankedMap,
         schema: &Schema,
+        warnings: &mut Vec<String>,
     ) -> Result<Option<Criteria<'a>>, ResponseError> {
-        let ranking_rules = self.index.main.ranking_rules(reader)?;
+        let stored_ranking_rules = match (&self.ranking_rules, self.relevance_preset) {
+            (Some(ranking_rules), _) => Some(ranking_rules.clone()),
+            // the asc and desc rules of the index still apply after the ones of the preset
+            (None, Some(preset)) => {
+                let custom_rules = self.index.main.ranking_rules(reader)?.unwrap_or_default();
+                let custom_rules = custom_rules.into_iter().filter(|rule| rule.field().is_some());
+                Some(preset.ranking_rules().into_iter().chain(custom_rules).collect())
+            }
+            (None, None) => self.index.main.ranking_rules(reader)?,
+        };
+        let ranking_rules = match (stored_ranking_rules, &self.sort) {
+            (Some(ranking_rules), _) => Some(ranking_rules),
+            // the sort rules must be composed with the default ranking rules
+            (None, Some(_)) => Some(DEFAULT_RANKING_RULES.to_vec()),
+            (None, None) => None,
+        };
 
         if let Some(ranking_rules) = ranking_rules {
-            let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
+            let missing_first = self.missing_first();
+            let sort_rules = self.sort.as_deref().unwrap_or_default();
+            let mut builder = CriteriaBuilder::with_capacity(7 + sort_rules.len() + ranking_rules.len());
+
+            // the sort rules are applied in the requested order, before the ranking rules
+            let mut geo_distances = Some(self.geo_distances.clone());
+            for rule in sort_rules {
+                let result = match rule {
+                    SortRule::Asc(field) => SortByAttr::lower_is_better(&ranked_map, &schema, field)
+                        .map(|c| builder.push(c.with_missing_first(missing_first))),
+                    SortRule::Desc(field) => SortByAttr::higher_is_better(&ranked_map, &schema, field)
+                        .map(|c| builder.push(c.with_missing_first(missing_first))),
+                    SortRule::GeoAsc(point) | SortRule::GeoDesc(point) => {
+                        let criterion = match rule {
+                            SortRule::GeoAsc(_) => GeoDistance::nearest_first(self.index.documents_fields, &schema, *point),
+                            _ => GeoDistance::farthest_first(self.index.documents_fields, &schema, *point),
+                        };
+                        // the distances to the point of the first geo rule are returned with the hits
+                        criterion.map(|c| match geo_distances.take() {
+                            Some(distances) => builder.push(c.with_distances(distances)),
+                            None => builder.push(c),
+                        })
+                    }
+                };
+                if let Err(e) = result {
+                    return Err(Error::bad_parameter("sort", format!("{}: {}", rule, e)).into());
+                }
+            }
+
             for rule in ranking_rules {
                 match rule {
                     RankingRule::Typo => builder.push(Typo),
@@ -271,16 +1223,16 @@ impl<'a> SearchBuilder<'a> {
                     RankingRule::Attribute => builder.push(Attribute),
                     RankingRule::WordsPosition => builder.push(WordsPosition),
                     RankingRule::Exactness => builder.push(Exactness),
-                    RankingRule::Asc(field) => {
-                        match SortByAttr::lower_is_better(&ranked_map, &schema, &field) {
-                            Ok(rule) => builder.push(rule),
-                            Err(err) => error!("Error during criteria builder; {:?}", err),
+                    RankingRule::Asc(ref field) => {
+                        match SortByAttr::lower_is_better(&ranked_map, &schema, field) {
+                            Ok(rule) => builder.push(rule.with_missing_first(missing_first)),
+                            Err(err) => warnings.push(format!("ranking rule `{}` is ignored: {}", rule, err)),
                         }
                     }
-                    RankingRule::Desc(field) => {
-                        match SortByAttr::higher_is_better(&ranked_map, &schema, &field) {
-                            Ok(rule) => builder.push(rule),
-                            Err(err) => error!("Error during criteria builder; {:?}", err),
+                    RankingRule::Desc(ref field) => {
+                        match SortByAttr::higher_is_better(&ranked_map, &schema, field) {
+                            Ok(rule) => builder.push(rule.with_missing_first(missing_first)),
+                            Err(err) => warnings.push(format!("ranking rule `{}` is ignored: {}", rule, err)),
                         }
                     }
                 }
@@ -293,54 +1245,661 @@ impl<'a> SearchBuilder<'a> {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
-pub struct MatchPosition {
-    pub start: usize,
-    pub length: usize,
+/// returns the rule sorting the placeholder searches like the given sort rule, when its
+/// attribute is ranked or when it sorts on the distance to a point.
+fn placeholder_sort_rule(rule: &SortRule, schema: &Schema) -> Option<PlaceholderSortRule> {
+    let attribute = |name: &str, descending| match schema.id(name) {
+        Some(field_id) if schema.is_ranked(field_id) => Some(PlaceholderSortRule::Attribute(field_id, descending)),
+        _ => None,
+    };
+    let geo_distance = |point, descending| {
+        schema.id(GEO_ATTRIBUTE).map(|_| PlaceholderSortRule::GeoDistance(point, descending))
+    };
+
+    match rule {
+        SortRule::Asc(name) => attribute(name, false),
+        SortRule::Desc(name) => attribute(name, true),
+        SortRule::GeoAsc(point) => geo_distance(*point, false),
+        SortRule::GeoDesc(point) => geo_distance(*point, true),
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MatchPosition {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Ord for MatchPosition {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.start.cmp(&other.start) {
+            Ordering::Equal => self.length.cmp(&other.length),
+            _ => self.start.cmp(&other.start),
+        }
+    }
+}
+
+pub type HighlightInfos = HashMap<String, Value>;
+pub type MatchesInfos = HashMap<String, Vec<MatchPosition>>;
+// pub type RankingInfos = HashMap<String, u64>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub document: IndexMap<String, Value>,
+    #[serde(rename = "_formatted", skip_serializing_if = "IndexMap::is_empty")]
+    pub formatted: IndexMap<String, Value>,
+    #[serde(rename = "_matchesInfo", skip_serializing_if = "Option::is_none")]
+    pub matches_info: Option<MatchesInfo>,
+    #[serde(rename = "_snippet", skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<Snippet>,
+    #[serde(rename = "_rankingScore", skip_serializing_if = "Option::is_none")]
+    pub ranking_score: Option<f64>,
+    #[serde(rename = "_matchedTerms", skip_serializing_if = "Option::is_none")]
+    pub matched_terms: Option<BTreeSet<String>>,
+    #[serde(rename = "_version", skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// the number of typos each query word has been matched with, returned in debug mode
+    #[serde(rename = "_termTypos", skip_serializing_if = "Option::is_none")]
+    pub term_typos: Option<Vec<TermTypos>>,
+    /// the distance in meters between the `_geo` attribute and the point of the geo sort
+    #[serde(rename = "_geoDistance", skip_serializing_if = "Option::is_none")]
+    pub geo_distance: Option<u64>,
+    /// the internal id of the document, the hits of several searches are merged with it
+    #[serde(skip)]
+    pub document_id: DocumentId,
+}
+
+/// The matches of a hit, either grouped by attribute or as a flat list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MatchesInfo {
+    Object(MatchesInfos),
+    Array(Vec<AttributeMatch>),
+}
+
+impl MatchesInfo {
+    fn new(matches: MatchesInfos, format: MatchesFormat) -> MatchesInfo {
+        match format {
+            MatchesFormat::Object => MatchesInfo::Object(matches),
+            MatchesFormat::Array => {
+                let mut array: Vec<_> = matches
+                    .into_iter()
+                    .flat_map(|(attribute, positions)| {
+                        positions.into_iter().map(move |MatchPosition { start, length }| {
+                            AttributeMatch { attribute: attribute.clone(), start, length }
+                        })
+                    })
+                    .collect();
+                array.sort_unstable_by(|a, b| (&a.attribute, a.start, a.length).cmp(&(&b.attribute, b.start, b.length)));
+                MatchesInfo::Array(array)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeMatch {
+    pub attribute: String,
+    pub start: usize,
+    pub length: usize,
+}
+
+/// The representation of the matches of the hits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchesFormat {
+    /// the positions of the matches grouped by attribute
+    Object,
+    /// a list of the matches with their attribute
+    Array,
+}
+
+impl FromStr for MatchesFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "object" => Ok(MatchesFormat::Object),
+            "array" => Ok(MatchesFormat::Array),
+            _ => Err(format!("invalid matches format {:?}, expected `object` or `array`", s)),
+        }
+    }
+}
+
+/// Where the documents without value for a sort attribute are placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullsOrder {
+    /// before the documents with a value
+    First,
+    /// after the documents with a value
+    Last,
+}
+
+impl FromStr for NullsOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(NullsOrder::First),
+            "last" => Ok(NullsOrder::Last),
+            _ => Err(format!("invalid nulls order {:?}, expected `first` or `last`", s)),
+        }
+    }
+}
+
+/// A named sequence of the built-in ranking rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelevancePreset {
+    /// the documents containing the query words exactly and close to each other come first:
+    /// exactness, proximity, typo, words, attribute, wordsPosition
+    Precision,
+    /// the documents containing the most query words come first, even with typos:
+    /// words, typo, attribute, proximity, wordsPosition, exactness
+    Recall,
+}
+
+impl RelevancePreset {
+    pub fn ranking_rules(self) -> Vec<RankingRule> {
+        match self {
+            RelevancePreset::Precision => vec![
+                RankingRule::Exactness,
+                RankingRule::Proximity,
+                RankingRule::Typo,
+                RankingRule::Words,
+                RankingRule::Attribute,
+                RankingRule::WordsPosition,
+            ],
+            RelevancePreset::Recall => vec![
+                RankingRule::Words,
+                RankingRule::Typo,
+                RankingRule::Attribute,
+                RankingRule::Proximity,
+                RankingRule::WordsPosition,
+                RankingRule::Exactness,
+            ],
+        }
+    }
+}
+
+impl FromStr for RelevancePreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "precision" => Ok(RelevancePreset::Precision),
+            "recall" => Ok(RelevancePreset::Recall),
+            _ => Err(format!("invalid relevance preset {:?}, expected `precision` or `recall`", s)),
+        }
+    }
+}
+
+/// The representation of the cropped attributes of the formatted hits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnippetFormat {
+    /// the cropped text
+    Text,
+    /// the cropped text split into the text before the first match, the match and the text after it
+    Parts,
+}
+
+impl FromStr for SnippetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(SnippetFormat::Text),
+            "parts" => Ok(SnippetFormat::Parts),
+            _ => Err(format!("invalid snippet format {:?}, expected `text` or `parts`", s)),
+        }
+    }
+}
+
+/// The layout of the hits in the search response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultsFormat {
+    /// an object per hit
+    Hits,
+    /// the names of the fields of the hits and a row of values per hit
+    Columnar,
+    /// a server-sent event per hit, followed by an event with the other fields of the result
+    EventStream,
+}
+
+impl FromStr for ResultsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hits" => Ok(ResultsFormat::Hits),
+            "columnar" => Ok(ResultsFormat::Columnar),
+            "eventStream" => Ok(ResultsFormat::EventStream),
+            _ => Err(format!("invalid format {:?}, expected `hits`, `columnar` or `eventStream`", s)),
+        }
+    }
+}
+
+/// The representation of the highlighted attributes of the formatted hits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HighlightFormat {
+    /// the matches are surrounded by `<em>` tags
+    Tags,
+    /// a list of the segments of the attribute flagged when they match
+    Segments,
+}
+
+impl FromStr for HighlightFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tags" => Ok(HighlightFormat::Tags),
+            "segments" => Ok(HighlightFormat::Segments),
+            _ => Err(format!("invalid highlight format {:?}, expected `tags` or `segments`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub attribute: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub hits: Vec<SearchHit>,
+    pub offset: usize,
+    pub limit: usize,
+    pub nb_hits: usize,
+    pub exhaustive_nb_hits: bool,
+    pub processing_time_ms: usize,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets_distribution: Option<BTreeMap<String, IndexMap<String, usize>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhaustive_facets_count: Option<bool>,
+    /// the share of the hits having each value of the facets distribution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets_percentages: Option<BTreeMap<String, IndexMap<String, f64>>>,
+    /// the number of hits for each combination of the values of the two pivot facets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets_pivot: Option<IndexMap<String, IndexMap<String, usize>>>,
+    /// the min, max and average values of the requested numeric facets over the hits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_stats: Option<BTreeMap<String, FacetStats>>,
+    /// non fatal issues encountered while processing the search request
+    #[serde(rename = "_warnings", skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(rename = "_debug", skip_serializing_if = "Option::is_none")]
+    pub debug: Option<SearchDebug>,
+    /// the query with its unknown words replaced by the closest words of the index,
+    /// only suggested when the query finds few documents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_query: Option<String>,
+    /// whether the query had more terms than the maximum allowed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub query_truncated: bool,
+    /// the number of terms searched when the query has been truncated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_terms: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typo_summary: Option<TypoSummary>,
+    /// the last time the index has been updated, the cached results can be invalidated after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_updated_at: Option<DateTime<Utc>>,
+    /// the ids of the hits in ranked order, returned instead of the hits when only the ids are requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<Value>>,
+    /// the type of every attribute of the hits, inferred from their values
+    #[serde(rename = "_fieldTypes", skip_serializing_if = "Option::is_none")]
+    pub field_types: Option<BTreeMap<String, FieldType>>,
+}
+
+impl SearchResult {
+    /// serializes the result with its hits transposed into columns, the fields are listed
+    /// in the order they first appear in the hits and are null in the hits missing them.
+    pub fn to_columnar(&self) -> Result<Value, ResponseError> {
+        let mut result = serde_json::to_value(self).map_err(Error::internal)?;
+        let hits = match result.get_mut("hits").map(Value::take) {
+            Some(Value::Array(hits)) => hits,
+            _ => Vec::new(),
+        };
+
+        let mut fields = IndexSet::new();
+        for hit in &hits {
+            if let Value::Object(hit) = hit {
+                fields.extend(hit.keys().cloned());
+            }
+        }
+
+        let rows: Vec<Vec<Value>> = hits
+            .into_iter()
+            .map(|mut hit| {
+                fields.iter().map(|field| hit.get_mut(field).map(Value::take).unwrap_or(Value::Null)).collect()
+            })
+            .collect();
+
+        let fields: Vec<_> = fields.into_iter().collect();
+        result["hits"] = json!({ "fields": fields, "rows": rows });
+        Ok(result)
+    }
+
+    /// divides the count of every value of the facets distribution by the number of hits,
+    /// the values keep the order of the distribution.
+    pub fn compute_facets_percentages(&mut self) {
+        let nb_hits = self.nb_hits;
+        self.facets_percentages = self.facets_distribution.as_ref().map(|distribution| {
+            distribution.iter().map(|(name, values)| {
+                let percentages = values.iter().map(|(value, count)| {
+                    let percentage = if nb_hits == 0 { 0.0 } else { *count as f64 / nb_hits as f64 };
+                    (value.clone(), percentage)
+                }).collect();
+                (name.clone(), percentages)
+            }).collect()
+        });
+    }
+
+    /// warns when the offset skips all the hits, distinguishing a page past the end from no results.
+    pub fn warn_overshooting_offset(&mut self) {
+        if self.nb_hits > 0 && self.offset >= self.nb_hits {
+            self.warnings.push(format!(
+                "the offset {} is past the end of the {} hits found",
+                self.offset, self.nb_hits,
+            ));
+        }
+    }
+}
+
+/// The order of the values in the facets distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FacetValuesOrder {
+    /// by descending count, then by value
+    Count,
+    /// by value
+    Alpha,
+}
+
+impl FromStr for FacetValuesOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "count" => Ok(FacetValuesOrder::Count),
+            "alpha" => Ok(FacetValuesOrder::Alpha),
+            _ => Err(format!("invalid facet values order {:?}, expected `count` or `alpha`", s)),
+        }
+    }
+}
+
+/// Orders the facets by name and their values by the given order, so that
+/// the distribution is always serialized the same way.
+fn sort_facets_distribution(
+    facets: HashMap<String, HashMap<String, usize>>,
+    order: FacetValuesOrder,
+) -> BTreeMap<String, IndexMap<String, usize>> {
+    facets
+        .into_iter()
+        .map(|(name, values)| {
+            let mut values: Vec<_> = values.into_iter().collect();
+            match order {
+                FacetValuesOrder::Count => values.sort_unstable_by(|(a, ca), (b, cb)| cb.cmp(ca).then_with(|| a.cmp(b))),
+                FacetValuesOrder::Alpha => values.sort_unstable_by(|(a, _), (b, _)| a.cmp(b)),
+            }
+            (name, values.into_iter().collect())
+        })
+        .collect()
+}
+
+/// Orders both dimensions of the facets pivot by frequency and keeps at most `max_values` values
+/// for each of them, the first dimension values are ranked by the total count of their combinations.
+fn truncate_facets_pivot(
+    pivot: HashMap<String, HashMap<String, usize>>,
+    max_values: Option<usize>,
+) -> IndexMap<String, IndexMap<String, usize>> {
+    fn most_frequent<T>(
+        mut values: Vec<(String, T)>,
+        max_values: Option<usize>,
+        count: impl Fn(&T) -> usize,
+    ) -> Vec<(String, T)> {
+        values.sort_unstable_by(|(a, ca), (b, cb)| count(cb).cmp(&count(ca)).then_with(|| a.cmp(b)));
+        if let Some(max) = max_values {
+            values.truncate(max);
+        }
+        values
+    }
+
+    let first_values = pivot.into_iter().collect();
+    most_frequent(first_values, max_values, |counts: &HashMap<String, usize>| counts.values().sum())
+        .into_iter()
+        .map(|(value, counts)| {
+            let counts = most_frequent(counts.into_iter().collect(), max_values, |count| *count);
+            (value, counts.into_iter().collect())
+        })
+        .collect()
+}
+
+/// Diagnostic informations about a search, returned when the `debug` parameter is set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDebug {
+    /// the number of times the filter has been evaluated on a document
+    pub filter_evaluations: usize,
+    /// the number of evaluations that accepted the document
+    pub filter_passed: usize,
+    /// the words searched for the query, every word followed by its synonyms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_query: Option<Vec<Vec<String>>>,
+}
+
+/// The number of typos of the best match of a query word in a hit,
+/// nothing when the hit does not contain the word.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermTypos {
+    pub term: String,
+    pub typos: Option<u8>,
+}
+
+/// The type of the values of an attribute of the hits.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    Null,
+    /// the hits have values of different types
+    Mixed,
+}
+
+impl FieldType {
+    fn of(value: &Value) -> FieldType {
+        match value {
+            Value::String(_) => FieldType::String,
+            Value::Number(_) => FieldType::Number,
+            Value::Bool(_) => FieldType::Boolean,
+            Value::Array(_) => FieldType::Array,
+            Value::Object(_) => FieldType::Object,
+            Value::Null => FieldType::Null,
+        }
+    }
+}
+
+/// The schema does not type the attributes, their type is the one of their values in the
+/// hits. The null values are ignored unless the attribute only has null values.
+fn add_field_types(types: &mut BTreeMap<String, FieldType>, document: &IndexMap<String, Value>) {
+    for (name, value) in document {
+        let field_type = FieldType::of(value);
+        let entry = types.entry(name.clone()).or_insert(field_type);
+        *entry = match (*entry, field_type) {
+            (current, FieldType::Null) => current,
+            (FieldType::Null, other) => other,
+            (current, other) if current == other => current,
+            _ => FieldType::Mixed,
+        };
+    }
+}
+
+/// The estimated cost of a search, returned by the explain route.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchExplanation {
+    pub query: String,
+    /// the criteria ranking the documents, in order
+    pub criteria: Vec<String>,
+    /// the filter evaluated on the ranked documents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<String>,
+    /// the number of documents containing each word of the query, or one of its alternatives,
+    /// the documents to rank are the union of these documents
+    pub words_candidates: IndexMap<String, usize>,
+    /// the number of documents matching the facet filters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facet_candidates: Option<usize>,
+    /// the number of documents to rank, before the filter is applied
+    pub nb_candidates: usize,
+    /// non fatal issues encountered while processing the request
+    #[serde(rename = "_warnings", skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// The number of returned hits matching the query as written and of the ones only
+/// matching it once corrected by typos, returned when the `typoSummary` parameter is set.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypoSummary {
+    pub exact: usize,
+    pub typo_corrected: usize,
+}
+
+/// A rule sorting the search results, either on a ranked attribute or on the distance
+/// between the `_geo` attribute of the documents and a point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortRule {
+    Asc(String),
+    Desc(String),
+    GeoAsc((f64, f64)),
+    GeoDesc((f64, f64)),
+}
+
+impl FromStr for SortRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, order) = match s.rfind(':') {
+            Some(index) => (s[..index].trim(), s[index + 1..].trim()),
+            None => return Err(format!("invalid sort rule {:?}, expected `attribute:asc` or `attribute:desc`", s)),
+        };
+
+        if target.starts_with("_geoPoint(") && target.ends_with(')') {
+            let coordinates: Vec<_> = target["_geoPoint(".len()..target.len() - 1]
+                .split(',')
+                .map(|c| c.trim().parse::<f64>())
+                .collect();
+            let point = match coordinates.as_slice() {
+                [Ok(lat), Ok(lng)] => (*lat, *lng),
+                _ => return Err(format!("invalid geo point {:?}, expected `_geoPoint(lat, lng)`", target)),
+            };
+            return match order {
+                "asc" => Ok(SortRule::GeoAsc(point)),
+                "desc" => Ok(SortRule::GeoDesc(point)),
+                _ => Err(format!("invalid sort order {:?}, expected `asc` or `desc`", order)),
+            };
+        }
+
+        match order {
+            "asc" => Ok(SortRule::Asc(target.to_string())),
+            "desc" => Ok(SortRule::Desc(target.to_string())),
+            _ => Err(format!("invalid sort order {:?}, expected `asc` or `desc`", order)),
+        }
+    }
+}
+
+impl fmt::Display for SortRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortRule::Asc(field) => write!(f, "{}:asc", field),
+            SortRule::Desc(field) => write!(f, "{}:desc", field),
+            SortRule::GeoAsc((lat, lng)) => write!(f, "_geoPoint({}, {}):asc", lat, lng),
+            SortRule::GeoDesc((lat, lng)) => write!(f, "_geoPoint({}, {}):desc", lat, lng),
+        }
+    }
+}
+
+/// Keeps the first `max` terms of the query, the number of terms kept is only
+/// returned when the query has been truncated.
+fn truncate_query(query: &str, max: usize) -> (String, Option<usize>) {
+    if max == 0 || Tokenizer::new(query).nth(max).is_none() {
+        return (query.to_string(), None);
+    }
+
+    let last = Tokenizer::new(query).nth(max - 1).unwrap();
+    let start = query.char_indices().nth(last.char_index).map_or(query.len(), |(i, _)| i);
+    // the last term kept is not the last term of the query, it is not a prefix
+    let mut truncated = query[..start + last.word.len()].to_string();
+    truncated.push(' ');
+    (truncated, Some(max))
+}
+
+/// Extracts the `attribute:word` terms of the query that scope a word to a searchable
+/// attribute, the words are kept in the returned query without their attribute.
+fn split_scoped_terms(query: &str, schema: &Schema) -> (String, Vec<(IndexedPos, String)>) {
+    let mut scoped_terms = Vec::new();
+    let words: Vec<&str> = query
+        .split_whitespace()
+        .map(|fragment| {
+            let mut split = fragment.splitn(2, ':');
+            let (name, word) = (split.next().unwrap_or_default(), split.next().unwrap_or_default());
+            match schema.id(name).and_then(|id| schema.is_indexed(id)) {
+                Some(attribute) if !word.is_empty() => {
+                    scoped_terms.push((*attribute, word.to_lowercase()));
+                    word
+                }
+                _ => fragment,
+            }
+        })
+        .collect();
+
+    if scoped_terms.is_empty() {
+        return (query.to_string(), scoped_terms);
+    }
+
+    // the last word is only a prefix when the query does not end with a space
+    let mut scoped_query = words.join(" ");
+    if query.ends_with(char::is_whitespace) {
+        scoped_query.push(' ');
+    }
+    (scoped_query, scoped_terms)
 }
 
-impl Ord for MatchPosition {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.start.cmp(&other.start) {
-            Ordering::Equal => self.length.cmp(&other.length),
-            _ => self.start.cmp(&other.start),
+/// splits a list of sort rules on the commas that are not part of a geo point.
+pub fn split_sort_rules(s: &str) -> Vec<&str> {
+    let mut rules = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                rules.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => (),
         }
     }
+    rules.push(&s[start..]);
+    rules.into_iter().map(str::trim).filter(|r| !r.is_empty()).collect()
 }
 
-pub type HighlightInfos = HashMap<String, Value>;
-pub type MatchesInfos = HashMap<String, Vec<MatchPosition>>;
-// pub type RankingInfos = HashMap<String, u64>;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchHit {
-    #[serde(flatten)]
-    pub document: IndexMap<String, Value>,
-    #[serde(rename = "_formatted", skip_serializing_if = "IndexMap::is_empty")]
-    pub formatted: IndexMap<String, Value>,
-    #[serde(rename = "_matchesInfo", skip_serializing_if = "Option::is_none")]
-    pub matches_info: Option<MatchesInfos>,
-}
-
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SearchResult {
-    pub hits: Vec<SearchHit>,
-    pub offset: usize,
-    pub limit: usize,
-    pub nb_hits: usize,
-    pub exhaustive_nb_hits: bool,
-    pub processing_time_ms: usize,
-    pub query: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub facets_distribution: Option<HashMap<String, HashMap<String, usize>>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub exhaustive_facets_count: Option<bool>,
+fn is_word_component(c: &char) -> bool {
+    c.is_alphanumeric() && !is_cjk(*c)
 }
 
 /// returns the start index and the length on the crop.
 fn aligned_crop(text: &str, match_index: usize, context: usize) -> (usize, usize) {
-    let is_word_component = |c: &char| c.is_alphanumeric() && !is_cjk(*c);
 
     let word_end_index = |mut index| {
         if text.chars().nth(index - 1).map_or(false, |c| is_word_component(&c)) {
@@ -366,30 +1925,34 @@ fn aligned_crop(text: &str, match_index: usize, context: usize) -> (usize, usize
     (start, end - start)
 }
 
+/// Crops the text around the first match, the surrounding whitespaces of the cropped
+/// text are removed when `trim` is set.
 fn crop_text(
     text: &str,
     matches: impl IntoIterator<Item = Highlight>,
     context: usize,
+    trim: bool,
 ) -> (String, Vec<Highlight>) {
     let mut matches = matches.into_iter().peekable();
 
     let char_index = matches.peek().map(|m| m.char_index as usize).unwrap_or(0);
     let (start, count) = aligned_crop(text, char_index, context);
 
-    // TODO do something about double allocation
-    let text = text
-        .chars()
-        .skip(start)
-        .take(count)
-        .collect::<String>()
-        .trim()
-        .to_string();
+    let cropped = text.chars().skip(start).take(count).collect::<String>();
+    // the matches are shifted by the whitespaces removed at the start of the text
+    let (text, shift) = if trim {
+        let trimmed = cropped.trim_start();
+        let shift = cropped[..cropped.len() - trimmed.len()].chars().count();
+        (trimmed.trim_end().to_string(), shift)
+    } else {
+        (cropped, 0)
+    };
 
     // update matches index to match the new cropped text
     let matches = matches
         .take_while(|m| (m.char_index as usize) + (m.char_length as usize) <= start + count)
         .map(|m| Highlight {
-            char_index: m.char_index - start as u16,
+            char_index: m.char_index - (start + shift) as u16,
             ..m
         })
         .collect();
@@ -402,6 +1965,7 @@ fn crop_document(
     matches: &mut Vec<Highlight>,
     schema: &Schema,
     fields: &HashMap<String, usize>,
+    trim: bool,
 ) {
     matches.sort_unstable_by_key(|m| (m.char_index, m.char_length));
 
@@ -418,7 +1982,7 @@ fn crop_document(
 
         if let Some(Value::String(ref mut original_text)) = document.get_mut(field) {
             let (cropped_text, cropped_matches) =
-                crop_text(original_text, selected_matches, *length);
+                crop_text(original_text, selected_matches, *length, trim);
 
             *original_text = cropped_text;
 
@@ -428,6 +1992,152 @@ fn crop_document(
     }
 }
 
+/// Replaces the cropped attributes by the text before their first match, the match itself and
+/// the text after it. The text of an attribute without match is entirely in the first part.
+fn split_cropped_document(
+    document: &mut IndexMap<String, Value>,
+    matches: &[Highlight],
+    schema: &Schema,
+    fields: &HashMap<String, usize>,
+) {
+    for field in fields.keys() {
+        let attribute = match schema.id(field) {
+            Some(attribute) => attribute,
+            None => continue,
+        };
+
+        if let Some(value) = document.get_mut(field) {
+            if let Value::String(text) = value {
+                let first_match = matches
+                    .iter()
+                    .filter(|m| FieldId::new(m.attribute) == attribute)
+                    .min_by_key(|m| m.char_index);
+                let (start, length) = first_match.map_or((text.chars().count(), 0), |m| {
+                    (m.char_index as usize, m.char_length as usize)
+                });
+
+                let before: String = text.chars().take(start).collect();
+                let matched: String = text.chars().skip(start).take(length).collect();
+                let after: String = text.chars().skip(start + length).collect();
+                *value = json!({ "before": before, "match": matched, "after": after });
+            }
+        }
+    }
+}
+
+/// The number of best candidates re-ranked by a score expression, when the requested page
+/// does not go further.
+const RERANKED_CANDIDATES: usize = 100;
+
+/// Sorts the documents by the value of the expression, the highest values first, the documents
+/// for which the expression has no value keep their order after the others.
+fn rerank_documents(
+    documents: &mut Vec<Document>,
+    expression: &ScoreExpression,
+    schema: &Schema,
+    ranked_map: &RankedMap,
+) {
+    let mut scored: Vec<_> = documents
+        .drain(..)
+        .map(|doc| {
+            let value = |name: &str| {
+                let field_id = schema.id(name)?;
+                ranked_map.get(doc.id, field_id)?.as_f64()
+            };
+            (expression.evaluate(doc.score, &value), doc)
+        })
+        .collect();
+
+    scored.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => b.partial_cmp(a).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+
+    documents.extend(scored.into_iter().map(|(_, doc)| doc));
+}
+
+/// Returns the number of documents to keep before the first large drop of relevance, a drop is
+/// large when the score decreases by at least `1 - sensitivity` of the previous score.
+fn relevance_cutoff(documents: &[Document], sensitivity: f64) -> usize {
+    documents
+        .windows(2)
+        .position(|w| {
+            let (previous, next) = (w[0].score, w[1].score);
+            next < previous && previous - next >= previous * (1.0 - sensitivity)
+        })
+        .map_or(documents.len(), |i| i + 1)
+}
+
+/// Selects the densest cluster of matches among all the fields to crop and returns the
+/// cropped text of its field. Every match is the start of a candidate window, the window
+/// containing the most distinct matched words wins, ties are broken by the attribute order
+/// of the searchable attributes, so that repeating a word in a less important attribute does
+/// not outweigh it, and then by the number of matches and the shortest span between them.
+fn select_snippet(
+    document: &IndexMap<String, Value>,
+    matches: &[Highlight],
+    schema: &Schema,
+    fields: &HashMap<String, usize>,
+    trim: bool,
+) -> Option<Snippet> {
+    let mut best = None;
+
+    for (field, length) in fields {
+        let attribute = match schema.id(field) {
+            Some(attribute) => attribute,
+            None => continue,
+        };
+
+        let text = match document.get(field) {
+            Some(Value::String(text)) => text,
+            _ => continue,
+        };
+
+        let priority = schema.is_indexed(attribute).map_or(u16::max_value(), |pos| pos.0);
+
+        let mut field_matches: Vec<_> = matches
+            .iter()
+            .filter(|m| FieldId::new(m.attribute) == attribute)
+            .cloned()
+            .collect();
+        field_matches.sort_unstable_by_key(|m| (m.char_index, m.char_length));
+
+        for (i, first) in field_matches.iter().enumerate() {
+            let window_end = first.char_index as usize + length;
+            let window: Vec<_> = field_matches[i..]
+                .iter()
+                .take_while(|m| (m.char_index as usize) + (m.char_length as usize) <= window_end)
+                .collect();
+
+            let words: HashSet<String> = window
+                .iter()
+                .map(|m| {
+                    text.chars()
+                        .skip(m.char_index as usize)
+                        .take(m.char_length as usize)
+                        .flat_map(char::to_lowercase)
+                        .collect()
+                })
+                .collect();
+            let span = window
+                .last()
+                .map_or(0, |m| (m.char_index - first.char_index + m.char_length) as usize);
+
+            let score = (words.len(), Reverse(priority), window.len(), Reverse(span));
+            if best.as_ref().map_or(true, |(best_score, ..)| score > *best_score) {
+                best = Some((score, field, text, *length, field_matches[i..].to_vec()));
+            }
+        }
+    }
+
+    best.map(|(_, field, text, length, matches)| {
+        let (text, _) = crop_text(text, matches, length, trim);
+        Snippet { attribute: field.clone(), text }
+    })
+}
+
 fn calculate_matches(
     matches: &[Highlight],
     attributes_to_retrieve: Option<HashSet<String>>,
@@ -466,39 +2176,265 @@ fn calculate_matches(
     matches_result
 }
 
+/// Merges the matches separated by at most `distance` characters into a single match, the
+/// matches must be sorted, as returned by `calculate_matches`.
+fn merge_adjacent_matches(matches: &mut MatchesInfos, distance: usize) {
+    for positions in matches.values_mut() {
+        let mut merged: Vec<MatchPosition> = Vec::with_capacity(positions.len());
+        for m in positions.drain(..) {
+            match merged.last_mut() {
+                Some(last) if m.start <= last.start + last.length + distance => {
+                    let end = (last.start + last.length).max(m.start + m.length);
+                    last.length = end - last.start;
+                }
+                _ => merged.push(m),
+            }
+        }
+        *positions = merged;
+    }
+}
+
+/// The name of the attribute holding the text formatted by `format_text`.
+const PREVIEW_ATTRIBUTE: &str = "text";
+
+/// Formats a text the way the `_formatted` field of a hit containing it would be. The words
+/// equal to a query word, or starting with the last one, are matched, typos are not tolerated.
+pub fn format_text(
+    text: &str,
+    query: &str,
+    stop_words: &HashSet<String>,
+    crop_length: Option<usize>,
+    highlight: bool,
+    whole_words: bool,
+) -> Result<Value, ResponseError> {
+    let query_words: Vec<String> = split_query_string(query)
+        .map(str::to_lowercase)
+        .filter(|word| !stop_words.contains(word))
+        .collect();
+    // the last word is a prefix, unless the query ends with a space
+    let last_is_prefix = !query.ends_with(char::is_whitespace);
+
+    let mut matches: Vec<Highlight> = Tokenizer::new(text)
+        .filter_map(|token| {
+            let word = token.word.to_lowercase();
+            let length = query_words.iter().enumerate().find_map(|(i, query_word)| {
+                let is_prefix = last_is_prefix && i + 1 == query_words.len();
+                if word == *query_word || (is_prefix && word.starts_with(query_word.as_str())) {
+                    Some(query_word.chars().count())
+                } else {
+                    None
+                }
+            })?;
+            // the highlights can't point after the first 65535 characters of the text
+            let char_index = u16::try_from(token.char_index).ok()?;
+            let char_length = u16::try_from(length).ok()?;
+            Some(Highlight { attribute: 0, char_index, char_length })
+        })
+        .collect();
+
+    let mut schema = Schema::new();
+    schema.insert_and_index(PREVIEW_ATTRIBUTE).map_err(Error::internal)?;
+
+    let mut document = IndexMap::new();
+    document.insert(PREVIEW_ATTRIBUTE.to_string(), Value::String(text.to_string()));
+
+    if let Some(length) = crop_length {
+        let mut fields = HashMap::new();
+        fields.insert(PREVIEW_ATTRIBUTE.to_string(), length);
+        crop_document(&mut document, &mut matches, &schema, &fields, true);
+    }
+
+    if highlight {
+        let mut attributes = HashSet::new();
+        attributes.insert(PREVIEW_ATTRIBUTE.to_string());
+        let matches = calculate_matches(&matches, Some(attributes.clone()), &schema);
+        document = calculate_highlights(&document, &matches, &attributes, whole_words, None);
+    }
+
+    Ok(document.remove(PREVIEW_ATTRIBUTE).unwrap_or_default())
+}
+
+/// returns the words of the displayed attributes of a document that matched the query.
+fn matched_terms(
+    index: &Index,
+    reader: &MainReader,
+    schema: &Schema,
+    document: &Document,
+) -> Result<BTreeSet<String>, ResponseError> {
+    let displayed = schema.displayed_name();
+    let mut values = HashMap::new();
+    let mut terms = BTreeSet::new();
+
+    for highlight in &document.highlights {
+        let field_id = FieldId::new(highlight.attribute);
+        match schema.name(field_id) {
+            Some(name) if displayed.contains(name) => (),
+            _ => continue,
+        }
+
+        if !values.contains_key(&field_id) {
+            let value = index
+                .document_attribute::<Value>(reader, document.id, field_id)
+                .map_err(|e| Error::retrieve_document(document.id.0, e))?;
+            values.insert(field_id, value);
+        }
+
+        // the positions of the matches are only known for the string attributes
+        if let Some(Some(Value::String(text))) = values.get(&field_id) {
+            let term: String = text
+                .chars()
+                .skip(highlight.char_index as usize)
+                .take(highlight.char_length as usize)
+                .collect();
+            if !term.is_empty() {
+                terms.insert(term.to_lowercase());
+            }
+        }
+    }
+
+    Ok(terms)
+}
+
+/// returns the bounds of the words the match starts and ends in.
+fn whole_words_bounds(value: &[char], start: usize, end: usize) -> (usize, usize) {
+    // a CJK character is a whole word, the bounds are only expanded on the other side
+    let start = match value.get(start) {
+        Some(c) if is_cjk(*c) => start,
+        _ => start - value[..start].iter().rev().take_while(|c| is_word_component(c)).count(),
+    };
+    let end = match end.checked_sub(1).and_then(|last| value.get(last)) {
+        Some(c) if is_cjk(*c) => end,
+        _ => end + value[end..].iter().take_while(|c| is_word_component(c)).count(),
+    };
+    (start, end)
+}
+
+/// Renames the attributes of a document, the attributes keep their order.
+fn rename_attributes(document: IndexMap<String, Value>, aliases: &HashMap<String, String>) -> IndexMap<String, Value> {
+    document
+        .into_iter()
+        .map(|(key, value)| match aliases.get(&key) {
+            Some(alias) => (alias.clone(), value),
+            None => (key, value),
+        })
+        .collect()
+}
+
+/// Replaces the numbers that match the query by their string representation, the one they
+/// have been indexed with, for them to be highlighted. Dates are already strings.
+fn stringify_matched_numbers(
+    document: &mut IndexMap<String, Value>,
+    matches: &MatchesInfos,
+    attributes_to_highlight: &HashSet<String>,
+) {
+    for attribute in matches.keys() {
+        if attributes_to_highlight.contains(attribute) {
+            if let Some(value) = document.get_mut(attribute) {
+                if let Value::Number(number) = value {
+                    *value = Value::String(number.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn ends_with_cjk(text: &str) -> bool {
+    text.chars().last().map_or(false, is_cjk)
+}
+
+fn starts_with_cjk(chars: &[char]) -> bool {
+    chars.first().map_or(false, |c| is_cjk(*c))
+}
+
+/// splits a value into consecutive segments, flagged when they are a match. Only the
+/// first `max_matches` matched segments are flagged, the other ones are left plain.
+fn highlight_segments(
+    value: &str,
+    matches: &[MatchPosition],
+    whole_words: bool,
+    max_matches: Option<usize>,
+) -> Vec<(String, bool)> {
+    let value: Vec<_> = value.chars().collect();
+    let mut segments = Vec::new();
+    let mut index = 0;
+
+    let longest_matches = matches
+        .linear_group_by_key(|m| m.start)
+        .map(|group| group.last().unwrap())
+        .filter(move |m| m.start >= index);
+
+    for m in longest_matches {
+        let (start, end) = match (m.start, m.start + m.length) {
+            (start, end) if whole_words && end <= value.len() => whole_words_bounds(&value, start, end),
+            bounds => bounds,
+        };
+        // the match is part of a word that has already been highlighted
+        if whole_words && start < index {
+            continue;
+        }
+
+        let before = value.get(index..start);
+        let highlighted = value.get(start..end);
+        if let (Some(before), Some(highlighted)) = (before, highlighted) {
+            if !before.is_empty() {
+                segments.push((before.iter().collect(), false));
+            }
+            // every CJK character is a token, the contiguous matched ones are highlighted together
+            match segments.last_mut() {
+                Some((previous, true)) if before.is_empty() && ends_with_cjk(previous) && starts_with_cjk(highlighted) => {
+                    previous.extend(highlighted);
+                }
+                _ => segments.push((highlighted.iter().collect(), true)),
+            }
+            index = end;
+        } else {
+            error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
+        }
+    }
+    if index < value.len() {
+        segments.push((value[index..].iter().collect(), false));
+    }
+    if let Some(max_matches) = max_matches {
+        let mut nb_matches = 0;
+        let mut capped: Vec<(String, bool)> = Vec::with_capacity(segments.len());
+        for (segment, is_match) in segments {
+            let is_match = is_match && {
+                nb_matches += 1;
+                nb_matches <= max_matches
+            };
+            // the matches that are not highlighted are merged with the surrounding text
+            match capped.last_mut() {
+                Some((previous, false)) if !is_match => previous.push_str(&segment),
+                _ => capped.push((segment, is_match)),
+            }
+        }
+        return capped;
+    }
+    segments
+}
+
 fn calculate_highlights(
     document: &IndexMap<String, Value>,
     matches: &MatchesInfos,
     attributes_to_highlight: &HashSet<String>,
+    whole_words: bool,
+    max_matches: Option<usize>,
 ) -> IndexMap<String, Value> {
     let mut highlight_result = document.clone();
 
     for (attribute, matches) in matches.iter() {
         if attributes_to_highlight.contains(attribute) {
             if let Some(Value::String(value)) = document.get(attribute) {
-                let value: Vec<_> = value.chars().collect();
                 let mut highlighted_value = String::new();
-                let mut index = 0;
-
-                let longest_matches = matches
-                    .linear_group_by_key(|m| m.start)
-                    .map(|group| group.last().unwrap())
-                    .filter(move |m| m.start >= index);
-
-                for m in longest_matches {
-                    let before = value.get(index..m.start);
-                    let highlighted = value.get(m.start..(m.start + m.length));
-                    if let (Some(before), Some(highlighted)) = (before, highlighted) {
-                        highlighted_value.extend(before);
+                for (segment, is_match) in highlight_segments(value, matches, whole_words, max_matches) {
+                    if is_match {
                         highlighted_value.push_str("<em>");
-                        highlighted_value.extend(highlighted);
+                        highlighted_value.push_str(&segment);
                         highlighted_value.push_str("</em>");
-                        index = m.start + m.length;
                     } else {
-                        error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
+                        highlighted_value.push_str(&segment);
                     }
                 }
-                highlighted_value.extend(value[index..].iter());
                 highlight_result.insert(attribute.to_string(), Value::String(highlighted_value));
             };
         }
@@ -506,6 +2442,31 @@ fn calculate_highlights(
     highlight_result
 }
 
+/// replaces the highlighted attributes by the list of their segments, the concatenation
+/// of the segments values is the original attribute.
+fn calculate_highlight_segments(
+    document: &IndexMap<String, Value>,
+    matches: &MatchesInfos,
+    attributes_to_highlight: &HashSet<String>,
+    whole_words: bool,
+    max_matches: Option<usize>,
+) -> IndexMap<String, Value> {
+    let mut highlight_result = document.clone();
+
+    for (attribute, matches) in matches.iter() {
+        if attributes_to_highlight.contains(attribute) {
+            if let Some(Value::String(value)) = document.get(attribute) {
+                let segments = highlight_segments(value, matches, whole_words, max_matches)
+                    .into_iter()
+                    .map(|(value, is_match)| json!({ "value": value, "isMatch": is_match }))
+                    .collect();
+                highlight_result.insert(attribute.to_string(), Value::Array(segments));
+            };
+        }
+    }
+    highlight_result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,6 +2503,22 @@ mod tests {
         assert_eq!("の", cropped);
     }
 
+    #[test]
+    fn crop_text_whitespaces() {
+        let text = "  hello world  ";
+        let matches = vec![Highlight { attribute: 0, char_index: 2, char_length: 5 }];
+
+        let (cropped, cropped_matches) = crop_text(text, matches.clone(), 6, true);
+        assert_eq!(cropped, "hello");
+        assert_eq!(cropped_matches, vec![Highlight { attribute: 0, char_index: 0, char_length: 5 }]);
+
+        let (cropped, cropped_matches) = crop_text(text, matches, 6, false);
+        assert_eq!(cropped, "  hello ");
+        assert_eq!(cropped_matches, vec![Highlight { attribute: 0, char_index: 2, char_length: 5 }]);
+        let highlighted: String = cropped.chars().skip(2).take(5).collect();
+        assert_eq!(highlighted, "hello");
+    }
+
     #[test]
     fn calculate_matches() {
         let mut matches = Vec::new();
@@ -598,7 +2575,7 @@ mod tests {
             length: 9,
         });
         matches.insert("description".to_string(), m);
-        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight);
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
 
         let mut result_expected = IndexMap::new();
         result_expected.insert(
@@ -633,7 +2610,7 @@ mod tests {
         });
         matches.insert("title".to_string(), m);
 
-        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight);
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
 
         let mut result_expected = IndexMap::new();
         result_expected.insert(
@@ -643,4 +2620,137 @@ mod tests {
 
         assert_eq!(result, result_expected);
     }
+
+    #[test]
+    fn highlight_whole_words() {
+        let data = r#"{
+            "title": "The Foundation trilogy"
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("title".to_string());
+
+        let mut matches = HashMap::new();
+
+        let mut m = Vec::new();
+        m.push(MatchPosition {
+            start: 4,
+            length: 5,
+        });
+        matches.insert("title".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
+        assert_eq!(result["title"], Value::String("The <em>Found</em>ation trilogy".to_string()));
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, true, None);
+        assert_eq!(result["title"], Value::String("The <em>Foundation</em> trilogy".to_string()));
+    }
+
+    #[test]
+    fn highlight_cjk_tokens() {
+        let data = r#"{
+            "title": "this isのス foo myタイリ test"
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("title".to_string());
+
+        // every CJK character is matched on its own
+        let m = vec![5, 7, 8, 16, 17, 18]
+            .into_iter()
+            .map(|start| MatchPosition { start, length: if start == 5 { 2 } else { 1 } })
+            .collect();
+        let mut matches = HashMap::new();
+        matches.insert("title".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
+        assert_eq!(result["title"], Value::String("this <em>is</em><em>のス</em> foo my<em>タイリ</em> test".to_string()));
+
+        // the whole words expansion stops at the CJK characters
+        let m = vec![MatchPosition { start: 5, length: 1 }, MatchPosition { start: 16, length: 1 }];
+        matches.insert("title".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, true, None);
+        assert_eq!(result["title"], Value::String("this <em>is</em>のス foo my<em>タ</em>イリ test".to_string()));
+    }
+
+    #[test]
+    fn merge_adjacent_matches() {
+        let data = r#"{
+            "title": "A well-known story, well told"
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("title".to_string());
+
+        let mut matches = HashMap::new();
+
+        let mut m = Vec::new();
+        m.push(MatchPosition {
+            start: 2,
+            length: 4,
+        });
+        m.push(MatchPosition {
+            start: 7,
+            length: 5,
+        });
+        m.push(MatchPosition {
+            start: 20,
+            length: 4,
+        });
+        matches.insert("title".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
+        assert_eq!(result["title"], Value::String("A <em>well</em>-<em>known</em> story, <em>well</em> told".to_string()));
+
+        super::merge_adjacent_matches(&mut matches, 1);
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
+        assert_eq!(result["title"], Value::String("A <em>well-known</em> story, <em>well</em> told".to_string()));
+    }
+
+    #[test]
+    fn calculate_capped_highlights() {
+        let data = r#"{
+            "title": "fish and fish, fish or fish"
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("title".to_string());
+
+        let mut matches = HashMap::new();
+        let m: Vec<_> = [0, 9, 15, 23].iter().map(|&start| MatchPosition { start, length: 4 }).collect();
+        matches.insert("title".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, Some(2));
+        assert_eq!(result["title"], Value::String("<em>fish</em> and <em>fish</em>, fish or fish".to_string()));
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight, false, None);
+        assert_eq!(result["title"], Value::String("<em>fish</em> and <em>fish</em>, <em>fish</em> or <em>fish</em>".to_string()));
+
+        let result = super::calculate_highlight_segments(&document, &matches, &attributes_to_highlight, false, Some(2));
+        let expected = json!([
+            { "value": "fish", "isMatch": true },
+            { "value": " and ", "isMatch": false },
+            { "value": "fish", "isMatch": true },
+            { "value": ", fish or fish", "isMatch": false },
+        ]);
+        assert_eq!(result["title"], expected);
+    }
+
+    #[test]
+    fn parse_sort_rules() {
+        let rules: Vec<SortRule> = split_sort_rules("_geoPoint(48.85, 2.35):asc, price:desc")
+            .into_iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        assert_eq!(rules, vec![SortRule::GeoAsc((48.85, 2.35)), SortRule::Desc("price".to_string())]);
+
+        assert!("price".parse::<SortRule>().is_err());
+        assert!("price:up".parse::<SortRule>().is_err());
+        assert!("_geoPoint(48.85):asc".parse::<SortRule>().is_err());
+    }
 }
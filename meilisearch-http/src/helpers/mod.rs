@@ -1,6 +1,8 @@
 pub mod authentication;
+pub mod facet_cache;
 pub mod meilisearch;
 pub mod normalize_path;
+pub mod score_expression;
 
 pub use authentication::Authentication;
 pub use normalize_path::NormalizePath;
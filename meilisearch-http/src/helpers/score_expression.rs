@@ -0,0 +1,225 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+/// The name of the ranking score of the documents in the expressions.
+const RANKING_SCORE: &str = "_rankingScore";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// An arithmetic expression over the ranking score and the ranked attributes of a document,
+/// for example `_rankingScore * 0.7 + popularity * 0.3`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreExpression {
+    Number(f64),
+    RankingScore,
+    Attribute(String),
+    Neg(Box<ScoreExpression>),
+    Binary(Box<ScoreExpression>, Operator, Box<ScoreExpression>),
+}
+
+impl ScoreExpression {
+    /// returns the attributes the expression refers to.
+    pub fn attributes(&self) -> Vec<&str> {
+        match self {
+            ScoreExpression::Number(_) | ScoreExpression::RankingScore => Vec::new(),
+            ScoreExpression::Attribute(name) => vec![name.as_str()],
+            ScoreExpression::Neg(expr) => expr.attributes(),
+            ScoreExpression::Binary(lhs, _, rhs) => {
+                let mut attributes = lhs.attributes();
+                attributes.extend(rhs.attributes());
+                attributes
+            }
+        }
+    }
+
+    /// computes the expression for a document, nothing is returned when one of the attributes
+    /// has no value or when the result is not a finite number, a division by zero for example.
+    pub fn evaluate(&self, ranking_score: f64, value: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        let result = match self {
+            ScoreExpression::Number(n) => *n,
+            ScoreExpression::RankingScore => ranking_score,
+            ScoreExpression::Attribute(name) => value(name)?,
+            ScoreExpression::Neg(expr) => -expr.evaluate(ranking_score, value)?,
+            ScoreExpression::Binary(lhs, op, rhs) => {
+                let lhs = lhs.evaluate(ranking_score, value)?;
+                let rhs = rhs.evaluate(ranking_score, value)?;
+                match op {
+                    Operator::Add => lhs + rhs,
+                    Operator::Sub => lhs - rhs,
+                    Operator::Mul => lhs * rhs,
+                    Operator::Div => lhs / rhs,
+                }
+            }
+        };
+        if result.is_finite() { Some(result) } else { None }
+    }
+}
+
+impl FromStr for ScoreExpression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, chars: s.char_indices().peekable() };
+        let expr = parser.expression()?;
+        parser.skip_whitespaces();
+        match parser.chars.next() {
+            Some((i, c)) => Err(format!("unexpected character {:?} at position {}", c, i)),
+            None => Ok(expr),
+        }
+    }
+}
+
+impl fmt::Display for ScoreExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreExpression::Number(n) => write!(f, "{}", n),
+            ScoreExpression::RankingScore => f.write_str(RANKING_SCORE),
+            ScoreExpression::Attribute(name) => f.write_str(name),
+            ScoreExpression::Neg(expr) => write!(f, "-{}", expr),
+            ScoreExpression::Binary(lhs, op, rhs) => {
+                let op = match op {
+                    Operator::Add => '+',
+                    Operator::Sub => '-',
+                    Operator::Mul => '*',
+                    Operator::Div => '/',
+                };
+                write!(f, "({} {} {})", lhs, op, rhs)
+            }
+        }
+    }
+}
+
+/// A recursive descent parser, the multiplications and divisions bind tighter than the
+/// additions and subtractions, the operators of the same precedence are left associative.
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespaces(&mut self) {
+        while let Some((_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+
+    /// consumes the next character if it is one of the given operators.
+    fn operator(&mut self, operators: &[(char, Operator)]) -> Option<Operator> {
+        self.skip_whitespaces();
+        let (_, c) = self.chars.peek()?;
+        let (_, op) = operators.iter().find(|(symbol, _)| symbol == c)?;
+        self.chars.next();
+        Some(*op)
+    }
+
+    fn expression(&mut self) -> Result<ScoreExpression, String> {
+        let mut expr = self.term()?;
+        while let Some(op) = self.operator(&[('+', Operator::Add), ('-', Operator::Sub)]) {
+            expr = ScoreExpression::Binary(Box::new(expr), op, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<ScoreExpression, String> {
+        let mut expr = self.factor()?;
+        while let Some(op) = self.operator(&[('*', Operator::Mul), ('/', Operator::Div)]) {
+            expr = ScoreExpression::Binary(Box::new(expr), op, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<ScoreExpression, String> {
+        self.skip_whitespaces();
+        match self.chars.peek().cloned() {
+            Some((_, '-')) => {
+                self.chars.next();
+                Ok(ScoreExpression::Neg(Box::new(self.factor()?)))
+            }
+            Some((i, '(')) => {
+                self.chars.next();
+                let expr = self.expression()?;
+                self.skip_whitespaces();
+                match self.chars.next() {
+                    Some((_, ')')) => Ok(expr),
+                    _ => Err(format!("unclosed parenthesis at position {}", i)),
+                }
+            }
+            Some((start, c)) if c.is_ascii_digit() || c == '.' => {
+                let end = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                let number = &self.input[start..end];
+                number.parse().map(ScoreExpression::Number)
+                    .map_err(|_| format!("invalid number {:?} at position {}", number, start))
+            }
+            Some((start, c)) if c.is_alphanumeric() || c == '_' => {
+                let end = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                match &self.input[start..end] {
+                    RANKING_SCORE => Ok(ScoreExpression::RankingScore),
+                    name => Ok(ScoreExpression::Attribute(name.to_string())),
+                }
+            }
+            Some((i, c)) => Err(format!("unexpected character {:?} at position {}", c, i)),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+
+    /// consumes the characters matching the predicate and returns the position after them.
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> usize {
+        while let Some((_, c)) = self.chars.peek() {
+            if !predicate(*c) {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().map_or(self.input.len(), |(i, _)| *i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_score_expressions() {
+        let expr: ScoreExpression = "_rankingScore * 0.7 + popularity * 0.3".parse().unwrap();
+        assert_eq!(expr.to_string(), "((_rankingScore * 0.7) + (popularity * 0.3))");
+        assert_eq!(expr.attributes(), vec!["popularity"]);
+
+        let expr: ScoreExpression = "-(a - b) / 2 - c".parse().unwrap();
+        assert_eq!(expr.to_string(), "((-(a - b) / 2) - c)");
+
+        assert!("".parse::<ScoreExpression>().is_err());
+        assert!("a +".parse::<ScoreExpression>().is_err());
+        assert!("a % b".parse::<ScoreExpression>().is_err());
+        assert!("(a + b".parse::<ScoreExpression>().is_err());
+        assert!("1.2.3".parse::<ScoreExpression>().is_err());
+        assert!("a b".parse::<ScoreExpression>().is_err());
+    }
+
+    #[test]
+    fn evaluate_score_expressions() {
+        let value = |name: &str| match name {
+            "popularity" => Some(10.0),
+            "zero" => Some(0.0),
+            _ => None,
+        };
+
+        let expr: ScoreExpression = "_rankingScore * 0.5 + popularity / 4".parse().unwrap();
+        assert_eq!(expr.evaluate(0.5, &value), Some(2.75));
+
+        // the documents without value and the divisions by zero have no result
+        let expr: ScoreExpression = "_rankingScore + missing".parse().unwrap();
+        assert_eq!(expr.evaluate(0.8, &value), None);
+        let expr: ScoreExpression = "popularity / zero".parse().unwrap();
+        assert_eq!(expr.evaluate(0.8, &value), None);
+    }
+}
@@ -8,8 +8,10 @@ pub mod option;
 pub mod routes;
 pub mod analytics;
 
+use actix_cors::{Cors, CorsFactory};
 use actix_http::Error;
 use actix_service::ServiceFactory;
+use actix_web::middleware::Condition;
 use actix_web::{dev, web, App};
 use chrono::Utc;
 use log::error;
@@ -52,12 +54,37 @@ pub fn create_app(
         .configure(routes::setting::services)
         .configure(routes::stop_words::services)
         .configure(routes::synonym::services)
+        .configure(routes::tokenize::services)
+        .configure(routes::format_preview::services)
         .configure(routes::health::services)
         .configure(routes::stats::services)
         .configure(routes::key::services)
 }
 
+/// Builds the CORS middleware allowing the given origins, the `*` origin allows any origin.
+/// The middleware is disabled when there is no allowed origin.
+pub fn create_cors(allowed_origins: &[String]) -> Condition<CorsFactory> {
+    let mut cors = Cors::new();
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        cors = cors.send_wildcard();
+    } else {
+        for origin in allowed_origins {
+            cors = cors.allowed_origin(origin);
+        }
+    }
+
+    let cors = cors
+        .allowed_headers(vec!["content-type", "x-meili-api-key"])
+        .max_age(86_400) // 24h
+        .finish();
+
+    Condition::new(!allowed_origins.is_empty(), cors)
+}
+
 pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpdateResult) {
+    // the cached facets distributions may not match the documents anymore
+    data.facet_cache.invalidate(index_uid);
+
     if status.error.is_some() {
         return;
     }
@@ -1,10 +1,9 @@
 use std::{env, thread};
 
-use actix_cors::Cors;
 use actix_web::{middleware, HttpServer};
 use main_error::MainError;
 use meilisearch_http::helpers::NormalizePath;
-use meilisearch_http::{create_app, index_update_callback, Data, Opt};
+use meilisearch_http::{create_app, create_cors, index_update_callback, Data, Opt};
 use structopt::StructOpt;
 
 mod analytics;
@@ -66,19 +65,20 @@ async fn main() -> Result<(), MainError> {
 
     print_launch_resume(&opt, &data);
 
-    let http_server = HttpServer::new(move || {
+    let cors_allowed_origins = opt.cors_allowed_origins.clone();
+    let mut http_server = HttpServer::new(move || {
         create_app(&data)
-            .wrap(
-                Cors::new()
-                    .send_wildcard()
-                    .allowed_headers(vec!["content-type", "x-meili-api-key"])
-                    .max_age(86_400) // 24h
-                    .finish(),
-            )
+            .wrap(create_cors(&cors_allowed_origins))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .wrap(NormalizePath)
-    });
+    })
+    .maxconn(opt.http_max_connections)
+    .keep_alive(opt.keep_alive());
+
+    if let Some(workers) = opt.http_workers {
+        http_server = http_server.workers(workers);
+    }
 
     if let Some(config) = opt.get_ssl_config()? {
         http_server
@@ -109,6 +109,7 @@ pub fn print_launch_resume(opt: &Opt, data: &Data) {
     eprintln!("Database path:\t\t{:?}", opt.db_path);
     eprintln!("Server listening on:\t{:?}", opt.http_addr);
     eprintln!("Environment:\t\t{:?}", opt.env);
+    eprintln!("CORS allowed origins:\t{:?}", opt.cors_allowed_origins);
     eprintln!("Commit SHA:\t\t{:?}", env!("VERGEN_SHA").to_string());
     eprintln!(
         "Build date:\t\t{:?}",
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::{error, fs};
 
+use actix_http::KeepAlive;
 use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
 use rustls::{
     AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth,
@@ -60,6 +61,64 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_HTTP_PAYLOAD_SIZE_LIMIT", default_value = "10485760")] // 10MB
     pub http_payload_size_limit: usize,
 
+    /// The number of http workers, defaults to the number of logical CPUs.
+    #[structopt(long, env = "MEILI_HTTP_WORKERS")]
+    pub http_workers: Option<usize>,
+
+    /// The maximum number of concurrent connections accepted by each http worker.
+    #[structopt(long, env = "MEILI_HTTP_MAX_CONNECTIONS", default_value = "25000")]
+    pub http_max_connections: usize,
+
+    /// The number of seconds an idle connection is kept alive, 0 disables the keep-alive.
+    #[structopt(long, env = "MEILI_HTTP_KEEP_ALIVE", default_value = "5")]
+    pub http_keep_alive: usize,
+
+    /// Ignore the unknown search parameters instead of rejecting the request.
+    /// The ignored parameters are listed in the `_warnings` field of the response.
+    #[structopt(long, env = "MEILI_LENIENT_SEARCH_PARAMETERS")]
+    pub lenient_search_parameters: bool,
+
+    /// The maximum number of and-ed clauses accepted in the facet filters of a search request.
+    /// By default the number of clauses is not limited.
+    #[structopt(long, env = "MEILI_MAX_FACET_FILTERS")]
+    pub max_facet_filters: Option<usize>,
+
+    /// Drop the facet filter elements on unknown attributes instead of rejecting the request.
+    /// The dropped elements are listed in the `_warnings` field of the response.
+    #[structopt(long, env = "MEILI_LENIENT_FACET_FILTERS")]
+    pub lenient_facet_filters: bool,
+
+    /// The maximum number of facets for which a search request can ask the distribution.
+    /// By default the number of facets is not limited.
+    #[structopt(long, env = "MEILI_MAX_FACETS_DISTRIBUTION")]
+    pub max_facets_distribution: Option<usize>,
+
+    /// Skip the documents that cannot be read instead of failing the whole search request.
+    /// The skipped documents are listed in the `_warnings` field of the response.
+    #[structopt(long, env = "MEILI_SKIP_UNREADABLE_DOCUMENTS")]
+    pub skip_unreadable_documents: bool,
+
+    /// The maximum number of terms of a search query, the following terms are ignored.
+    /// By default the number of terms is not limited.
+    #[structopt(long, env = "MEILI_MAX_QUERY_TERMS")]
+    pub max_query_terms: Option<usize>,
+
+    /// The maximum number of documents read by the distinct attribute rule to fill a page of
+    /// results, the page may then be incomplete. By default, or when set to 0, the number of documents
+    /// is not limited.
+    #[structopt(long, env = "MEILI_DISTINCT_SCAN_LIMIT")]
+    pub distinct_scan_limit: Option<usize>,
+
+    /// Always sort the placeholder searches with the ranking rules, even when the sort rules of
+    /// the request could be applied directly on the filtered documents.
+    #[structopt(long, env = "MEILI_DISABLE_SORT_FAST_PATH")]
+    pub disable_sort_fast_path: bool,
+
+    /// The origins allowed to send cross-origin requests, separated by commas. The `*` origin
+    /// allows any origin. By default no cross-origin request is allowed.
+    #[structopt(long, env = "MEILI_CORS_ALLOWED_ORIGINS", use_delimiter = true)]
+    pub cors_allowed_origins: Vec<String>,
+
     /// Read server certificates from CERTFILE.
     /// This should contain PEM-format certificates
     /// in the right order (the first certificate should
@@ -96,6 +155,14 @@ pub struct Opt {
 }
 
 impl Opt {
+    /// the keep-alive of the http server, a timeout of 0 would keep the idle connections open.
+    pub fn keep_alive(&self) -> KeepAlive {
+        match self.http_keep_alive {
+            0 => KeepAlive::Disabled,
+            timeout => KeepAlive::Timeout(timeout),
+        }
+    }
+
     pub fn get_ssl_config(&self) -> Result<Option<rustls::ServerConfig>, Box<dyn error::Error>> {
         if let (Some(cert_path), Some(key_path)) = (&self.ssl_cert_path, &self.ssl_key_path) {
             let client_auth = match &self.ssl_auth_path {
@@ -181,3 +248,28 @@ fn load_ocsp(filename: &Option<PathBuf>) -> Result<Vec<u8>, Box<dyn error::Error
 
     Ok(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_server_options() {
+        let opt = Opt::from_iter(&["meilisearch"]);
+        assert_eq!(opt.http_workers, None);
+        assert_eq!(opt.http_max_connections, 25000);
+        assert_eq!(opt.http_keep_alive, 5);
+        assert_eq!(opt.keep_alive(), KeepAlive::Timeout(5));
+
+        let opt = Opt::from_iter(&[
+            "meilisearch",
+            "--http-workers", "4",
+            "--http-max-connections", "1000",
+            "--http-keep-alive", "0",
+        ]);
+        assert_eq!(opt.http_workers, Some(4));
+        assert_eq!(opt.http_max_connections, 1000);
+        assert_eq!(opt.http_keep_alive, 0);
+        assert_eq!(opt.keep_alive(), KeepAlive::Disabled);
+    }
+}
@@ -1,14 +1,21 @@
 use std::collections::{BTreeSet, HashSet};
+use std::pin::Pin;
 
-use actix_web::{web, HttpResponse};
+use actix_web::error::JsonPayloadError;
+use actix_web::dev::Decompress;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
 use actix_web_macros::{delete, get, post, put};
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{stream, SinkExt, StreamExt};
 use indexmap::IndexMap;
-use meilisearch_core::update;
-use serde::Deserialize;
+use meilisearch_core::{update, Filter};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::Data;
-use crate::error::{Error, ResponseError};
+use crate::error::{payload_error_handler, Error, ResponseError};
 use crate::helpers::Authentication;
 use crate::routes::{IndexParam, IndexUpdateResponse};
 
@@ -24,12 +31,20 @@ pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(get_document)
         .service(delete_document)
         .service(get_all_documents)
+        .service(export_documents)
         .service(add_documents)
         .service(update_documents)
         .service(delete_documents)
         .service(clear_all_documents);
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct GetDocumentQuery {
+    attributes_to_retrieve: Option<String>,
+    show_version: Option<bool>,
+}
+
 #[get(
     "/indexes/{index_uid}/documents/{document_id}",
     wrap = "Authentication::Public"
@@ -37,6 +52,7 @@ pub fn services(cfg: &mut web::ServiceConfig) {
 async fn get_document(
     data: web::Data<Data>,
     path: web::Path<DocumentParam>,
+    params: web::Query<GetDocumentQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -49,10 +65,21 @@ async fn get_document(
         .external_to_internal_docid(&reader, &path.document_id)?
         .ok_or(Error::document_not_found(&path.document_id))?;
 
-    let document: Document = index
-        .document(&reader, None, internal_id)?
+    let attributes: Option<HashSet<&str>> = params
+        .attributes_to_retrieve
+        .as_ref()
+        .map(|a| a.split(',').collect());
+
+    let mut document: Document = index
+        .document(&reader, attributes.as_ref(), internal_id)?
         .ok_or(Error::document_not_found(&path.document_id))?;
 
+    if let Some(true) = params.show_version {
+        if let Some(version) = index.document_version(&reader, internal_id)? {
+            document.insert("_version".to_string(), Value::from(version));
+        }
+    }
+
     Ok(HttpResponse::Ok().json(document))
 }
 
@@ -124,6 +151,94 @@ async fn get_all_documents(
     Ok(HttpResponse::Ok().json(documents))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportQuery {
+    filters: Option<String>,
+}
+
+/// The number of exported lines serialized ahead of the response.
+const EXPORT_BUFFER_SIZE: usize = 100;
+
+/// returns all the stored documents of the index, one JSON document per line, the
+/// response can be sent back to the documents route to restore them.
+#[get("/indexes/{index_uid}/export", wrap = "Authentication::Private")]
+async fn export_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    // the documents are read on the blocking thread pool and streamed as soon as they are
+    // serialized, the export stops when the response is dropped
+    let (sender, receiver) = mpsc::channel(EXPORT_BUFFER_SIZE);
+    let index_uid = path.into_inner().index_uid;
+    let filters = params.into_inner().filters;
+    let export = web::block(move || export_lines(&data, &index_uid, filters.as_deref(), sender));
+
+    let end = stream::once(export).filter_map(|result| async move {
+        result.err().map(|e| Err(ResponseError::from(e)))
+    });
+    let mut lines = Box::pin(receiver.map(Ok).chain(end)).peekable();
+
+    // the errors raised before the first line, like an invalid filter, are returned as usual
+    if let Some(Err(_)) = Pin::new(&mut lines).peek().await {
+        if let Some(Err(e)) = lines.next().await {
+            return Err(e);
+        }
+    }
+
+    Ok(HttpResponse::Ok().content_type("application/x-ndjson").streaming(lines))
+}
+
+/// sends the documents of the index accepted by the filters, one JSON document per line.
+fn export_lines(
+    data: &Data,
+    index_uid: &str,
+    filters: Option<&str>,
+    mut sender: mpsc::Sender<Bytes>,
+) -> Result<(), ResponseError> {
+    let index = data
+        .db
+        .open_index(index_uid)
+        .ok_or(Error::index_not_found(index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let schema = match index.main.schema(&reader)? {
+        Some(schema) => schema,
+        // an index without schema has no documents
+        None => return Ok(()),
+    };
+    let ranked_map = index.main.ranked_map(&reader)?.unwrap_or_default();
+
+    let filter = match filters {
+        Some(filter_expression) => {
+            let filterable = index.main.filterable_attributes(&reader)?;
+            Some(Filter::parse(filter_expression, &schema, filterable.as_deref())?)
+        }
+        None => None,
+    };
+
+    for document_id in index.documents_fields_counts.documents_ids(&reader)? {
+        let document_id = document_id?;
+        if let Some(filter) = &filter {
+            if !filter.test(&reader, &index, &ranked_map, document_id)? {
+                continue;
+            }
+        }
+
+        if let Some(document) = index.document::<Document>(&reader, None, document_id)? {
+            let mut line = serde_json::to_vec(&document).map_err(Error::internal)?;
+            line.push(b'\n');
+            // the receiver is gone when the response has been dropped
+            if block_on(sender.send(Bytes::from(line))).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
     for key in document.keys() {
         if key.to_lowercase().contains("id") {
@@ -133,17 +248,78 @@ fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
     None
 }
 
+/// returns the first candidate that is present in every document with a distinct value.
+fn select_primary_key(documents: &[Document], candidates: &[String]) -> Option<String> {
+    candidates
+        .iter()
+        .find(|candidate| {
+            let mut values = HashSet::new();
+            documents.iter().all(|document| match document.get(candidate.as_str()) {
+                Some(value) => values.insert(value.to_string()),
+                None => false,
+            })
+        })
+        .cloned()
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct UpdateDocumentsQuery {
     primary_key: Option<String>,
+    primary_key_candidates: Option<String>,
+    /// the attributes whose values form the primary key, set along with the primary key
+    primary_key_components: Option<String>,
+    skip_invalid_documents: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RejectedDocument {
+    index: usize,
+    error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentsUpdateResponse {
+    update_id: u64,
+    rejected_documents: Vec<RejectedDocument>,
+}
+
+/// reads the documents of a JSON payload that must not be larger than `limit` bytes once
+/// decompressed, the payload is decompressed according to its `Content-Encoding`.
+async fn read_documents(
+    req: &HttpRequest,
+    payload: web::Payload,
+    limit: usize,
+) -> Result<Vec<Document>, ResponseError> {
+    let is_json = match req.mime_type() {
+        Ok(Some(mime)) => mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON),
+        _ => false,
+    };
+    if !is_json {
+        return Err(payload_error_handler(JsonPayloadError::ContentType));
+    }
+
+    let mut payload = Decompress::from_headers(payload, req.headers());
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| Error::bad_request(format!("Problem while decoding the request: {}", e)))?;
+        if body.len() + chunk.len() > limit {
+            return Err(Error::PayloadTooLarge.into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    serde_json::from_slice(&body).map_err(|e| payload_error_handler(JsonPayloadError::Deserialize(e)))
 }
 
 async fn update_multiple_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
+    req: HttpRequest,
+    payload: web::Payload,
     is_partial: bool,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -151,6 +327,13 @@ async fn update_multiple_documents(
         .open_index(&path.index_uid)
         .ok_or(Error::index_not_found(&path.index_uid))?;
 
+    // the payload size limit of the index overrides the global one
+    let limit = {
+        let reader = data.db.main_read_txn()?;
+        index.main.payload_size_limit(&reader)?
+    };
+    let body = read_documents(&req, payload, limit.unwrap_or(data.http_payload_size_limit)).await?;
+
     let reader = data.db.main_read_txn()?;
 
     let mut schema = index
@@ -158,10 +341,37 @@ async fn update_multiple_documents(
         .schema(&reader)?
         .ok_or(meilisearch_core::Error::SchemaMissing)?;
 
+    let mut components = index.main.primary_key_components(&reader)?;
+    // the components are only chosen with the primary key, they cannot change afterwards
+    if schema.primary_key().is_some() && params.primary_key_components.is_some() {
+        return Err(Error::bad_request(
+            "primaryKeyComponents cannot be set, the index already has a primary key",
+        ).into());
+    }
     if schema.primary_key().is_none() {
-        let id = match &params.primary_key {
-            Some(id) => id.to_string(),
-            None => body
+        if let Some(new_components) = &params.primary_key_components {
+            components = Some(new_components.split(',').map(|c| c.trim().to_string()).collect());
+        }
+        if params.primary_key_components.is_some() && params.primary_key.is_none() {
+            return Err(Error::bad_request(
+                "primaryKeyComponents requires the primaryKey attribute they form",
+            ).into());
+        }
+
+        // the candidates given with the documents take precedence over the index ones
+        let candidates = match &params.primary_key_candidates {
+            Some(candidates) => Some(candidates.split(',').map(|c| c.trim().to_string()).collect()),
+            None => index.main.primary_key_candidates(&reader)?,
+        };
+
+        let id = match (&params.primary_key, candidates) {
+            (Some(id), _) => id.to_string(),
+            (None, Some(candidates)) => select_primary_key(&body, &candidates)
+                .ok_or_else(|| Error::bad_request(format!(
+                    "none of the primary key candidates {:?} is present and unique in every document",
+                    candidates,
+                )))?,
+            (None, None) => body
                 .first()
                 .and_then(find_primary_key)
                 .ok_or(meilisearch_core::Error::MissingPrimaryKey)?
@@ -171,7 +381,14 @@ async fn update_multiple_documents(
             .set_primary_key(&id)
             .map_err(Error::bad_request)?;
 
-        data.db.main_write(|w| index.main.put_schema(w, &schema))?;
+        data.db.main_write::<_, _, ResponseError>(|w| {
+            index.main.put_schema(w, &schema)?;
+            index.main.delete_primary_key_candidates(w)?;
+            if let (Some(components), Some(_)) = (&components, &params.primary_key_components) {
+                index.main.put_primary_key_components(w, components)?;
+            }
+            Ok(())
+        })?;
     }
 
     let mut document_addition = if is_partial {
@@ -180,13 +397,33 @@ async fn update_multiple_documents(
         index.documents_addition()
     };
 
-    for document in body.into_inner() {
-        document_addition.update_document(document);
+    if !params.skip_invalid_documents.unwrap_or(false) {
+        for document in body {
+            document_addition.update_document(document);
+        }
+
+        let update_id = data.db.update_write(|w| document_addition.finalize(w))?;
+
+        return Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)));
+    }
+
+    // the documents with a missing or invalid id are reported instead of failing the whole batch
+    let primary_key = schema.primary_key().map(ToString::to_string).unwrap_or_default();
+    let mut rejected_documents = Vec::new();
+    for (index, document) in body.into_iter().enumerate() {
+        let document_id = match &components {
+            Some(components) => update::composite_document_id(&primary_key, components, &document),
+            None => update::validate_document_id(&primary_key, &document),
+        };
+        match document_id {
+            Ok(_) => document_addition.update_document(document),
+            Err(e) => rejected_documents.push(RejectedDocument { index, error: e.to_string() }),
+        }
     }
 
     let update_id = data.db.update_write(|w| document_addition.finalize(w))?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(DocumentsUpdateResponse { update_id, rejected_documents }))
 }
 
 #[post("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
@@ -194,9 +431,10 @@ async fn add_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
+    req: HttpRequest,
+    payload: web::Payload,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, false).await
+    update_multiple_documents(data, path, params, req, payload, false).await
 }
 
 #[put("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
@@ -204,9 +442,10 @@ async fn update_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
+    req: HttpRequest,
+    payload: web::Payload,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, true).await
+    update_multiple_documents(data, path, params, req, payload, true).await
 }
 
 #[post(
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use actix_web_macros::post;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::meilisearch::format_text;
+use crate::helpers::Authentication;
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(format_preview);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct FormatPreviewBody {
+    text: String,
+    q: String,
+    crop: Option<bool>,
+    crop_length: Option<usize>,
+    highlight: Option<bool>,
+    highlight_whole_words: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct FormatPreviewResponse {
+    #[serde(rename = "_formatted")]
+    formatted: Value,
+}
+
+/// Returns the given text cropped and highlighted as it would be in the `_formatted` field
+/// of a hit, nothing is read from or written to the documents of the index.
+#[post("/indexes/{index_uid}/format-preview", wrap = "Authentication::Public")]
+async fn format_preview(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<FormatPreviewBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let stop_words: HashSet<_> = index.main.stop_words(&reader)?.into_iter().collect();
+
+    let crop_length = match body.crop {
+        Some(true) => Some(body.crop_length.unwrap_or(200)),
+        _ => None,
+    };
+    let formatted = format_text(
+        &body.text,
+        &body.q,
+        &stop_words,
+        crop_length,
+        body.highlight.unwrap_or(true),
+        body.highlight_whole_words.unwrap_or(false),
+    )?;
+
+    Ok(HttpResponse::Ok().json(FormatPreviewResponse { formatted }))
+}
@@ -143,6 +143,11 @@ struct IndexCreateRequest {
     name: Option<String>,
     uid: Option<String>,
     primary_key: Option<String>,
+    /// the attributes that can become the primary key, the first one present
+    /// and unique in the first added documents is chosen.
+    primary_key_candidates: Option<Vec<String>>,
+    /// the attributes whose values are joined by a dash to form the primary key of the documents.
+    primary_key_components: Option<Vec<String>>,
 }
 
 #[post("/indexes", wrap = "Authentication::Private")]
@@ -156,6 +161,34 @@ async fn create_index(
         ).into());
     }
 
+    match &body.primary_key_candidates {
+        Some(_) if body.primary_key.is_some() => {
+            return Err(Error::bad_request(
+                "primaryKey and primaryKeyCandidates cannot be used together",
+            ).into());
+        }
+        Some(candidates) if candidates.is_empty() => {
+            return Err(Error::bad_request(
+                "primaryKeyCandidates must contain at least one attribute",
+            ).into());
+        }
+        _ => (),
+    }
+
+    match &body.primary_key_components {
+        Some(_) if body.primary_key.is_none() => {
+            return Err(Error::bad_request(
+                "primaryKeyComponents requires the primaryKey attribute they form",
+            ).into());
+        }
+        Some(components) if components.is_empty() => {
+            return Err(Error::bad_request(
+                "primaryKeyComponents must contain at least one attribute",
+            ).into());
+        }
+        _ => (),
+    }
+
     let uid = match &body.uid {
         Some(uid) => {
             if uid
@@ -205,6 +238,12 @@ async fn create_index(
                 created_index.main.put_schema(&mut writer, &schema)?;
             }
         }
+        if let Some(candidates) = &body.primary_key_candidates {
+            created_index.main.put_primary_key_candidates(&mut writer, candidates)?;
+        }
+        if let Some(components) = &body.primary_key_components {
+            created_index.main.put_primary_key_components(&mut writer, components)?;
+        }
         let index_response = IndexResponse {
             name: name.to_string(),
             uid,
@@ -303,6 +342,7 @@ async fn delete_index(
     path: web::Path<IndexParam>,
 ) -> Result<HttpResponse, ResponseError> {
     if data.db.delete_index(&path.index_uid)? {
+        data.facet_cache.invalidate(&path.index_uid);
         Ok(HttpResponse::NoContent().finish())
     } else {
         Err(Error::index_not_found(&path.index_uid).into())
@@ -2,6 +2,7 @@ use actix_web::{get, HttpResponse};
 use serde::{Deserialize, Serialize};
 
 pub mod document;
+pub mod format_preview;
 pub mod health;
 pub mod index;
 pub mod key;
@@ -10,6 +11,7 @@ pub mod setting;
 pub mod stats;
 pub mod stop_words;
 pub mod synonym;
+pub mod tokenize;
 
 #[derive(Deserialize)]
 pub struct IndexParam {
@@ -1,116 +1,513 @@
 use std::collections::{HashSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use log::warn;
-use actix_web::web;
+use actix_web::error::JsonPayloadError;
+use actix_web::{web, HttpRequest};
 use actix_web::HttpResponse;
 use actix_web_macros::{get, post};
+use bytes::Bytes;
+use futures::channel::mpsc::{self, Sender};
+use futures::{stream, StreamExt};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use siphasher::sip::SipHasher;
 
-use crate::error::{Error, FacetCountError, ResponseError};
-use crate::helpers::meilisearch::{IndexSearchExt, SearchResult};
+use crate::error::{payload_error_handler, Error, FacetCountError, ResponseError};
+use crate::helpers::meilisearch::{split_sort_rules, IndexSearchExt, RelevancePreset, ResultsFormat, SearchExplanation, SearchHit, SearchResult, SortRule};
 use crate::helpers::Authentication;
 use crate::routes::IndexParam;
 use crate::Data;
 
 use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::{Index, MainReader};
+use meilisearch_core::settings::{RankingRule, DEFAULT_FACET_NORMALIZATION};
 use meilisearch_schema::{Schema, FieldId};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(search_with_post)
-        .service(search_with_url_query);
+        .service(search_with_url_query)
+        .service(explain_search);
 }
 
-#[derive(Serialize, Deserialize)]
+/// The constant of the reciprocal rank fusion, it lowers the weight of the first ranks so
+/// that a document found by several queries outranks a document found first by a single one.
+const RECIPROCAL_RANK_FUSION_K: f64 = 60.0;
+
+/// The minimum number of hits of every query taking part in the fusion.
+const FUSED_HITS_PER_QUERY: usize = 100;
+
+/// The number of hits formatted ahead of the event stream.
+const EVENT_STREAM_BUFFER_SIZE: usize = 20;
+
+/// The parameters accepted by the search routes, the other ones are
+/// ignored when the server is lenient with the search parameters.
+const SEARCH_PARAMETERS: &[&str] = &[
+    "q",
+    "queries",
+    "offset",
+    "limit",
+    "attributesToRetrieve",
+    "attributeAliases",
+    "attributesToCrop",
+    "cropLength",
+    "cropTrim",
+    "snippetFormat",
+    "attributesToHighlight",
+    "filters",
+    "matches",
+    "matchesFormat",
+    "facetFilters",
+    "facetsDistribution",
+    "facetsPercentages",
+    "maxValuesPerFacet",
+    "facetsPivot",
+    "facetStats",
+    "sortFacetValuesBy",
+    "locale",
+    "includePrimaryKey",
+    "stickyFacets",
+    "snippet",
+    "autoRelevanceCutoff",
+    "cutoffSensitivity",
+    "showRankingScore",
+    "rankingScoreThreshold",
+    "rankingScoreExpression",
+    "exhaustiveNbHits",
+    "withinDocumentIds",
+    "joinFilter",
+    "highlightWholeWords",
+    "highlightFormat",
+    "highlightNumbers",
+    "sort",
+    "nullsOrder",
+    "showMatchedTerms",
+    "showVersion",
+    "highlightMergeDistance",
+    "maxHighlightedMatches",
+    "debug",
+    "suggestQuery",
+    "rankingRules",
+    "relevancePreset",
+    "typoSummary",
+    "removeStopWords",
+    "idsOnly",
+    "format",
+    "showFieldTypes",
+];
+
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct SearchQuery {
     q: Option<String>,
+    queries: Option<String>,
     offset: Option<usize>,
     limit: Option<usize>,
     attributes_to_retrieve: Option<String>,
+    attribute_aliases: Option<String>,
     attributes_to_crop: Option<String>,
     crop_length: Option<usize>,
+    crop_trim: Option<bool>,
+    snippet_format: Option<String>,
     attributes_to_highlight: Option<String>,
     filters: Option<String>,
     matches: Option<bool>,
+    matches_format: Option<String>,
     facet_filters: Option<String>,
     facets_distribution: Option<String>,
+    facets_percentages: Option<bool>,
+    max_values_per_facet: Option<usize>,
+    facets_pivot: Option<String>,
+    facet_stats: Option<String>,
+    sort_facet_values_by: Option<String>,
+    locale: Option<String>,
+    include_primary_key: Option<bool>,
+    sticky_facets: Option<bool>,
+    snippet: Option<bool>,
+    auto_relevance_cutoff: Option<bool>,
+    cutoff_sensitivity: Option<f64>,
+    show_ranking_score: Option<bool>,
+    ranking_score_threshold: Option<f64>,
+    ranking_score_expression: Option<String>,
+    exhaustive_nb_hits: Option<bool>,
+    within_document_ids: Option<String>,
+    join_filter: Option<String>,
+    highlight_whole_words: Option<bool>,
+    highlight_format: Option<String>,
+    highlight_numbers: Option<bool>,
+    sort: Option<String>,
+    nulls_order: Option<String>,
+    show_matched_terms: Option<bool>,
+    show_version: Option<bool>,
+    highlight_merge_distance: Option<usize>,
+    max_highlighted_matches: Option<usize>,
+    debug: Option<bool>,
+    suggest_query: Option<bool>,
+    ranking_rules: Option<String>,
+    relevance_preset: Option<String>,
+    typo_summary: Option<bool>,
+    remove_stop_words: Option<bool>,
+    ids_only: Option<bool>,
+    format: Option<String>,
+    show_field_types: Option<bool>,
 }
 
 #[get("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
 async fn search_with_url_query(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    params: web::Query<SearchQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ResponseError> {
-    let search_result = params.search(&path.index_uid, data)?;
-    Ok(HttpResponse::Ok().json(search_result))
+    let (params, ignored): (Vec<_>, Vec<_>) = req
+        .query_string()
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .partition(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !data.lenient_search_parameters || SEARCH_PARAMETERS.contains(&name)
+        });
+    let ignored = ignored.iter().map(|param| param.split('=').next().unwrap_or_default().to_string()).collect();
+
+    let params = web::Query::<SearchQuery>::from_query(&params.join("&")).map_err(payload_error_handler)?;
+    search_response(params.into_inner(), path.into_inner().index_uid, data, ignored).await
+}
+
+/// raises the cancellation flag of a search when dropped. The handler future is dropped before
+/// its completion when the client disconnects, the search then stops at its next check.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// runs the search on the blocking thread pool, it is cancelled if the request is abandoned.
+async fn cancellable_search(
+    query: SearchQuery,
+    index_uid: String,
+    data: web::Data<Data>,
+) -> Result<SearchResult, ResponseError> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancelled.clone());
+    let search_result = web::block(move || query.search(&index_uid, data, &cancelled, None)).await?;
+    Ok(search_result)
+}
+
+/// runs the search and serializes its result with the hits in the requested layout.
+async fn search_response(
+    query: SearchQuery,
+    index_uid: String,
+    data: web::Data<Data>,
+    ignored_params: Vec<String>,
+) -> Result<HttpResponse, ResponseError> {
+    let format = query.results_format()?;
+    if format == ResultsFormat::EventStream {
+        return event_stream_search(query, index_uid, data, ignored_params).await;
+    }
+
+    let mut search_result = cancellable_search(query, index_uid, data).await?;
+    search_result.warnings.extend(ignored_params_warnings(ignored_params));
+    match format {
+        ResultsFormat::Columnar => Ok(HttpResponse::Ok().json(search_result.to_columnar()?)),
+        _ => Ok(HttpResponse::Ok().json(search_result)),
+    }
+}
+
+/// runs the search on the blocking thread pool and streams every hit as a server-sent event as
+/// soon as it is formatted, the last event holds the other fields of the result. The search is
+/// cancelled when the response is dropped, the hits cannot be sent anymore.
+async fn event_stream_search(
+    query: SearchQuery,
+    index_uid: String,
+    data: web::Data<Data>,
+    ignored_params: Vec<String>,
+) -> Result<HttpResponse, ResponseError> {
+    let (sender, receiver) = mpsc::channel(EVENT_STREAM_BUFFER_SIZE);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let guard = CancelOnDrop(cancelled.clone());
+    let search = web::block(move || query.search(&index_uid, data, &cancelled, Some(sender)));
+
+    let hits = receiver.map(|hit| -> Result<Bytes, ResponseError> {
+        let hit = serde_json::to_value(hit).map_err(Error::internal)?;
+        Ok(server_sent_event(None, &hit))
+    });
+    // the guard lives as long as the response stream, the search is cancelled once it is dropped
+    let metadata = stream::once(async move {
+        let _guard = guard;
+        let mut search_result = search.await?;
+        search_result.warnings.extend(ignored_params_warnings(ignored_params));
+        let mut metadata = serde_json::to_value(search_result).map_err(Error::internal)?;
+        if let Value::Object(fields) = &mut metadata {
+            fields.remove("hits");
+        }
+        Ok::<_, ResponseError>(server_sent_event(Some("metadata"), &metadata))
+    });
+
+    // the errors raised before the first hit, like an invalid parameter, are returned as usual
+    let mut events = Box::pin(hits.chain(metadata)).peekable();
+    if let Some(Err(_)) = Pin::new(&mut events).peek().await {
+        if let Some(Err(e)) = events.next().await {
+            return Err(e);
+        }
+    }
+
+    Ok(HttpResponse::Ok().content_type("text/event-stream").streaming(events))
+}
+
+fn server_sent_event(event: Option<&str>, data: &Value) -> Bytes {
+    let data = format!("data: {}\n\n", data);
+    match event {
+        Some(event) => Bytes::from(format!("event: {}\n{}", event, data)),
+        None => Bytes::from(data),
+    }
+}
+
+fn ignored_params_warnings(mut ignored_params: Vec<String>) -> Vec<String> {
+    ignored_params.sort_unstable();
+    ignored_params.dedup();
+    ignored_params
+        .into_iter()
+        .map(|param| format!("unknown search parameter `{}` has been ignored", param))
+        .collect()
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct SearchQueryPost {
     q: Option<String>,
+    queries: Option<Vec<String>>,
     offset: Option<usize>,
     limit: Option<usize>,
     attributes_to_retrieve: Option<Vec<String>>,
+    attribute_aliases: Option<Value>,
     attributes_to_crop: Option<Vec<String>>,
     crop_length: Option<usize>,
+    crop_trim: Option<bool>,
+    snippet_format: Option<String>,
     attributes_to_highlight: Option<Vec<String>>,
     filters: Option<String>,
     matches: Option<bool>,
+    matches_format: Option<String>,
     facet_filters: Option<Value>,
     facets_distribution: Option<Vec<String>>,
+    facets_percentages: Option<bool>,
+    max_values_per_facet: Option<usize>,
+    facets_pivot: Option<Vec<String>>,
+    facet_stats: Option<Vec<String>>,
+    sort_facet_values_by: Option<String>,
+    locale: Option<String>,
+    include_primary_key: Option<bool>,
+    sticky_facets: Option<bool>,
+    snippet: Option<bool>,
+    auto_relevance_cutoff: Option<bool>,
+    cutoff_sensitivity: Option<f64>,
+    show_ranking_score: Option<bool>,
+    ranking_score_threshold: Option<f64>,
+    ranking_score_expression: Option<String>,
+    exhaustive_nb_hits: Option<bool>,
+    within_document_ids: Option<Vec<String>>,
+    join_filter: Option<Value>,
+    highlight_whole_words: Option<bool>,
+    highlight_format: Option<String>,
+    highlight_numbers: Option<bool>,
+    sort: Option<Vec<String>>,
+    nulls_order: Option<String>,
+    show_matched_terms: Option<bool>,
+    show_version: Option<bool>,
+    highlight_merge_distance: Option<usize>,
+    max_highlighted_matches: Option<usize>,
+    debug: Option<bool>,
+    suggest_query: Option<bool>,
+    ranking_rules: Option<Vec<String>>,
+    relevance_preset: Option<String>,
+    typo_summary: Option<bool>,
+    remove_stop_words: Option<bool>,
+    ids_only: Option<bool>,
+    format: Option<String>,
+    show_field_types: Option<bool>,
+}
+
+/// Restricts the hits to the documents whose id is a value of the `key` attribute
+/// of the documents of another index.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct JoinFilter {
+    index_uid: String,
+    key: String,
 }
 
 impl From<SearchQueryPost> for SearchQuery {
     fn from(other: SearchQueryPost) -> SearchQuery {
         SearchQuery {
             q: other.q,
+            queries: other.queries.map(|queries| Value::from(queries).to_string()),
             offset: other.offset,
             limit: other.limit,
             attributes_to_retrieve: other.attributes_to_retrieve.map(|attrs| attrs.join(",")),
+            attribute_aliases: other.attribute_aliases.map(|aliases| aliases.to_string()),
             attributes_to_crop: other.attributes_to_crop.map(|attrs| attrs.join(",")),
             crop_length: other.crop_length,
+            crop_trim: other.crop_trim,
+            snippet_format: other.snippet_format,
             attributes_to_highlight: other.attributes_to_highlight.map(|attrs| attrs.join(",")),
             filters: other.filters,
             matches: other.matches,
+            matches_format: other.matches_format,
             facet_filters: other.facet_filters.map(|f| f.to_string()),
             facets_distribution: other.facets_distribution.map(|f| format!("{:?}", f)),
+            facets_percentages: other.facets_percentages,
+            max_values_per_facet: other.max_values_per_facet,
+            facets_pivot: other.facets_pivot.map(|attrs| attrs.join(",")),
+            facet_stats: other.facet_stats.map(|attrs| attrs.join(",")),
+            sort_facet_values_by: other.sort_facet_values_by,
+            locale: other.locale,
+            include_primary_key: other.include_primary_key,
+            sticky_facets: other.sticky_facets,
+            snippet: other.snippet,
+            auto_relevance_cutoff: other.auto_relevance_cutoff,
+            cutoff_sensitivity: other.cutoff_sensitivity,
+            show_ranking_score: other.show_ranking_score,
+            ranking_score_threshold: other.ranking_score_threshold,
+            ranking_score_expression: other.ranking_score_expression,
+            exhaustive_nb_hits: other.exhaustive_nb_hits,
+            within_document_ids: other.within_document_ids.map(|ids| ids.join(",")),
+            join_filter: other.join_filter.map(|filter| filter.to_string()),
+            highlight_whole_words: other.highlight_whole_words,
+            highlight_format: other.highlight_format,
+            highlight_numbers: other.highlight_numbers,
+            sort: other.sort.map(|rules| rules.join(",")),
+            nulls_order: other.nulls_order,
+            show_matched_terms: other.show_matched_terms,
+            show_version: other.show_version,
+            highlight_merge_distance: other.highlight_merge_distance,
+            max_highlighted_matches: other.max_highlighted_matches,
+            debug: other.debug,
+            suggest_query: other.suggest_query,
+            ranking_rules: other.ranking_rules.map(|rules| rules.join(",")),
+            relevance_preset: other.relevance_preset,
+            typo_summary: other.typo_summary,
+            remove_stop_words: other.remove_stop_words,
+            ids_only: other.ids_only,
+            format: other.format,
+            show_field_types: other.show_field_types,
         }
     }
 }
 
 #[post("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
 async fn search_with_post(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Json<Value>,
+) -> Result<HttpResponse, ResponseError> {
+    let mut params = params.into_inner();
+    let mut ignored = Vec::new();
+    if let (true, Value::Object(params)) = (data.lenient_search_parameters, &mut params) {
+        ignored = params.keys().filter(|name| !SEARCH_PARAMETERS.contains(&name.as_str())).cloned().collect();
+        for name in &ignored {
+            params.remove(name);
+        }
+    }
+
+    let params: SearchQueryPost = serde_json::from_value(params)
+        .map_err(|e| payload_error_handler(JsonPayloadError::Deserialize(e)))?;
+    let query: SearchQuery = params.into();
+    search_response(query, path.into_inner().index_uid, data, ignored).await
+}
+
+#[post("/indexes/{index_uid}/search/explain", wrap = "Authentication::Public")]
+async fn explain_search(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Json<SearchQueryPost>,
 ) -> Result<HttpResponse, ResponseError> {
-    let query: SearchQuery = params.0.into();
-    let search_result = query.search(&path.index_uid, data)?;
-    Ok(HttpResponse::Ok().json(search_result))
+    let query: SearchQuery = params.into_inner().into();
+    let explanation = query.explain(&path.index_uid, data)?;
+    Ok(HttpResponse::Ok().json(explanation))
 }
 
 impl SearchQuery {
-    fn search(&self, index_uid: &str, data: web::Data<Data>) -> Result<SearchResult, ResponseError> {
+    fn results_format(&self) -> Result<ResultsFormat, ResponseError> {
+        match &self.format {
+            Some(format) => Ok(format.parse().map_err(|e| Error::bad_parameter("format", e))?),
+            None => Ok(ResultsFormat::Hits),
+        }
+    }
+
+    fn search(
+        &self,
+        index_uid: &str,
+        data: web::Data<Data>,
+        cancelled: &Arc<AtomicBool>,
+        hits_sender: Option<Sender<SearchHit>>,
+    ) -> Result<SearchResult, ResponseError> {
+        if let Some(queries) = &self.queries {
+            if self.q.is_some() {
+                return Err(Error::bad_parameter("queries", "cannot be used with q").into());
+            }
+            if let Some(true) = self.ids_only {
+                return Err(Error::bad_parameter("idsOnly", "cannot be used with queries").into());
+            }
+            // the hits of the queries are only ranked once they are all found
+            if hits_sender.is_some() {
+                return Err(Error::bad_parameter("format", "`eventStream` cannot be used with queries").into());
+            }
+            let queries: Vec<String> = serde_json::from_str(queries)
+                .map_err(|e| Error::bad_parameter("queries", format!("expected an array of strings: {}", e)))?;
+            return self.fused_search(index_uid, data, &queries, cancelled);
+        }
+
+        let reader = data.db.main_read_txn()?;
+        self.search_with_reader(index_uid, &data, &reader, cancelled, hits_sender)
+    }
+
+    /// runs the search with the given transaction, the searches merged together read the same one.
+    fn search_with_reader(
+        &self,
+        index_uid: &str,
+        data: &Data,
+        reader: &MainReader,
+        cancelled: &Arc<AtomicBool>,
+        hits_sender: Option<Sender<SearchHit>>,
+    ) -> Result<SearchResult, ResponseError> {
         let index = data
             .db
             .open_index(index_uid)
             .ok_or(Error::index_not_found(index_uid))?;
 
-        let reader = data.db.main_read_txn()?;
-        let schema = index
-            .main
-            .schema(&reader)?
-            .ok_or(Error::internal("Impossible to retrieve the schema"))?;
+        // a missing schema means the index has not been populated yet, the search
+        // builder will then return an empty result.
+        let schema = index.main.schema(reader)?.unwrap_or_default();
+
+        // the joined index is read with the same transaction as the searched one
+        let joined_index = match &self.join_filter {
+            Some(join_filter) => {
+                let join_filter: JoinFilter = serde_json::from_str(join_filter)
+                    .map_err(|e| Error::bad_parameter("joinFilter", e))?;
+                let joined_index = data
+                    .db
+                    .open_index(&join_filter.index_uid)
+                    .ok_or(Error::index_not_found(&join_filter.index_uid))?;
+                Some((joined_index, join_filter.key))
+            }
+            None => None,
+        };
 
         let mut search_builder = index.new_search(self.q.clone());
 
         if let Some(offset) = self.offset {
             search_builder.offset(offset);
         }
-        if let Some(limit) = self.limit {
+        // the default number of hits of the index is used when the request has no limit
+        let limit = match self.limit {
+            Some(limit) => Some(limit),
+            None => index.main.default_hits_per_page(reader)?,
+        };
+        if let Some(limit) = limit {
             search_builder.limit(limit);
         }
 
@@ -118,44 +515,161 @@ impl SearchQuery {
         let mut restricted_attributes: HashSet<&str>;
         match &self.attributes_to_retrieve {
             Some(attributes_to_retrieve) => {
-                let attributes_to_retrieve: HashSet<&str> = attributes_to_retrieve.split(',').collect();
-                if attributes_to_retrieve.contains("*") {
+                // `-attr` removes the attribute from the ones selected by the other entries
+                let (excluded_attributes, attributes_to_retrieve): (Vec<&str>, HashSet<&str>) = attributes_to_retrieve
+                    .split(',')
+                    .partition(|attr| attr.starts_with('-'));
+                // only giving exclusions retrieves all the other attributes
+                let wildcard = attributes_to_retrieve.contains("*") || attributes_to_retrieve.is_empty();
+                if wildcard {
                     restricted_attributes = available_attributes.clone();
                 } else {
                     restricted_attributes = HashSet::new();
                     for attr in attributes_to_retrieve {
-                        if available_attributes.contains(attr) {
+                        if attr.len() > 2 && attr.ends_with(".*") {
+                            // `meta.*` retrieves the `meta` attribute and all the attributes under it
+                            let prefix = &attr[..attr.len() - 1];
+                            let parent = &attr[..attr.len() - 2];
+                            for &available in &available_attributes {
+                                if available.starts_with(prefix) || available == parent {
+                                    restricted_attributes.insert(available);
+                                }
+                            }
+                        } else if available_attributes.contains(attr) {
                             restricted_attributes.insert(attr);
-                            search_builder.add_retrievable_field(attr.to_string());
                         } else {
-                            warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+                            search_builder.add_warning(format!("attribute `{}` in attributesToRetrieve does not exist", attr));
                         }
                     }
                 }
+
+                for attr in &excluded_attributes {
+                    let attr = &attr[1..];
+                    if attr.len() > 2 && attr.ends_with(".*") {
+                        let prefix = &attr[..attr.len() - 1];
+                        let parent = &attr[..attr.len() - 2];
+                        restricted_attributes.retain(|available| !available.starts_with(prefix) && *available != parent);
+                    } else {
+                        restricted_attributes.remove(attr);
+                    }
+                }
+
+                if !excluded_attributes.is_empty() {
+                    let attributes = restricted_attributes.iter().map(|attr| attr.to_string()).collect();
+                    search_builder.attributes_to_retrieve(attributes);
+                } else if !wildcard {
+                    for attr in &restricted_attributes {
+                        search_builder.add_retrievable_field(attr.to_string());
+                    }
+                }
             },
             None => {
                 restricted_attributes = available_attributes.clone();
             }
         }
 
-        if let Some(ref facet_filters) = self.facet_filters {
-            let attrs = index.main.attributes_for_faceting(&reader)?.unwrap_or_default();
-            search_builder.add_facet_filters(FacetFilter::from_str(facet_filters, &schema, &attrs)?);
+        let mut facet_filters_warnings = Vec::new();
+        if let Some(facet_filters) = self.facet_filters(&index, reader, &schema, data, &mut facet_filters_warnings)? {
+            search_builder.add_facet_filters(facet_filters);
+        }
+        for warning in facet_filters_warnings {
+            search_builder.add_warning(warning);
+        }
+
+        if let Some(aliases) = &self.attribute_aliases {
+            let aliases: HashMap<String, String> = serde_json::from_str(aliases)
+                .map_err(|e| Error::bad_parameter("attributeAliases", e))?;
+            // the renamed attributes must not replace another attribute of the hits
+            let mut names = HashSet::new();
+            for (attribute, alias) in &aliases {
+                if schema.id(alias).is_some() || alias.starts_with('_') || !names.insert(alias) {
+                    let message = format!("the alias `{}` of `{}` collides with another attribute", alias, attribute);
+                    return Err(Error::bad_parameter("attributeAliases", message).into());
+                }
+            }
+            search_builder.attribute_aliases(aliases);
         }
 
+        // the facets distributions computed for the same hits are reused until the index is
+        // updated, the update date is written with every update and serves as the index version
+        let mut cached_facets = None;
+        let mut facets_to_cache = None;
         if let Some(facets) = &self.facets_distribution {
-            match index.main.attributes_for_faceting(&reader)? {
+            match index.main.attributes_for_faceting(reader)? {
                 Some(ref attrs) => {
                     let field_ids = prepare_facet_list(&facets, &schema, attrs)?;
-                    search_builder.add_facets(field_ids);
+                    // every facet costs the count of all its values
+                    if let Some(max) = data.max_facets_distribution {
+                        if field_ids.len() > max {
+                            let message = format!("at most {} facets are allowed, found {}", max, field_ids.len());
+                            return Err(Error::bad_parameter("facetsDistribution", message).into());
+                        }
+                    }
+                    let names: Vec<_> = field_ids.iter().map(|(_, name)| name.clone()).collect();
+                    let signature = self.facets_signature();
+                    let mut version = index.main.updated_at(reader)?
+                        .map_or(0, |date| date.timestamp_nanos());
+                    // the distributions of a join also change with the updates of the joined index
+                    if let Some((joined_index, _)) = &joined_index {
+                        let joined_version = joined_index.main.updated_at(reader)?
+                            .map_or(0, |date| date.timestamp_nanos());
+                        let mut hasher = SipHasher::new();
+                        (version, joined_version).hash(&mut hasher);
+                        version = hasher.finish() as i64;
+                    }
+                    match data.facet_cache.get(index_uid, signature, version, &names) {
+                        Some(distributions) => cached_facets = Some(distributions),
+                        None => {
+                            search_builder.add_facets(field_ids);
+                            facets_to_cache = Some((signature, version));
+                        }
+                    }
                 },
                 None => return Err(FacetCountError::NoFacetSet.into()),
             }
         }
 
+        if let Some(max) = self.max_values_per_facet {
+            search_builder.max_values_per_facet(max);
+        }
+
+        if let Some(order) = &self.sort_facet_values_by {
+            let order = order.parse().map_err(|e| Error::bad_parameter("sortFacetValuesBy", e))?;
+            search_builder.facet_values_order(order);
+        }
+
+        if let Some(facets_pivot) = &self.facets_pivot {
+            let attrs = index.main.attributes_for_faceting(reader)?.unwrap_or_default();
+            let field_ids = facets_pivot
+                .split(',')
+                .map(|name| match schema.id(name) {
+                    Some(id) if attrs.contains(&id) => Ok(id),
+                    _ => {
+                        let message = format!("attribute `{}` is not set as a facet", name);
+                        Err(Error::bad_parameter("facetsPivot", message))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match field_ids.as_slice() {
+                &[first, second] if first != second => {
+                    search_builder.facets_pivot(first, second);
+                },
+                _ => {
+                    let message = "two distinct facet attributes are expected";
+                    return Err(Error::bad_parameter("facetsPivot", message).into());
+                }
+            }
+        }
+
+        if let Some(facet_stats) = &self.facet_stats {
+            let attributes = facet_stats.split(',').map(|name| name.trim().to_string()).collect();
+            search_builder.facet_stats(attributes);
+        }
+
         if let Some(attributes_to_crop) = &self.attributes_to_crop {
             let default_length = self.crop_length.unwrap_or(200);
             let mut final_attributes: HashMap<String, usize> = HashMap::new();
+            let mut excluded_attributes = Vec::new();
 
             for attribute in attributes_to_crop.split(',') {
                 let mut attribute = attribute.split(':');
@@ -167,46 +681,489 @@ impl SearchQuery {
                             final_attributes.insert(attr.to_string(), length);
                         }
                     },
+                    // `-attr` removes the attribute from the ones selected by the other entries
+                    Some(attr) if attr.starts_with('-') => excluded_attributes.push(&attr[1..]),
                     Some(attr) => {
                         if available_attributes.contains(attr) {
                             final_attributes.insert(attr.to_string(), length);
                         } else {
-                            warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+                            search_builder.add_warning(format!("attribute `{}` in attributesToCrop does not exist", attr));
                         }
                     },
                     None => (),
                 }
             }
+            for attr in excluded_attributes {
+                final_attributes.remove(attr);
+            }
             search_builder.attributes_to_crop(final_attributes);
+            if let Some(trim) = self.crop_trim {
+                search_builder.crop_trim(trim);
+            }
+            if let Some(format) = &self.snippet_format {
+                let format = format.parse().map_err(|e| Error::bad_parameter("snippetFormat", e))?;
+                search_builder.snippet_format(format);
+            }
         }
 
         if let Some(attributes_to_highlight) = &self.attributes_to_highlight {
+            // the wildcard only highlights the default attributes to highlight, when there are some
+            let default_attributes = index.main.default_attributes_to_highlight(reader)?;
             let mut final_attributes: HashSet<String> = HashSet::new();
+            let mut excluded_attributes = Vec::new();
             for attribute in attributes_to_highlight.split(',') {
                 if attribute == "*" {
                     for attr in &restricted_attributes {
-                        final_attributes.insert(attr.to_string());
+                        let is_default = match (&default_attributes, schema.id(attr)) {
+                            (Some(defaults), Some(id)) => defaults.contains(&id),
+                            (Some(_), None) => false,
+                            (None, _) => true,
+                        };
+                        if is_default {
+                            final_attributes.insert(attr.to_string());
+                        }
                     }
+                } else if attribute.starts_with('-') {
+                    // `-attr` removes the attribute from the ones selected by the other entries
+                    excluded_attributes.push(&attribute[1..]);
                 } else if available_attributes.contains(attribute) {
                     final_attributes.insert(attribute.to_string());
                 } else {
-                    warn!("The attributes {:?} present in attributesToHighlight parameter doesn't exist", attribute);
+                    search_builder.add_warning(format!("attribute `{}` in attributesToHighlight does not exist", attribute));
                 }
             }
+            for attr in excluded_attributes {
+                final_attributes.remove(attr);
+            }
 
             search_builder.attributes_to_highlight(final_attributes);
         }
 
+        if let Some(true) = self.highlight_whole_words {
+            search_builder.highlight_whole_words();
+        }
+
+        if let Some(format) = &self.highlight_format {
+            let format = format.parse().map_err(|e| Error::bad_parameter("highlightFormat", e))?;
+            search_builder.highlight_format(format);
+        }
+
+        if let Some(true) = self.highlight_numbers {
+            search_builder.highlight_numbers();
+        }
+
+        if let Some(sort_rules) = self.sort_rules()? {
+            search_builder.sort(sort_rules);
+        }
+
+        if let Some(order) = &self.nulls_order {
+            let order = order.parse().map_err(|e| Error::bad_parameter("nullsOrder", e))?;
+            search_builder.nulls_order(order);
+        }
+
+        if let Some(true) = self.show_matched_terms {
+            search_builder.show_matched_terms();
+        }
+
+        if let Some(true) = self.show_version {
+            search_builder.show_version();
+        }
+
+        if let Some(false) = self.remove_stop_words {
+            search_builder.keep_stop_words();
+        }
+
+        if let Some(distance) = self.highlight_merge_distance {
+            search_builder.highlight_merge_distance(distance);
+        }
+
+        if let Some(max_matches) = self.max_highlighted_matches {
+            search_builder.max_highlighted_matches(max_matches);
+        }
+
+        if let Some(true) = self.debug {
+            search_builder.debug();
+        }
+
+        if let Some(true) = self.suggest_query {
+            search_builder.suggest_query();
+        }
+
+        if let Some(true) = self.typo_summary {
+            search_builder.typo_summary();
+        }
+
+        if let Some(true) = self.show_field_types {
+            search_builder.field_types();
+        }
+
+        if let Some(true) = self.ids_only {
+            search_builder.ids_only();
+        }
+
+        if let Some(ranking_rules) = self.ranking_rules()? {
+            search_builder.ranking_rules(ranking_rules);
+        }
+
+        if let Some(preset) = self.relevance_preset()? {
+            search_builder.relevance_preset(preset);
+        }
+
+        if let Some(max) = data.max_query_terms {
+            search_builder.max_query_terms(max);
+        }
+
+        if let Some(limit) = data.distinct_scan_limit {
+            search_builder.distinct_scan_limit(limit);
+        }
+
+        if data.disable_sort_fast_path {
+            search_builder.sort_fast_path(false);
+        }
+
+        if data.skip_unreadable_documents {
+            search_builder.skip_unreadable_documents();
+        }
+
         if let Some(filters) = &self.filters {
             search_builder.filters(filters.to_string());
         }
 
+        if let Some(locale) = &self.locale {
+            let locale = locale.parse().map_err(|e| Error::bad_parameter("locale", e))?;
+            search_builder.locale(locale);
+        }
+
         if let Some(matches) = self.matches {
             if matches {
                 search_builder.get_matches();
             }
         }
-        search_builder.search(&reader)
+
+        if let Some(format) = &self.matches_format {
+            let format = format.parse().map_err(|e| Error::bad_parameter("matchesFormat", e))?;
+            search_builder.matches_format(format);
+        }
+
+        if let Some(true) = self.include_primary_key {
+            search_builder.include_primary_key();
+        }
+
+        if let Some(true) = self.sticky_facets {
+            search_builder.sticky_facets();
+        }
+
+        if let Some(true) = self.snippet {
+            search_builder.snippet();
+        }
+
+        if let Some(true) = self.auto_relevance_cutoff {
+            let sensitivity = self.cutoff_sensitivity.unwrap_or(0.5);
+            if !(0.0..=1.0).contains(&sensitivity) {
+                return Err(Error::bad_parameter("cutoffSensitivity", "must be between 0 and 1").into());
+            }
+            search_builder.auto_relevance_cutoff(sensitivity);
+        }
+
+        if let Some(true) = self.show_ranking_score {
+            search_builder.show_ranking_score();
+        }
+
+        if let Some(threshold) = self.ranking_score_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(Error::bad_parameter("rankingScoreThreshold", "must be between 0 and 1").into());
+            }
+            search_builder.ranking_score_threshold(threshold);
+        }
+
+        if let Some(expression) = &self.ranking_score_expression {
+            let expression = expression.parse().map_err(|e| Error::bad_parameter("rankingScoreExpression", e))?;
+            search_builder.score_expression(expression);
+        }
+
+        if let Some(true) = self.exhaustive_nb_hits {
+            search_builder.exhaustive_nb_hits();
+        }
+
+        if let Some(within_document_ids) = &self.within_document_ids {
+            let document_ids = within_document_ids.split(',').map(ToString::to_string).collect();
+            search_builder.within_document_ids(document_ids);
+        }
+
+        if let Some((joined_index, key)) = joined_index {
+            search_builder.join_filter(joined_index, key);
+        }
+
+        search_builder.cancellation_flag(cancelled.clone());
+        if let Some(sender) = hits_sender {
+            search_builder.hits_sender(sender);
+        }
+
+        let mut search_result = search_builder.search(reader)?;
+
+        match (cached_facets, facets_to_cache, &search_result.facets_distribution) {
+            (Some(distributions), _, _) => {
+                search_result.facets_distribution = Some(distributions);
+                search_result.exhaustive_facets_count = Some(true);
+            }
+            (None, Some((signature, version)), Some(distributions)) => {
+                data.facet_cache.insert(index_uid, signature, version, distributions);
+            }
+            _ => (),
+        }
+
+        if let Some(true) = self.facets_percentages {
+            search_result.compute_facets_percentages();
+        }
+
+        Ok(search_result)
+    }
+
+    /// runs every query with the other parameters of the request and merges their hits by
+    /// reciprocal rank fusion, a document found by several queries is ranked higher. The
+    /// other fields of the result, like the facets distribution, are the first query ones.
+    fn fused_search(
+        &self,
+        index_uid: &str,
+        data: web::Data<Data>,
+        queries: &[String],
+        cancelled: &Arc<AtomicBool>,
+    ) -> Result<SearchResult, ResponseError> {
+        let queries: Vec<_> = queries.iter().map(|q| q.trim()).filter(|q| !q.is_empty()).collect();
+        if queries.is_empty() {
+            return Err(Error::bad_parameter("queries", "at least one query is expected").into());
+        }
+
+        // every query reads the same version of the index
+        let reader = data.db.main_read_txn()?;
+
+        let offset = self.offset.unwrap_or(0);
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => {
+                let index = data
+                    .db
+                    .open_index(index_uid)
+                    .ok_or(Error::index_not_found(index_uid))?;
+                index.main.default_hits_per_page(&reader)?.unwrap_or(20)
+            }
+        };
+
+        let mut fused_hits = IndexMap::new();
+        let mut fused_result: Option<SearchResult> = None;
+        for (i, query) in queries.iter().enumerate() {
+            // the hits ranked after the requested page by a query can be moved up by the others
+            let mut params = SearchQuery {
+                q: Some(query.to_string()),
+                queries: None,
+                offset: Some(0),
+                limit: Some((offset + limit).max(FUSED_HITS_PER_QUERY)),
+                ..self.clone()
+            };
+            // only the hits of the other queries are kept, the rest of their result is not computed
+            if i > 0 {
+                params.facets_distribution = None;
+                params.facets_percentages = None;
+                params.facets_pivot = None;
+                params.facet_stats = None;
+                params.debug = None;
+                params.suggest_query = None;
+                params.typo_summary = None;
+                params.show_version = None;
+                params.show_field_types = None;
+            }
+            let mut result = params.search_with_reader(index_uid, &data, &reader, cancelled, None)?;
+
+            for (rank, hit) in result.hits.drain(..).enumerate() {
+                let score = 1.0 / (RECIPROCAL_RANK_FUSION_K + rank as f64 + 1.0);
+                fused_hits
+                    .entry(hit.document_id)
+                    .or_insert((0.0, hit))
+                    .0 += score;
+            }
+
+            match fused_result.as_mut() {
+                Some(fused) => {
+                    fused.processing_time_ms += result.processing_time_ms;
+                    fused.warnings.extend(result.warnings);
+                }
+                None => fused_result = Some(result),
+            }
+        }
+
+        // the hits with the same score keep the order in which they were found
+        let mut hits: Vec<_> = fused_hits.into_iter().map(|(_, hit)| hit).collect();
+        hits.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut result = fused_result.expect("at least one query has been run");
+        result.nb_hits = hits.len();
+        result.exhaustive_nb_hits = false;
+        result.hits = hits.into_iter().skip(offset).take(limit).map(|(_, hit)| hit).collect();
+        result.offset = offset;
+        result.limit = limit;
+        result.query = queries.join(",");
+        result.warn_overshooting_offset();
+        result.warnings.sort_unstable();
+        result.warnings.dedup();
+        Ok(result)
+    }
+
+    /// estimates the cost of the search, only the parameters selecting and ranking the
+    /// documents are taken into account.
+    fn explain(&self, index_uid: &str, data: web::Data<Data>) -> Result<SearchExplanation, ResponseError> {
+        let index = data
+            .db
+            .open_index(index_uid)
+            .ok_or(Error::index_not_found(index_uid))?;
+
+        let reader = data.db.main_read_txn()?;
+        let schema = index.main.schema(&reader)?.unwrap_or_default();
+
+        let mut search_builder = index.new_search(self.q.clone());
+
+        let mut facet_filters_warnings = Vec::new();
+        if let Some(facet_filters) = self.facet_filters(&index, &reader, &schema, &data, &mut facet_filters_warnings)? {
+            search_builder.add_facet_filters(facet_filters);
+        }
+        for warning in facet_filters_warnings {
+            search_builder.add_warning(warning);
+        }
+
+        if let Some(sort_rules) = self.sort_rules()? {
+            search_builder.sort(sort_rules);
+        }
+
+        if let Some(order) = &self.nulls_order {
+            let order = order.parse().map_err(|e| Error::bad_parameter("nullsOrder", e))?;
+            search_builder.nulls_order(order);
+        }
+
+        if let Some(ranking_rules) = self.ranking_rules()? {
+            search_builder.ranking_rules(ranking_rules);
+        }
+
+        if let Some(preset) = self.relevance_preset()? {
+            search_builder.relevance_preset(preset);
+        }
+
+        if let Some(false) = self.remove_stop_words {
+            search_builder.keep_stop_words();
+        }
+
+        if let Some(filters) = &self.filters {
+            search_builder.filters(filters.to_string());
+        }
+
+        if let Some(locale) = &self.locale {
+            let locale = locale.parse().map_err(|e| Error::bad_parameter("locale", e))?;
+            search_builder.locale(locale);
+        }
+
+        search_builder.explain(&reader)
+    }
+
+    fn facet_filters(
+        &self,
+        index: &Index,
+        reader: &MainReader,
+        schema: &Schema,
+        data: &Data,
+        warnings: &mut Vec<String>,
+    ) -> Result<Option<FacetFilter>, ResponseError> {
+        let facet_filters = match &self.facet_filters {
+            Some(facet_filters) => facet_filters,
+            None => return Ok(None),
+        };
+
+        let mut attrs = index.main.attributes_for_faceting(reader)?.unwrap_or_default().to_vec();
+        // only the filterable attributes can be used in the facet filters
+        if let Some(filterable) = index.main.filterable_attributes(reader)? {
+            attrs.retain(|attr| filterable.contains(attr));
+        }
+        let normalization = index.main
+            .facet_normalization(reader)?
+            .unwrap_or_else(|| DEFAULT_FACET_NORMALIZATION.to_vec());
+        let facet_filters = if data.lenient_facet_filters {
+            let (facet_filters, dropped) = FacetFilter::from_str_lenient(facet_filters, schema, &attrs, &normalization)?;
+            warnings.extend(dropped.into_iter().map(|e| format!("facet filter ignored: {}", e)));
+            // every clause has been dropped, there is no restriction
+            if facet_filters.is_empty() {
+                return Ok(None);
+            }
+            facet_filters
+        } else {
+            FacetFilter::from_str(facet_filters, schema, &attrs, &normalization)?
+        };
+        // every and-ed clause costs an intersection of document ids
+        if let Some(max) = data.max_facet_filters {
+            if facet_filters.len() > max {
+                let message = format!("at most {} and-ed clauses are allowed, found {}", max, facet_filters.len());
+                return Err(Error::bad_parameter("facetFilters", message).into());
+            }
+        }
+        Ok(Some(facet_filters))
+    }
+
+    fn sort_rules(&self) -> Result<Option<Vec<SortRule>>, ResponseError> {
+        match &self.sort {
+            Some(sort) => {
+                let sort_rules = split_sort_rules(sort)
+                    .into_iter()
+                    .map(|rule| rule.parse())
+                    .collect::<Result<Vec<SortRule>, _>>()
+                    .map_err(|e| Error::bad_parameter("sort", e))?;
+                Ok(Some(sort_rules))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn ranking_rules(&self) -> Result<Option<Vec<RankingRule>>, ResponseError> {
+        let ranking_rules = match &self.ranking_rules {
+            Some(ranking_rules) => ranking_rules,
+            None => return Ok(None),
+        };
+
+        let mut rules: Vec<RankingRule> = Vec::new();
+        for rule in ranking_rules.split(',').map(str::trim) {
+            let rule: RankingRule = rule
+                .parse()
+                .map_err(|_| Error::bad_parameter("rankingRules", format!("invalid ranking rule {:?}", rule)))?;
+            if rules.iter().any(|r| r.to_string() == rule.to_string()) {
+                return Err(Error::bad_parameter("rankingRules", format!("duplicate ranking rule `{}`", rule)).into());
+            }
+            rules.push(rule);
+        }
+        Ok(Some(rules))
+    }
+
+    fn relevance_preset(&self) -> Result<Option<RelevancePreset>, ResponseError> {
+        let preset = match &self.relevance_preset {
+            Some(preset) => preset,
+            None => return Ok(None),
+        };
+        if self.ranking_rules.is_some() {
+            return Err(Error::bad_parameter("relevancePreset", "cannot be used with rankingRules").into());
+        }
+        let preset = preset.parse().map_err(|e| Error::bad_parameter("relevancePreset", e))?;
+        Ok(Some(preset))
+    }
+
+    /// identifies the hits of the request, the requests with the
+    /// same signature have the same facets distributions.
+    fn facets_signature(&self) -> u64 {
+        let mut hasher = SipHasher::new();
+        self.q.hash(&mut hasher);
+        self.filters.hash(&mut hasher);
+        self.facet_filters.hash(&mut hasher);
+        self.within_document_ids.hash(&mut hasher);
+        self.join_filter.hash(&mut hasher);
+        self.sticky_facets.hash(&mut hasher);
+        self.sort_facet_values_by.hash(&mut hasher);
+        self.max_values_per_facet.hash(&mut hasher);
+        self.remove_stop_words.hash(&mut hasher);
+        self.locale.hash(&mut hasher);
+        hasher.finish()
     }
 }
 
@@ -231,11 +1188,9 @@ fn prepare_facet_list(facets: &str, schema: &Schema, facet_attrs: &[FieldId]) ->
             for facet in vals {
                 match facet {
                     Value::String(facet) => {
-                        if let Some(id) = schema.id(&facet) {
-                            if !facet_attrs.contains(&id) {
-                                return Err(FacetCountError::AttributeNotSet(facet));
-                            }
-                            field_ids.push((id, facet));
+                        match schema.id(&facet) {
+                            Some(id) if facet_attrs.contains(&id) => field_ids.push((id, facet)),
+                            _ => return Err(FacetCountError::AttributeNotSet(facet)),
                         }
                     }
                     bad_val => return Err(FacetCountError::unexpected_token(bad_val, &["String"])),
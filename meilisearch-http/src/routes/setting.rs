@@ -27,7 +27,8 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(delete_displayed)
         .service(get_attributes_for_faceting)
         .service(delete_attributes_for_faceting)
-        .service(update_attributes_for_faceting);
+        .service(update_attributes_for_faceting)
+        .service(rebuild_facets);
 }
 
 #[post("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
@@ -107,6 +108,38 @@ async fn get_all(
         _ => vec![],
     };
 
+    let locale = index.main.locale(&reader)?;
+
+    let filterable_attributes = match (&schema, &index.main.filterable_attributes(&reader)?) {
+        (Some(schema), Some(attrs)) => {
+            let attrs = attrs
+                .iter()
+                .filter_map(|&id| schema.name(id))
+                .map(str::to_string)
+                .collect();
+            Some(attrs)
+        }
+        _ => None,
+    };
+
+    let default_attributes_to_highlight = match (&schema, &index.main.default_attributes_to_highlight(&reader)?) {
+        (Some(schema), Some(attrs)) => {
+            let attrs = attrs
+                .iter()
+                .filter_map(|&id| schema.name(id))
+                .map(str::to_string)
+                .collect();
+            Some(attrs)
+        }
+        _ => None,
+    };
+
+    let facet_normalization = index.main.facet_normalization(&reader)?;
+    let payload_size_limit = index.main.payload_size_limit(&reader)?;
+    let default_hits_per_page = index.main.default_hits_per_page(&reader)?;
+    let min_query_length = index.main.min_query_length(&reader)?;
+    let max_facet_value_length = index.main.max_facet_value_length(&reader)?;
+
     let searchable_attributes = schema.as_ref().map(get_indexed_attributes);
     let displayed_attributes = schema.as_ref().map(get_displayed_attributes);
 
@@ -118,6 +151,14 @@ async fn get_all(
         stop_words: Some(Some(stop_words)),
         synonyms: Some(Some(synonyms)),
         attributes_for_faceting: Some(Some(attributes_for_faceting)),
+        locale: Some(locale),
+        filterable_attributes: Some(filterable_attributes),
+        default_attributes_to_highlight: Some(default_attributes_to_highlight),
+        facet_normalization: Some(facet_normalization),
+        payload_size_limit: Some(payload_size_limit),
+        default_hits_per_page: Some(default_hits_per_page),
+        min_query_length: Some(min_query_length),
+        max_facet_value_length: Some(max_facet_value_length),
     };
 
     Ok(HttpResponse::Ok().json(settings))
@@ -142,6 +183,14 @@ async fn delete_all(
         stop_words: UpdateState::Clear,
         synonyms: UpdateState::Clear,
         attributes_for_faceting: UpdateState::Clear,
+        locale: UpdateState::Clear,
+        filterable_attributes: UpdateState::Clear,
+        default_attributes_to_highlight: UpdateState::Clear,
+        facet_normalization: UpdateState::Clear,
+        payload_size_limit: UpdateState::Clear,
+        default_hits_per_page: UpdateState::Clear,
+        min_query_length: UpdateState::Clear,
+        max_facet_value_length: UpdateState::Clear,
     };
 
     let update_id = data.db.update_write(|w| index.settings_update(w, settings))?;
@@ -513,6 +562,24 @@ async fn delete_attributes_for_faceting(
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
 
+#[post(
+    "/indexes/{index_uid}/settings/attributes-for-faceting/rebuild",
+    wrap = "Authentication::Private"
+)]
+async fn rebuild_facets(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+
+    let update_id = data.db.update_write(|w| index.facets_rebuild(w))?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
 fn get_indexed_attributes(schema: &Schema) -> Vec<String> {
     if schema.is_indexed_all() {
         ["*"].iter().map(|s| s.to_string()).collect()
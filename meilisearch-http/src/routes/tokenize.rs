@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use actix_web_macros::post;
+use meilisearch_tokenizer::Tokenizer;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ResponseError};
+use crate::helpers::Authentication;
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(tokenize);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TokenizeBody {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenResponse {
+    word: String,
+    index: usize,
+    word_index: usize,
+    char_index: usize,
+    stop_word: bool,
+}
+
+/// Returns the tokens the index would extract from the given text, as they are indexed.
+#[post("/indexes/{index_uid}/tokenize", wrap = "Authentication::Private")]
+async fn tokenize(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<TokenizeBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(Error::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+    let stop_words: HashSet<_> = index.main.stop_words(&reader)?.into_iter().collect();
+
+    let tokens: Vec<_> = Tokenizer::new(&body.text)
+        .map(|token| {
+            let word = token.word.to_lowercase();
+            TokenResponse {
+                stop_word: stop_words.contains(&word),
+                word,
+                index: token.index,
+                word_index: token.word_index,
+                char_index: token.char_index,
+            }
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
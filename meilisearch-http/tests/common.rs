@@ -27,6 +27,16 @@ macro_rules! test_post_get_search {
     };
 }
 
+/// Returns the ids of the hits of a search response, in the order of the hits
+pub fn hit_ids(response: &Value) -> Vec<u64> {
+    response["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["id"].as_u64().unwrap())
+        .collect()
+}
+
 pub struct Server {
     uid: String,
     data: Data,
@@ -34,6 +44,11 @@ pub struct Server {
 
 impl Server {
     pub fn with_uid(uid: &str) -> Server {
+        Self::with_uid_and_options(uid, Opt::default())
+    }
+
+    /// The database related options are always overridden with test values.
+    pub fn with_uid_and_options(uid: &str, opt: Opt) -> Server {
         let tmp_dir = TempDir::new("meilisearch").unwrap();
 
         let default_db_options = DatabaseOptions::default();
@@ -46,8 +61,11 @@ impl Server {
             no_analytics: true,
             main_map_size: default_db_options.main_map_size,
             update_map_size: default_db_options.update_map_size,
-            http_payload_size_limit: 10000000,
-            ..Opt::default()
+            http_payload_size_limit: match opt.http_payload_size_limit {
+                0 => 10000000,
+                limit => limit,
+            },
+            ..opt
         };
 
         let data = Data::new(opt.clone()).unwrap();
@@ -126,9 +144,15 @@ impl Server {
 
 
     pub async fn wait_update_id(&mut self, update_id: u64) {
+        let uid = self.uid.clone();
+        self.wait_index_update_id(&uid, update_id).await
+    }
+
+    pub async fn wait_index_update_id(&mut self, index_uid: &str, update_id: u64) {
         // try 10 times to get status, or panic to not wait forever
         for _ in 0..10 {
-            let (response, status_code) = self.get_update_status(update_id).await;
+            let url = format!("/indexes/{}/updates/{}", index_uid, update_id);
+            let (response, status_code) = self.get_request(&url).await;
             assert_eq!(status_code, 200);
 
             if response["status"] == "processed" || response["status"] == "failed" {
@@ -157,6 +181,48 @@ impl Server {
         (response, status_code)
     }
 
+    /// returns the raw body of the response, for the routes that do not answer with JSON.
+    pub async fn get_request_text(&mut self, url: &str) -> (String, StatusCode) {
+        eprintln!("get_request_text: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let req = test::TestRequest::get().uri(url).to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        (String::from_utf8(body.to_vec()).unwrap(), status_code)
+    }
+
+    /// sends a cross-origin request through the CORS middleware and returns the allowed origin.
+    pub async fn get_request_with_origin(
+        &mut self,
+        url: &str,
+        origin: &str,
+        allowed_origins: &[String],
+    ) -> (Option<String>, StatusCode) {
+        eprintln!("get_request_with_origin: {} from {}", url, origin);
+
+        let app = meilisearch_http::create_app(&self.data)
+            .wrap(meilisearch_http::create_cors(allowed_origins))
+            .wrap(NormalizePath);
+        let mut app = test::init_service(app).await;
+
+        let req = test::TestRequest::get()
+            .uri(url)
+            .header("origin", origin)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let allowed_origin = res
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|value| value.to_str().unwrap().to_string());
+        (allowed_origin, status_code)
+    }
+
     pub async fn post_request(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request: {}", url);
 
@@ -174,6 +240,24 @@ impl Server {
         (response, status_code)
     }
 
+    /// sends a raw body with the given headers, for the payloads that are not plain JSON.
+    pub async fn post_request_raw(&mut self, url: &str, body: Vec<u8>, headers: &[(&str, &str)]) -> (Value, StatusCode) {
+        eprintln!("post_request_raw: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizePath)).await;
+
+        let mut req = test::TestRequest::post().uri(url).set_payload(body);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        let res = test::call_service(&mut app, req.to_request()).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
     pub async fn post_request_async(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request_async: {}", url);
 
@@ -279,6 +363,17 @@ impl Server {
         self.post_request(&url, body).await
     }
 
+    /// Removes the stored fields of a document while keeping it in the search index,
+    /// the document can still be found but cannot be read anymore.
+    pub fn corrupt_document(&mut self, document_id: &str) {
+        let index = self.data.db.open_index(&self.uid).unwrap();
+        self.data.db.main_write::<_, _, meilisearch_core::Error>(|writer| {
+            let docid = index.main.external_to_internal_docid(writer, document_id)?.unwrap();
+            index.documents_fields.del_all_document_fields(writer, docid)?;
+            Ok(())
+        }).unwrap();
+    }
+
     pub async fn get_all_updates_status(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/updates", self.uid);
         self.get_request(&url).await
@@ -299,6 +394,14 @@ impl Server {
         self.post_request_async(&url, body).await;
     }
 
+    /// adds the documents to another index of the server and waits for their indexing.
+    pub async fn add_or_replace_multiple_documents_in(&mut self, index_uid: &str, body: Value) {
+        let url = format!("/indexes/{}/documents", index_uid);
+        let (response, status_code) = self.post_request(&url, body).await;
+        assert_eq!(status_code, 202);
+        self.wait_index_update_id(index_uid, response["updateId"].as_u64().unwrap()).await;
+    }
+
     pub async fn add_or_replace_multiple_documents_sync(
         &mut self,
         body: Value,
@@ -317,6 +420,21 @@ impl Server {
         self.delete_request_async(&url).await;
     }
 
+    pub async fn rebuild_facets(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/attributes-for-faceting/rebuild", self.uid);
+        self.post_request_async(&url, Value::Null).await
+    }
+
+    pub async fn tokenize(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/tokenize", self.uid);
+        self.post_request(&url, body).await
+    }
+
+    pub async fn format_preview(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/format-preview", self.uid);
+        self.post_request(&url, body).await
+    }
+
     pub async fn get_document(&mut self, document_id: impl ToString) -> (Value, StatusCode) {
         let url = format!(
             "/indexes/{}/documents/{}",
@@ -0,0 +1,36 @@
+use meilisearch_http::Opt;
+use structopt::StructOpt;
+
+mod common;
+
+#[actix_rt::test]
+async fn cors_allows_the_configured_origins() {
+    let mut server = common::Server::with_uid("movies");
+
+    let opt = Opt::from_iter(&["meilisearch", "--cors-allowed-origins", "https://example.com,https://meilisearch.com"]);
+    assert_eq!(opt.cors_allowed_origins, vec!["https://example.com", "https://meilisearch.com"]);
+
+    let (allowed_origin, status_code) = server
+        .get_request_with_origin("/health", "https://example.com", &opt.cors_allowed_origins)
+        .await;
+    assert_eq!(status_code, 200);
+    assert_eq!(allowed_origin.as_deref(), Some("https://example.com"));
+
+    // an origin that is not configured is rejected
+    let (allowed_origin, status_code) = server
+        .get_request_with_origin("/health", "https://evil.com", &opt.cors_allowed_origins)
+        .await;
+    assert_eq!(status_code, 400);
+    assert_eq!(allowed_origin, None);
+
+    // the wildcard must be configured explicitly
+    let (allowed_origin, _status_code) = server
+        .get_request_with_origin("/health", "https://example.com", &[])
+        .await;
+    assert_eq!(allowed_origin, None);
+
+    let (allowed_origin, _status_code) = server
+        .get_request_with_origin("/health", "https://example.com", &["*".to_string()])
+        .await;
+    assert_eq!(allowed_origin.as_deref(), Some("*"));
+}
@@ -215,3 +215,213 @@ async fn documents_with_same_id_are_overwritten() {
     assert_eq!(response.as_array().unwrap().len(), 1);
     assert_eq!(response.as_array().unwrap()[0].as_object().unwrap()["content"], "test2");
 }
+
+#[actix_rt::test]
+async fn primary_key_is_chosen_among_the_candidates() {
+    let mut server = common::Server::with_uid("movies");
+    let body = json!({
+        "uid": "movies",
+        "primaryKeyCandidates": ["isbn", "reference", "title"],
+    });
+    let (response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["primaryKey"], json!(null));
+
+    // `isbn` is missing from a document and `reference` is not unique
+    let documents = json!([
+        { "isbn": 1, "reference": "a", "title": "carol" },
+        { "reference": "a", "title": "wonder_woman" },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (response, _status_code) = server.get_index().await;
+    assert_eq!(response["primaryKey"], "title");
+
+    // the chosen primary key is kept for the next documents
+    let documents = json!([{ "isbn": 2, "title": "taxi_driver" }]);
+    server.add_or_replace_multiple_documents(documents).await;
+    let (response, status_code) = server.get_document("taxi_driver").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["isbn"], 2);
+
+    let (response, _status_code) = server.get_index().await;
+    assert_eq!(response["primaryKey"], "title");
+}
+
+#[actix_rt::test]
+async fn primary_key_candidates_with_documents() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies" })).await;
+
+    let documents = json!([
+        { "title": "carol", "comment": "comment test" },
+        { "title": "wonder_woman", "comment": "comment test" },
+    ]);
+    let url = "/indexes/movies/documents?primaryKeyCandidates=comment,title";
+    server.post_request_async(url, documents).await;
+
+    let (response, _status_code) = server.get_index().await;
+    assert_eq!(response["primaryKey"], "title");
+
+    // none of the candidates can be used
+    let mut server = common::Server::with_uid("books");
+    server.create_index(json!({ "uid": "books" })).await;
+    let documents = json!([{ "title": "Carol" }]);
+    let url = "/indexes/books/documents?primaryKeyCandidates=isbn";
+    let (_response, status_code) = server.post_request(url, documents).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn index_payload_size_limit_overrides_the_global_one() {
+    let opt = meilisearch_http::option::Opt {
+        http_payload_size_limit: 100,
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("movies", opt);
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "The Lord of the Rings: The Fellowship of the Ring" },
+        { "id": 2, "title": "The Lord of the Rings: The Two Towers" },
+    ]);
+    let (response, status_code) = server.add_or_replace_multiple_documents_sync(documents.clone()).await;
+    assert_eq!(status_code, 413);
+    assert_eq!(response["errorCode"], "payload_too_large");
+
+    server.update_all_settings(json!({ "payloadSizeLimit": 1000 })).await;
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response["payloadSizeLimit"], 1000);
+
+    server.add_or_replace_multiple_documents(documents).await;
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+async fn invalid_documents_are_skipped_and_reported() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "The Lord of the Rings: The Fellowship of the Ring" },
+        { "title": "The Lord of the Rings: The Two Towers" },
+        { "id": "the return", "title": "The Lord of the Rings: The Return of the King" },
+        { "id": 4, "title": "The Hobbit" },
+    ]);
+
+    let url = "/indexes/movies/documents?skipInvalidDocuments=true";
+    let (response, status_code) = server.post_request(url, documents).await;
+    assert_eq!(status_code, 202);
+    let rejected = response["rejectedDocuments"].as_array().unwrap();
+    assert_eq!(rejected.len(), 2);
+    assert_eq!(rejected[0]["index"], 1);
+    assert_eq!(rejected[0]["error"], "Primary key is missing.");
+    assert_eq!(rejected[1]["index"], 2);
+
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+async fn compressed_documents_are_decompressed() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    // `[{"id":1,"title":"hello"},{"id":2,"title":"world"}]` compressed with gzip
+    let body = vec![31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 139, 174, 86, 202, 76, 81, 178, 50, 212, 81, 42, 201, 44, 201, 73, 85, 178, 82, 202, 72, 205, 201, 201, 87, 170, 213, 129, 72, 24, 33, 36, 202, 243, 139, 114, 82, 148, 106, 99, 1, 239, 129, 128, 37, 51, 0, 0, 0];
+    let headers = [("Content-Type", "application/json"), ("Content-Encoding", "gzip")];
+    let (response, status_code) = server.post_request_raw("/indexes/movies/documents", body, &headers).await;
+    assert_eq!(status_code, 202, "{}", response);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+
+    // the documents must be sent as JSON
+    let body = br#"[{"id":3,"title":"hello"}]"#.to_vec();
+    let (response, status_code) = server
+        .post_request_raw("/indexes/movies/documents", body, &[("Content-Type", "text/plain")])
+        .await;
+    assert_eq!(status_code, 415);
+    assert_eq!(response["errorCode"], "unsupported_media_type");
+}
+
+#[actix_rt::test]
+async fn documents_with_a_composite_primary_key() {
+    let mut server = common::Server::with_uid("products");
+    let body = json!({
+        "uid": "products",
+        "primaryKey": "id",
+        "primaryKeyComponents": ["store_id", "sku"],
+    });
+    let (response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201, "{}", response);
+
+    let documents = json!([
+        { "store_id": 1, "sku": "apple", "price": 3 },
+        { "store_id": 2, "sku": "apple", "price": 4 },
+        { "store_id": 1, "sku": "pear", "price": 5 },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 3);
+
+    let (response, status_code) = server.get_document("2-apple").await;
+    assert_eq!(status_code, 200, "{}", response);
+    assert_eq!(response["id"], "2-apple");
+    assert_eq!(response["store_id"], 2);
+    assert_eq!(response["price"], 4);
+
+    // the document with the same components is replaced
+    let documents = json!([{ "store_id": 2, "sku": "apple", "price": 6 }]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 3);
+    let (response, _status_code) = server.get_document("2-apple").await;
+    assert_eq!(response["price"], 6);
+
+    // a document carrying its id is updated without the attributes forming it
+    let (response, _status_code) = server
+        .put_request("/indexes/products/documents", json!([{ "id": "2-apple", "price": 7 }]))
+        .await;
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+    let (response, _status_code) = server.get_document("2-apple").await;
+    assert_eq!(response["price"], 7);
+    assert_eq!(response["sku"], "apple");
+
+    // the dashes of the attributes cannot make two documents share an id
+    let documents = json!([
+        { "store_id": "a-b", "sku": "c" },
+        { "store_id": "a", "sku": "b-c" },
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+    let (response, _status_code) = server.get_all_documents().await;
+    assert_eq!(response.as_array().unwrap().len(), 5);
+
+    // an id that does not match the attributes forming it is rejected
+    let documents = json!([{ "id": "1-apple", "store_id": 1, "sku": "pear" }]);
+    let (response, _status_code) = server.post_request("/indexes/products/documents", documents).await;
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+    let (response, _status_code) = server.get_update_status(response["updateId"].as_u64().unwrap()).await;
+    assert_eq!(response["status"], "failed");
+
+    // the components of an index are only set along with its primary key
+    let documents = json!([{ "store_id": 3, "sku": "fig" }]);
+    let (_response, status_code) = server
+        .post_request("/indexes/products/documents?primaryKeyComponents=sku", documents)
+        .await;
+    assert_eq!(status_code, 400);
+
+    // the components are required with the attribute they form
+    let body = json!({ "uid": "stores", "primaryKeyComponents": ["store_id"] });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 400);
+}
@@ -32,3 +32,24 @@ async fn delete_batch() {
         assert_eq!(status_code, 404);
     }
 }
+
+#[actix_rt::test]
+async fn clear_all_documents_keeps_settings() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(serde_json::json!({ "attributesForFaceting": ["color"] })).await;
+    let (settings, _status_code) = server.get_all_settings().await;
+
+    server.clear_all_documents().await;
+
+    let (response, _status_code) = server.search_post(serde_json::json!({ "q": "a" })).await;
+    assert!(response["hits"].as_array().unwrap().is_empty());
+
+    // the facet store is cleared too, no stale count is returned
+    let query = serde_json::json!({ "facetsDistribution": ["color"] });
+    let (response, _status_code) = server.search_post(query).await;
+    let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+    assert!(colors.values().all(|count| count == 0));
+
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response, settings);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use serde_json::json;
+
+#[actix_rt::test]
+async fn get_document() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_document(50).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["id"], 50);
+    assert!(response.get("name").is_some());
+
+    let (response, status_code) = server.get_document(2048).await;
+    assert_eq!(status_code, 404);
+    assert_eq!(response["errorCode"], "document_not_found");
+}
+
+#[actix_rt::test]
+async fn get_document_with_attributes_to_retrieve() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server
+        .get_request("/indexes/test/documents/50?attributesToRetrieve=name,age")
+        .await;
+    assert_eq!(status_code, 200);
+
+    let document = response.as_object().unwrap();
+    assert_eq!(document.len(), 2);
+    assert!(document.contains_key("name"));
+    assert!(document.contains_key("age"));
+
+    let (_response, status_code) = server
+        .get_request("/indexes/test/documents/2048?attributesToRetrieve=name")
+        .await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn get_document_version() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let (response, status_code) = server.get_request("/indexes/test/documents/1?showVersion=true").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["_version"], 1);
+
+    // the version is only returned on demand
+    let (response, _status_code) = server.get_document(1).await;
+    assert!(response.get("_version").is_none());
+
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello world" }])).await;
+
+    let (response, _status_code) = server.get_request("/indexes/test/documents/1?showVersion=true").await;
+    assert_eq!(response["title"], "hello world");
+    assert_eq!(response["_version"], 2);
+
+    let (response, _status_code) = server.search_post(json!({ "q": "hello", "showVersion": true })).await;
+    assert_eq!(response["hits"][0]["_version"], 2);
+}
+
+fn parse_ndjson(body: &str) -> Vec<serde_json::Value> {
+    let mut documents: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    documents.sort_by_key(|document| document["id"].as_u64());
+    documents
+}
+
+#[actix_rt::test]
+async fn export_and_reimport_documents() {
+    let mut server = common::Server::test_server().await;
+
+    let (body, status_code) = server.get_request_text("/indexes/test/export").await;
+    assert_eq!(status_code, 200);
+    let exported = parse_ndjson(&body);
+    assert_eq!(exported.len(), 77);
+
+    let mut fresh = common::Server::with_uid("test");
+    fresh.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    fresh.add_or_replace_multiple_documents(json!(exported)).await;
+
+    let (body, status_code) = fresh.get_request_text("/indexes/test/export").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(parse_ndjson(&body), exported);
+
+    // only the documents matching the filters are exported
+    let (body, status_code) = server.get_request_text("/indexes/test/export?filters=color%20%3D%20green").await;
+    assert_eq!(status_code, 200);
+    let green = parse_ndjson(&body);
+    assert_eq!(green.len(), 25);
+    assert!(green.iter().all(|document| document["color"] == "green"));
+}
@@ -0,0 +1,65 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn format_preview_matches_search() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 1,
+        "attributesToRetrieve": ["about"],
+        "attributesToCrop": ["about:20"],
+        "attributesToHighlight": ["about"],
+    });
+    let (response, _status_code) = server.search_post(query).await;
+    let hit = &response["hits"][0];
+
+    let body = json!({
+        "text": hit["about"],
+        "q": "exercitation",
+        "crop": true,
+        "cropLength": 20,
+    });
+    let (response, status_code) = server.format_preview(body).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["_formatted"], hit["_formatted"]["about"]);
+    assert!(response["_formatted"].as_str().unwrap().contains("<em>"));
+
+    // without any crop the whole text is highlighted
+    let query = json!({
+        "q": "exercitation",
+        "limit": 1,
+        "attributesToRetrieve": ["about"],
+        "attributesToHighlight": ["about"],
+    });
+    let (response, _status_code) = server.search_post(query).await;
+    let hit = &response["hits"][0];
+
+    let body = json!({ "text": hit["about"], "q": "exercitation" });
+    let (response, status_code) = server.format_preview(body).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["_formatted"], hit["_formatted"]["about"]);
+}
+
+#[actix_rt::test]
+async fn format_preview_unknown_index() {
+    let mut server = common::Server::with_uid("test");
+
+    let (_response, status_code) = server.format_preview(json!({ "text": "hello", "q": "hello" })).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn format_preview_long_text() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+
+    // the second match is too far in the text to be highlighted
+    let text = format!("hello {}hello", "a ".repeat(40_000));
+    let (response, status_code) = server.format_preview(json!({ "text": text, "q": "hello" })).await;
+    assert_eq!(status_code, 200);
+    let expected = format!("<em>hello</em> {}hello", "a ".repeat(40_000));
+    assert_eq!(response["_formatted"], expected);
+}
@@ -341,7 +341,7 @@ async fn placeholder_test_faceted_search_invalid() {
         "attributesForFaceting": ["color", "tags"]
     });
     server.update_all_settings(body).await;
-    // empty arrays are error
+    // empty arrays do not filter anything
     // []
     let query = json!({
         "facetFilters": []
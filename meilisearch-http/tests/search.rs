@@ -1,6 +1,7 @@
 use std::convert::Into;
 
 use assert_json_diff::assert_json_eq;
+use chrono::{DateTime, Utc};
 use serde_json::json;
 use serde_json::Value;
 
@@ -881,6 +882,35 @@ async fn search_with_attributes_to_highlight_and_matches() {
     });
 }
 
+#[actix_rt::test]
+async fn search_with_excluded_attribute_to_highlight() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "cherry",
+        "limit": 1,
+        "attributesToHighlight": ["*", "-email"],
+    });
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        let formatted = &response["hits"][0]["_formatted"];
+        assert_eq!(formatted["name"], "<em>Cherry</em> Orr");
+        assert_eq!(formatted["email"], "cherryorr@chorizon.com");
+    });
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 1,
+        "attributesToCrop": ["*", "-about"],
+        "cropLength": 5,
+    });
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hit = &response["hits"][0];
+        assert_eq!(hit["_formatted"]["about"], hit["about"]);
+    });
+}
+
 #[actix_rt::test]
 async fn search_with_attributes_to_highlight_and_matches_and_crop() {
     let mut server = common::Server::test_server().await;
@@ -1035,6 +1065,27 @@ async fn search_with_differents_attributes_3() {
     });
 }
 
+#[actix_rt::test]
+async fn search_with_crop_without_highlight() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 1,
+        "attributesToCrop": ["about:20"],
+    });
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hit = &response["hits"][0];
+        let formatted = hit["_formatted"].as_object().unwrap();
+        assert_eq!(formatted["about"], "Exercitation officia");
+        // the attributes not cropped are copied as is
+        assert_eq!(formatted["name"], hit["name"]);
+        // nothing is highlighted
+        assert!(formatted.values().all(|value| !value.to_string().contains("<em>")));
+    });
+}
+
 #[actix_rt::test]
 async fn search_with_differents_attributes_4() {
     let mut server = common::Server::test_server().await;
@@ -1370,16 +1421,15 @@ async fn test_faceted_search_invalid() {
         "attributesForFaceting": ["color", "tags"]
     });
     server.update_all_settings(body).await;
-    // empty arrays are error
+    // empty arrays do not filter anything
     // []
     let query = json!({
         "q": "a",
         "facetFilters": []
     });
 
-    test_post_get_search!(server, query, |response, status_code| {
-        assert_eq!(status_code, 400);
-        assert_eq!(response["errorCode"], "invalid_facet");
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 200);
     });
     // [[]]
     let query = json!({
@@ -1387,9 +1437,8 @@ async fn test_faceted_search_invalid() {
         "facetFilters": [[]]
     });
 
-    test_post_get_search!(server, query, |response, status_code| {
-        assert_eq!(status_code, 400);
-        assert_eq!(response["errorCode"], "invalid_facet");
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 200);
     });
 
     // ["color:green", []]
@@ -1399,8 +1448,12 @@ async fn test_faceted_search_invalid() {
     });
 
     test_post_get_search!(server, query, |response, status_code| {
-        assert_eq!(status_code, 400);
-        assert_eq!(response["errorCode"], "invalid_facet");
+        assert_eq!(status_code, 200);
+        assert!(response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|value| value.get("color").unwrap() == "green"));
     });
 
     // too much depth
@@ -1512,10 +1565,32 @@ async fn test_facet_count() {
         "q": "a",
         "facetsDistribution": ["gender"]
     });
-    test_post_get_search!(server, query, |_response, status_code|{
+    test_post_get_search!(server, query, |response, status_code|{
+        assert_eq!(status_code, 400);
+        assert!(response["message"].as_str().unwrap().contains("attribute gender is not set as facet"));
+    });
+
+    // unknown attribute passed:
+    let query = json!({
+        "q": "a",
+        "facetsDistribution": ["unknown"]
+    });
+    test_post_get_search!(server, query, |response, status_code|{
         assert_eq!(status_code, 400);
+        assert!(response["message"].as_str().unwrap().contains("attribute unknown is not set as facet"));
     });
 
+    // a facet without any value in the results returns an empty distribution
+    let query = json!({
+        "q": "a",
+        "filters": "color = unexisting",
+        "facetsDistribution": ["color"]
+    });
+    test_post_get_search!(server, query, |response, status_code|{
+        assert_eq!(status_code, 200);
+        let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+        assert!(colors.values().all(|count| count == 0));
+    });
 }
 
 #[actix_rt::test]
@@ -1688,3 +1763,3021 @@ async fn update_documents_with_facet_distribution() {
     let (response2, _) = server.search_post(search).await;
     assert_json_eq!(expected_facet_distribution, response2["facetsDistribution"].clone());
 }
+
+#[actix_rt::test]
+async fn search_on_empty_index() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+
+    let query = json!({
+        "q": "a",
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["nbHits"], 0);
+        assert!(response["hits"].as_array().unwrap().is_empty());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_locale_hint() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        {
+            "id": 1,
+            "title": "Apfel Strudel mit Sahne"
+        },
+        {
+            "id": 2,
+            "title": "Schwarzwälder Kirschtorte"
+        }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // without hint the compound word is split in its most frequent components
+    let query = json!({
+        "q": "apfelstrudel",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    });
+
+    let query = json!({
+        "q": "apfelstrudel",
+        "locale": "de-DE",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    });
+
+    // english words are not compounds, the query word is kept as is
+    let query = json!({
+        "q": "apfelstrudel",
+        "locale": "en",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"].as_array().unwrap().is_empty());
+    });
+
+    // the index locale is used when the query does not specify any
+    server.update_all_settings(json!({ "locale": "en" })).await;
+    let query = json!({
+        "q": "apfelstrudel",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"].as_array().unwrap().is_empty());
+    });
+
+    let query = json!({
+        "q": "apfelstrudel",
+        "locale": "not a locale",
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_include_primary_key() {
+    let mut server = common::Server::test_server().await;
+    server.update_displayed_attributes(json!(["name", "color"])).await;
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 3,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|hit| hit.get("id").is_none()));
+    });
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 3,
+        "includePrimaryKey": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|hit| hit.get("id").is_some() && hit.get("name").is_some()));
+    });
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 3,
+        "attributesToRetrieve": ["color"],
+        "includePrimaryKey": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|hit| hit.as_object().unwrap().len() == 2 && hit.get("id").is_some()));
+    });
+}
+
+#[actix_rt::test]
+async fn facet_distribution_with_filters() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "tags"] })).await;
+
+    let query = json!({
+        "q": "a",
+        "facetsDistribution": ["color"],
+    });
+    let (response, _status_code) = server.search_post(query).await;
+    let unfiltered_colors = response["facetsDistribution"]["color"].as_object().unwrap().clone();
+    assert!(unfiltered_colors.iter().filter(|(_, count)| count.as_u64().unwrap() > 0).count() > 1);
+
+    let query = json!({
+        "q": "a",
+        "filters": "color = green",
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+        for (color, count) in colors {
+            if color == "green" {
+                assert_eq!(count, &unfiltered_colors["green"]);
+            } else {
+                assert_eq!(count, 0);
+            }
+        }
+    });
+
+    // placeholder search
+    let query = json!({
+        "filters": "color = green",
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+        assert!(colors["green"].as_u64().unwrap() > 0);
+        assert!(colors.iter().all(|(color, count)| color == "green" || count == 0));
+    });
+}
+
+#[actix_rt::test]
+async fn sticky_facets_distribution() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "tags"] })).await;
+
+    let query = json!({
+        "q": "a",
+        "facetsDistribution": ["color"],
+    });
+    let (response, _status_code) = server.search_post(query).await;
+    let unfiltered_colors = response["facetsDistribution"]["color"].as_object().unwrap().clone();
+
+    // the color filter is ignored when counting the colors
+    let query = json!({
+        "q": "a",
+        "facetFilters": ["color:green"],
+        "facetsDistribution": ["color"],
+        "stickyFacets": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"].as_array().unwrap().iter().all(|hit| hit["color"] == "green"));
+        let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+        assert_eq!(colors, &unfiltered_colors);
+    });
+
+    // the filters on the other facets still apply
+    let query = json!({
+        "q": "a",
+        "facetFilters": ["color:green", "tags:bug"],
+        "facetsDistribution": ["color"],
+        "stickyFacets": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let colors = response["facetsDistribution"]["color"].as_object().unwrap();
+        assert!(colors.iter().all(|(color, count)| count.as_u64().unwrap() <= unfiltered_colors[color].as_u64().unwrap()));
+        assert_ne!(colors, &unfiltered_colors);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_snippet() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "searchableAttributes": ["title", "body"] })).await;
+
+    let documents = json!([
+        {
+            "id": 1,
+            "title": "Coral, a short introduction to the wonders of the ocean",
+            "body": "Divers love the coral reef because the coral reef shelters thousands of fishes, the ocean is full of life."
+        }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "coral reef",
+        "attributesToCrop": ["title", "body"],
+        "cropLength": 20,
+        "snippet": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let snippet = &response["hits"][0]["_snippet"];
+        assert_eq!(snippet["attribute"], "body");
+        assert!(snippet["text"].as_str().unwrap().contains("coral reef"));
+    });
+
+    // the snippet is only returned when asked
+    let query = json!({
+        "q": "coral reef",
+        "attributesToCrop": ["title", "body"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"][0].get("_snippet").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_snippet_prefers_searchable_attributes_order() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "searchableAttributes": ["title", "body"] })).await;
+
+    let documents = json!([
+        {
+            "id": 1,
+            "title": "A guide to the coral gardens",
+            "body": "Some coral is hard, some coral is soft, and every coral needs light."
+        }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "coral",
+        "attributesToCrop": ["title", "body"],
+        "cropLength": 30,
+        "snippet": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let snippet = &response["hits"][0]["_snippet"];
+        assert_eq!(snippet["attribute"], "title");
+        assert!(snippet["text"].as_str().unwrap().contains("coral"));
+    });
+
+    // the most important attribute follows the searchable attributes order
+    server.update_all_settings(json!({ "searchableAttributes": ["body", "title"] })).await;
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"][0]["_snippet"]["attribute"], "body");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_auto_relevance_cutoff() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "United Kingdom" },
+        { "id": 2, "title": "The United Kingdom of Great Britain" },
+        { "id": 3, "title": "Unitet Kingdon" },
+        { "id": 4, "title": "The unitet kingdon of typos" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "united kingdom" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 4);
+    });
+
+    // the documents with typos are far less relevant and are cut
+    let query = json!({
+        "q": "united kingdom",
+        "autoRelevanceCutoff": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit["id"] == 1 || hit["id"] == 2));
+        assert_eq!(response["nbHits"], 2);
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+
+    // the drop right after the requested page is found too
+    let query = json!({
+        "q": "united kingdom",
+        "autoRelevanceCutoff": true,
+        "limit": 2,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    // the next page does not return the documents cut from the first one
+    let query = json!({
+        "q": "united kingdom",
+        "autoRelevanceCutoff": true,
+        "offset": 2,
+        "limit": 2,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    // a low sensitivity only cuts on larger drops
+    let query = json!({
+        "q": "united kingdom",
+        "autoRelevanceCutoff": true,
+        "cutoffSensitivity": 0.1,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 4);
+    });
+
+    let query = json!({
+        "q": "united kingdom",
+        "autoRelevanceCutoff": true,
+        "cutoffSensitivity": 2.0,
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_score() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation nostrud",
+        "showRankingScore": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let scores: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["_rankingScore"].as_f64().unwrap())
+            .collect();
+        assert!(!scores.is_empty());
+        assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    });
+
+    let query = json!({ "q": "exercitation nostrud" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"][0].get("_rankingScore").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_exhaustive_nb_hits() {
+    let mut server = common::Server::test_server().await;
+
+    // without filter nor distinct rule the number of hits is always exact
+    let query = json!({ "q": "exercitation" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+
+    // without the option the number of hits does not take the filter into account
+    let query = json!({
+        "filters": "color = green",
+        "limit": 100,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 25);
+        assert_eq!(response["nbHits"], 77);
+        assert_eq!(response["exhaustiveNbHits"], false);
+    });
+
+    let query = json!({
+        "filters": "color = green",
+        "limit": 100,
+        "exhaustiveNbHits": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 25);
+        assert_eq!(response["nbHits"], 25);
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+
+    let query = json!({
+        "q": "exercitation",
+        "filters": "color = green",
+        "exhaustiveNbHits": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let nb_hits = response["nbHits"].as_u64().unwrap() as usize;
+        assert!(nb_hits <= 25);
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+}
+
+#[actix_rt::test]
+async fn search_within_document_ids() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation",
+        "withinDocumentIds": ["2", "7"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 7]);
+    });
+
+    // the restriction applies on top of the filters
+    let query = json!({
+        "q": "exercitation",
+        "filters": "color = green",
+        "withinDocumentIds": ["2", "7"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], 7);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_exists_filter() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "a picture of a cat", "image": "cat.png" },
+        { "id": 2, "title": "a story about a cat" },
+        { "id": 3, "title": "a picture of a dog", "image": "dog.png" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "a",
+        "filters": "image EXISTS",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.get("image").is_some()));
+    });
+
+    let query = json!({
+        "q": "a",
+        "filters": "image NOT EXISTS",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], 2);
+    });
+
+    let query = json!({
+        "q": "a",
+        "filters": "NOT image EXISTS OR id = 3",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_in_list_filter() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "a",
+        "filters": "color IN [green, 'blue']",
+        "limit": 50,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert!(hits.iter().any(|hit| hit["color"] == "green"));
+        assert!(hits.iter().any(|hit| hit["color"] == "blue"));
+        assert!(hits.iter().all(|hit| hit["color"] == "green" || hit["color"] == "blue"));
+    });
+
+    // the IN list is equivalent to OR clauses
+    let in_list = json!({ "q": "a", "filters": "color IN [green, blue] AND age > 30", "limit": 50 });
+    let or_clauses = json!({ "q": "a", "filters": "(color = green OR color = blue) AND age > 30", "limit": 50 });
+    let (in_list_response, _status_code) = server.search_post(in_list).await;
+    let (or_clauses_response, _status_code) = server.search_post(or_clauses).await;
+    assert_eq!(in_list_response["hits"], or_clauses_response["hits"]);
+}
+
+#[actix_rt::test]
+async fn search_with_unknown_parameter() {
+    let mut server = common::Server::test_server().await;
+
+    // unknown parameters are rejected by default
+    let (_response, status_code) = server.search_get("q=a&unknownParam=1").await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.search_post(json!({ "q": "a", "unknownParam": 1 })).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_unknown_parameter_in_lenient_mode() {
+    let opt = meilisearch_http::option::Opt {
+        lenient_search_parameters: true,
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let (response, status_code) = server.search_get("q=hello&unknownParam=1&limit=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["_warnings"], json!(["unknown search parameter `unknownParam` has been ignored"]));
+
+    let (response, status_code) = server.search_post(json!({ "q": "hello", "unknownParam": 1, "limit": 1 })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["_warnings"], json!(["unknown search parameter `unknownParam` has been ignored"]));
+
+    // no warnings are returned when all the parameters are known
+    let (response, _status_code) = server.search_post(json!({ "q": "hello" })).await;
+    assert!(response.get("_warnings").is_none());
+}
+
+#[actix_rt::test]
+async fn search_with_too_many_facet_filters() {
+    let opt = meilisearch_http::option::Opt {
+        max_facet_filters: Some(2),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "size"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "shirt", "color": "blue", "size": "xl" },
+        { "id": 2, "title": "shirt", "color": "red", "size": "m" }
+    ])).await;
+
+    let query = json!({
+        "q": "shirt",
+        "facetFilters": ["color:blue", ["size:xl", "size:m"]],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    });
+
+    let query = json!({
+        "q": "shirt",
+        "facetFilters": ["color:blue", "size:xl", "color:red"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_geo_bounding_box_filter() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "hotel in paris", "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": 2, "name": "hotel in lyon", "_geo": { "lat": 45.76, "lng": 4.83 } },
+        { "id": 3, "name": "hotel in suva", "_geo": { "lat": -18.14, "lng": 178.44 } },
+        { "id": 4, "name": "hotel in taveuni", "_geo": { "lat": -16.85, "lng": -179.97 } },
+        { "id": 5, "name": "hotel nowhere" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let sorted_hit_ids = |response: &Value| {
+        let mut ids = common::hit_ids(response);
+        ids.sort_unstable();
+        ids
+    };
+
+    let query = json!({
+        "q": "hotel",
+        "filters": "_geoBoundingBox([49, 2], [48.5, 2.7])",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![1]);
+    });
+
+    let query = json!({
+        "q": "hotel",
+        "filters": "_geoBoundingBox([50, -5], [40, 10])",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![1, 2]);
+    });
+
+    // the box crosses the antimeridian
+    let query = json!({
+        "q": "hotel",
+        "filters": "_geoBoundingBox([-12, 176], [-21, -178])",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![3, 4]);
+    });
+
+    let query = json!({
+        "q": "hotel",
+        "filters": "NOT _geoBoundingBox([-12, 176], [-21, -178])",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![1, 2, 5]);
+    });
+
+    let query = json!({
+        "q": "hotel",
+        "filters": "_geoBoundingBox([10, 176], [20, -178])",
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "invalid_filter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_warnings() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation",
+        "attributesToCrop": ["about", "unknown"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["_warnings"], json!(["attribute `unknown` in attributesToCrop does not exist"]));
+    });
+
+    let query = json!({
+        "q": "exercitation",
+        "attributesToCrop": ["about"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("_warnings").is_none());
+    });
+
+    server.update_stop_words(json!(["the", "of"])).await;
+    let query = json!({ "q": "the of" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["_warnings"], json!(["the query only contains stop words"]));
+    });
+}
+
+#[actix_rt::test]
+async fn search_warns_about_ineffective_ranking_rules() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(name)", "asc(price)"],
+    })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "hotel", "price": 10 },
+        { "id": 2, "name": "hotel", "price": 30 }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "hotel" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(
+            response["_warnings"],
+            json!(["ranking rule `desc(name)` has no effect, the attribute has no numeric value in the hits"])
+        );
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_rules_override() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(price)"],
+    })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "hotel", "price": 30 },
+        { "id": 2, "name": "hotel", "price": 10 },
+        { "id": 3, "name": "hotel", "price": 20 }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "hotel", "rankingRules": ["asc(price)", "typo", "words"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![2, 3, 1]);
+    });
+
+    let query = json!({ "q": "hotel", "rankingRules": ["desc(price)", "typo", "words"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![1, 3, 2]);
+    });
+
+    // the stored ranking rules are left untouched
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response["rankingRules"][6], "asc(price)");
+
+    let query = json!({ "q": "hotel", "rankingRules": ["typo", "typo"] });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+
+    let query = json!({ "q": "hotel", "rankingRules": ["unknown"] });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_pagination_with_sort_ties() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(price)"],
+    })).await;
+
+    // most of the documents share the same price
+    let documents: Vec<Value> = (0..20)
+        .map(|id| {
+            let price = match id {
+                7 => 30,
+                13 => 5,
+                _ => 10,
+            };
+            json!({ "id": id, "name": "hotel", "price": price })
+        })
+        .collect();
+    server.add_or_replace_multiple_documents(Value::Array(documents)).await;
+
+    for q in &[json!("hotel"), Value::Null] {
+        let (response, _status_code) = server.search_post(json!({ "q": q, "limit": 20 })).await;
+        let all_ids = common::hit_ids(&response);
+        assert_eq!(all_ids.len(), 20);
+        assert_eq!(all_ids[0], 7);
+        assert_eq!(all_ids[19], 13);
+
+        let mut paginated_ids = Vec::new();
+        for offset in (0..20).step_by(3) {
+            let (response, _status_code) = server.search_post(json!({ "q": q, "offset": offset, "limit": 3 })).await;
+            paginated_ids.extend(common::hit_ids(&response));
+        }
+        assert_eq!(paginated_ids, all_ids);
+    }
+}
+
+#[actix_rt::test]
+async fn search_with_geo_and_attribute_sort() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(price)"],
+    })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "hotel", "price": 10, "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": 2, "name": "hotel", "price": 30, "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": 3, "name": "hotel", "price": 50, "_geo": { "lat": 45.76, "lng": 4.83 } },
+        { "id": 4, "name": "hotel", "price": 20, "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": 5, "name": "hotel", "price": 40, "_geo": { "lat": 48.86, "lng": 2.34 } }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // the ties in distance are broken by price
+    let query = json!({
+        "q": "hotel",
+        "sort": ["_geoPoint(48.85, 2.35):asc", "price:desc"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![2, 4, 1, 5, 3]);
+    });
+
+    let query = json!({
+        "q": "hotel",
+        "sort": ["_geoPoint(48.85, 2.35):desc", "price:asc"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![3, 5, 1, 4, 2]);
+    });
+
+    // the placeholder searches are sorted the same way
+    let query = json!({
+        "sort": ["_geoPoint(48.85, 2.35):asc", "price:desc"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![2, 4, 1, 5, 3]);
+    });
+
+    let query = json!({
+        "filters": "price > 15",
+        "sort": ["_geoPoint(48.85, 2.35):desc", "price:asc"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![3, 5, 4, 2]);
+    });
+
+    let query = json!({
+        "q": "hotel",
+        "sort": ["_geoPoint(48.85):asc"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_matched_terms() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "Red apple", "description": "a fruit" },
+        { "id": 2, "title": "Green apple", "description": "another fruit" },
+        { "id": 3, "title": "Car", "description": "a red one" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "red apple",
+        "showMatchedTerms": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 3);
+        for hit in hits {
+            let expected = match hit["id"].as_u64().unwrap() {
+                1 => json!(["apple", "red"]),
+                2 => json!(["apple"]),
+                3 => json!(["red"]),
+                _ => unreachable!(),
+            };
+            assert_eq!(hit["_matchedTerms"], expected);
+        }
+    });
+
+    let query = json!({ "q": "red apple" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"][0].get("_matchedTerms").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_debug_filter_counts() {
+    let mut server = common::Server::test_server().await;
+
+    // the placeholder search tests every document against the filter to fill the page
+    let query = json!({
+        "filters": "color = green",
+        "limit": 100,
+        "debug": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 25);
+        assert_eq!(response["_debug"], json!({ "filterEvaluations": 77, "filterPassed": 25 }));
+    });
+
+    let query = json!({
+        "filters": "color = green",
+        "limit": 100,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("_debug").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_debug_normalized_query() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "stopWords": ["the"],
+        "synonyms": { "ny": ["nyc"], "nyc": ["ny"] },
+    })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "the NYC subway" }])).await;
+
+    let query = json!({
+        "q": "The NY subway",
+        "debug": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["_debug"]["normalizedQuery"], json!([["ny", "nyc"], ["subway"]]));
+    });
+
+    // the stop words are kept when they are not removed from the query
+    let query = json!({
+        "q": "The NY subway",
+        "debug": true,
+        "removeStopWords": false,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["_debug"]["normalizedQuery"], json!([["the"], ["ny", "nyc"], ["subway"]]));
+    });
+
+    let query = json!({ "q": "The NY subway" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("_debug").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_returns_index_updated_at() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let updated_at = |response: &Value| -> DateTime<Utc> {
+        response["indexUpdatedAt"].as_str().unwrap().parse().unwrap()
+    };
+
+    let (response, _status_code) = server.search_post(json!({ "q": "hello" })).await;
+    let first_update = updated_at(&response);
+
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "hello world" }])).await;
+
+    let (response, _status_code) = server.search_post(json!({ "q": "hello" })).await;
+    assert!(updated_at(&response) > first_update);
+}
+
+#[actix_rt::test]
+async fn search_skips_unreadable_documents() {
+    let documents = json!([
+        { "id": 1, "title": "hello" },
+        { "id": 2, "title": "hello world" },
+        { "id": 3, "title": "hello there" }
+    ]);
+
+    // by default an unreadable document fails the whole search
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(documents.clone()).await;
+    server.corrupt_document("2");
+
+    let query = json!({ "q": "hello" });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 500);
+    });
+
+    let opt = meilisearch_http::option::Opt {
+        skip_unreadable_documents: true,
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(documents).await;
+    server.corrupt_document("2");
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(response["nbHits"], 2);
+        assert_eq!(response["_warnings"].as_array().unwrap().len(), 1);
+    });
+}
+
+#[actix_rt::test]
+async fn search_empty_query_with_facets_distribution() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "gender"] })).await;
+
+    let query = json!({
+        "q": "",
+        "filters": "color = green",
+        "facetsDistribution": ["color", "gender"],
+        "limit": 100,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 25);
+        assert!(hits.iter().all(|hit| hit["color"] == "green"));
+
+        let distribution = &response["facetsDistribution"];
+        assert_eq!(distribution["color"], json!({ "green": 25, "blue": 0, "brown": 0 }));
+        let genders = distribution["gender"].as_object().unwrap();
+        assert_eq!(genders.values().map(|count| count.as_u64().unwrap()).sum::<u64>(), 25);
+        assert_eq!(response["exhaustiveFacetsCount"], true);
+    });
+
+    let query = json!({
+        "q": "",
+        "facetFilters": ["color:green"],
+        "facetsDistribution": ["color"],
+        "limit": 100,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 25);
+        assert_eq!(response["nbHits"], 25);
+        assert_eq!(response["facetsDistribution"]["color"]["green"], 25);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_facets_pivot() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "gender"] })).await;
+
+    let query = json!({
+        "q": "",
+        "facetsPivot": ["color", "gender"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = json!({
+            "blue": { "female": 15, "male": 13 },
+            "brown": { "female": 13, "male": 11 },
+            "green": { "female": 12, "male": 13 },
+        });
+        assert_json_eq!(expected, response["facetsPivot"].clone(), ordered: false);
+        assert!(response.get("facetsDistribution").is_none());
+    });
+
+    // the combinations are only counted over the hits
+    let query = json!({
+        "q": "",
+        "filters": "gender = male",
+        "facetsPivot": ["color", "gender"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = json!({
+            "blue": { "male": 13 },
+            "brown": { "male": 11 },
+            "green": { "male": 13 },
+        });
+        assert_json_eq!(expected, response["facetsPivot"].clone(), ordered: false);
+    });
+
+    // the most frequent values come first and the max values cap applies to both dimensions
+    let query = json!({
+        "q": "",
+        "facetsPivot": ["color", "gender"],
+        "maxValuesPerFacet": 1,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let pivot = response["facetsPivot"].as_object().unwrap();
+        let colors: Vec<_> = pivot.keys().collect();
+        assert_eq!(colors, ["blue"]);
+        assert_eq!(response["facetsPivot"]["blue"], json!({ "female": 15 }));
+    });
+
+    let query = json!({
+        "q": "",
+        "facetsPivot": ["color", "gender"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let pivot = response["facetsPivot"].as_object().unwrap();
+        let colors: Vec<_> = pivot.keys().collect();
+        assert_eq!(colors, ["blue", "green", "brown"]);
+        let genders: Vec<_> = pivot["green"].as_object().unwrap().keys().collect();
+        assert_eq!(genders, ["male", "female"]);
+    });
+
+    let query = json!({
+        "q": "",
+        "facetsPivot": ["color"],
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+
+    let query = json!({
+        "q": "",
+        "facetsPivot": ["color", "name"],
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_typo_summary() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "hello world" },
+        { "id": 2, "title": "hello" },
+        { "id": 3, "title": "hallo" },
+        { "id": 4, "title": "goodbye" },
+    ])).await;
+
+    let query = json!({
+        "q": "hello",
+        "typoSummary": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["nbHits"], 3);
+        assert_eq!(response["typoSummary"], json!({ "exact": 2, "typoCorrected": 1 }));
+    });
+
+    let query = json!({
+        "q": "hallo",
+        "typoSummary": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["typoSummary"], json!({ "exact": 1, "typoCorrected": 2 }));
+    });
+
+    let query = json!({ "q": "hello" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("typoSummary").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_sorted_facet_values() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let values = |response: &Value| -> Vec<(String, u64)> {
+        response["facetsDistribution"]["color"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(value, count)| (value.clone(), count.as_u64().unwrap()))
+            .collect()
+    };
+
+    // the values are sorted by descending count by default
+    let query = json!({
+        "q": "",
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = vec![("blue".to_string(), 28), ("green".to_string(), 25), ("brown".to_string(), 24)];
+        assert_eq!(values(&response), expected);
+    });
+
+    let query = json!({
+        "q": "",
+        "facetsDistribution": ["color"],
+        "sortFacetValuesBy": "alpha",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = vec![("blue".to_string(), 28), ("brown".to_string(), 24), ("green".to_string(), 25)];
+        assert_eq!(values(&response), expected);
+    });
+
+    let query = json!({
+        "q": "",
+        "facetsDistribution": ["color"],
+        "sortFacetValuesBy": "size",
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_field_scoped_terms() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Seldon plan", "overview": "the psychohistory of the empire" },
+        { "id": 2, "title": "Foundation", "overview": "Hari Seldon and the psychohistory" },
+        { "id": 3, "title": "Robots", "overview": "the three laws" },
+    ])).await;
+
+    let sorted_hit_ids = |response: &Value| {
+        let mut ids = common::hit_ids(response);
+        ids.sort_unstable();
+        ids
+    };
+
+    let query = json!({ "q": "seldon" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![1, 2]);
+    });
+
+    // the scoped term only matches in the title, the other terms match anywhere
+    let query = json!({ "q": "psychohistory title:seldon" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![1]);
+        assert_eq!(response["query"], "psychohistory title:seldon");
+    });
+
+    let query = json!({ "q": "overview:seldon" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(sorted_hit_ids(&response), vec![2]);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_truncated_query() {
+    let opt = meilisearch_http::option::Opt {
+        max_query_terms: Some(2),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "hello world" },
+        { "id": 2, "title": "goodbye" },
+    ])).await;
+
+    // the terms after the second one are ignored
+    let query = json!({ "q": "hello world unknown terms" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["nbHits"], 1);
+        assert_eq!(response["hits"][0]["id"], 1);
+        assert_eq!(response["queryTruncated"], true);
+        assert_eq!(response["queryTerms"], 2);
+        assert_eq!(response["query"], "hello world unknown terms");
+    });
+
+    let query = json!({ "q": "hello world" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["nbHits"], 1);
+        assert!(response.get("queryTruncated").is_none());
+        assert!(response.get("queryTerms").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_distinct_scan_limit() {
+    // the ten documents of the `a` group are more relevant than the one of the `b` group
+    let mut documents: Vec<_> = (0..10).map(|id| json!({ "id": id, "title": "hello", "group": "a" })).collect();
+    documents.push(json!({ "id": 10, "title": "hallo", "group": "b" }));
+    let query = json!({
+        "q": "hello",
+        "exhaustiveNbHits": true,
+    });
+
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!(documents)).await;
+    server.update_distinct_attribute(json!("group")).await;
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+        assert_eq!(response["nbHits"], 2);
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+
+    // the scan stops in the `a` group, before finding the `b` one
+    let opt = meilisearch_http::option::Opt {
+        distinct_scan_limit: Some(5),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!(documents)).await;
+    server.update_distinct_attribute(json!("group")).await;
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["group"], "a");
+        assert_eq!(response["exhaustiveNbHits"], false);
+    });
+
+    // a limit of zero does not limit the scan
+    let opt = meilisearch_http::option::Opt {
+        distinct_scan_limit: Some(0),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!(documents)).await;
+    server.update_distinct_attribute(json!("group")).await;
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+        assert_eq!(response["exhaustiveNbHits"], true);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_distinct_scan_limit_ranks_the_scanned_documents() {
+    // the documents only differ by their rank, two documents share each group
+    let documents: Vec<_> = (0..8).map(|id| json!({ "id": id, "title": "hello", "group": id / 2, "rank": id })).collect();
+    let opt = meilisearch_http::option::Opt {
+        distinct_scan_limit: Some(4),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!(documents)).await;
+    server.update_distinct_attribute(json!("group")).await;
+    server.update_ranking_rules(json!([
+        "typo",
+        "words",
+        "proximity",
+        "attribute",
+        "wordsPosition",
+        "exactness",
+        "desc(rank)",
+    ])).await;
+
+    // the scanned documents are still sorted by every ranking rule
+    let query = json!({ "q": "hello", "limit": 4 });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let ranks: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["rank"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ranks.len(), 2);
+        assert!(ranks.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(response["exhaustiveNbHits"], false);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_highlighted_numbers() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "The Foundation", "year": 1951 },
+    ])).await;
+
+    let query = json!({
+        "q": "1951",
+        "attributesToHighlight": ["year"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"][0]["_formatted"]["year"], 1951);
+    });
+
+    let query = json!({
+        "q": "1951",
+        "attributesToHighlight": ["year"],
+        "highlightNumbers": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"][0]["year"], 1951);
+        assert_eq!(response["hits"][0]["_formatted"]["year"], "<em>1951</em>");
+    });
+}
+
+#[actix_rt::test]
+async fn facets_distribution_on_empty_index() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let query = json!({ "facetsDistribution": ["color"] });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["hits"], json!([]));
+        assert_eq!(response["facetsDistribution"], json!({ "color": {} }));
+    });
+
+    let query = json!({
+        "q": "hello",
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["facetsDistribution"], json!({ "color": {} }));
+    });
+}
+
+#[actix_rt::test]
+async fn placeholder_search_sort_fast_path() {
+    let documents = json!([
+        { "id": 1, "color": "red", "price": 30 },
+        { "id": 2, "color": "blue", "price": 50 },
+        { "id": 3, "color": "red", "price": 10 },
+        { "id": 4, "color": "red", "price": 40 },
+        { "id": 5, "color": "blue", "price": 20 },
+        { "id": 6, "color": "red", "price": 25 }
+    ]);
+    let settings = json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(price)"],
+    });
+    let query = json!({
+        "filters": "color = red",
+        "sort": ["price:asc"],
+        "limit": 3,
+    });
+
+    // the fast path is taken by default, the sort rules win over the ranking rules
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(settings.clone()).await;
+    server.add_or_replace_multiple_documents(documents.clone()).await;
+    let (fast_response, _status_code) = server.search_post(query.clone()).await;
+    assert_eq!(common::hit_ids(&fast_response), vec![3, 6, 1]);
+
+    let opt = meilisearch_http::option::Opt {
+        disable_sort_fast_path: true,
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(settings).await;
+    server.add_or_replace_multiple_documents(documents).await;
+    let (response, _status_code) = server.search_post(query).await;
+    assert_eq!(response["hits"], fast_response["hits"]);
+    assert_eq!(response["nbHits"], fast_response["nbHits"]);
+
+    // the general path also applies the sort rules to the documents of every color
+    let query = json!({ "sort": ["price:asc"], "limit": 3 });
+    let (response, _status_code) = server.search_post(query).await;
+    assert_eq!(common::hit_ids(&response), vec![3, 5, 6]);
+}
+
+#[actix_rt::test]
+async fn search_with_matches_format() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "hello world", "description": "the world says hello" },
+    ])).await;
+
+    let query = json!({ "q": "hello world", "matches": true });
+    let (response, _status_code) = server.search_post(query).await;
+    let object = response["hits"][0]["_matchesInfo"].clone();
+    assert_eq!(object["title"], json!([{ "start": 0, "length": 5 }, { "start": 6, "length": 5 }]));
+
+    let query = json!({ "q": "hello world", "matches": true, "matchesFormat": "array" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let array = response["hits"][0]["_matchesInfo"].as_array().unwrap().clone();
+
+        // both formats encode the same matches
+        let nb_matches: usize = object.as_object().unwrap().values().map(|m| m.as_array().unwrap().len()).sum();
+        assert_eq!(array.len(), nb_matches);
+        for m in &array {
+            let position = json!({ "start": m["start"], "length": m["length"] });
+            let attribute = m["attribute"].as_str().unwrap();
+            assert!(object[attribute].as_array().unwrap().contains(&position));
+        }
+        assert_eq!(array[0], json!({ "attribute": "description", "start": 4, "length": 5 }));
+    });
+
+    let query = json!({ "q": "hello", "matches": true, "matchesFormat": "list" });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_score_threshold_and_limit() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "United Kingdom" },
+        { "id": 2, "title": "The United Kingdom of Great Britain" },
+        { "id": 3, "title": "Unitet Kingdon" },
+        { "id": 4, "title": "The unitet kingdon of typos" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // the documents with typos are below the threshold
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 0.9,
+        "showRankingScore": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit["_rankingScore"].as_f64().unwrap() >= 0.9));
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    // the limit is still respected when more hits are above the threshold
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 0.9,
+        "limit": 1,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], 1);
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    // the offset skips the hits above the threshold only
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 0.9,
+        "offset": 1,
+        "limit": 1,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], 2);
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 0.9,
+        "offset": 2,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        assert_eq!(response["nbHits"], 2);
+    });
+
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 0.0,
+        "limit": 3,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 3);
+    });
+
+    let query = json!({
+        "q": "united kingdom",
+        "rankingScoreThreshold": 1.5,
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_facet_stats() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "running shoe", "color": "red", "price": 30 },
+        { "id": 2, "name": "running shoe", "color": "blue", "price": 50 },
+        { "id": 3, "name": "walking shoe", "color": "red", "price": 10 },
+        { "id": 4, "name": "hiking boot", "color": "red", "price": 40 },
+        { "id": 5, "name": "hiking boot", "color": "blue", "price": 20.5 },
+        { "id": 6, "name": "sandal", "color": "red" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // the stats are computed over all the filtered candidates, not only the returned page
+    let query = json!({
+        "filters": "color = red",
+        "facetStats": ["price"],
+        "limit": 1,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+        assert_eq!(response["facetStats"], json!({ "price": { "min": 10.0, "max": 40.0, "avg": 80.0 / 3.0 } }));
+    });
+
+    let query = json!({
+        "q": "shoe",
+        "facetStats": ["price"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetStats"], json!({ "price": { "min": 10.0, "max": 50.0, "avg": 30.0 } }));
+    });
+
+    // the attributes without numeric values are skipped
+    let query = json!({
+        "q": "sandal",
+        "facetStats": ["price", "color"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetStats"], json!({}));
+        let warnings = response["_warnings"].as_array().unwrap();
+        assert!(warnings.iter().any(|w| w.as_str().unwrap().contains("`color` in facetStats")));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_fused_queries() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "red" },
+        { "id": 2, "title": "red apple" },
+        { "id": 3, "title": "apple" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "red" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![1, 2]);
+    });
+
+    // the document found by both queries is ranked first and returned once
+    let query = json!({ "queries": ["red", "apple"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let ids = common::hit_ids(&response);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[0], 2);
+        assert_eq!(response["nbHits"], 3);
+    });
+
+    let query = json!({ "queries": ["red", "apple"], "limit": 1 });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![2]);
+    });
+
+    // a query is never split on its commas
+    let query = json!({ "q": "red, apple" });
+    let (single, _status_code) = server.search_post(query).await;
+    let query = json!({ "queries": ["red, apple"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), common::hit_ids(&single));
+    });
+
+    let query = json!({ "queries": [] });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+
+    let query = json!({ "q": "red", "queries": ["apple"] });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_too_many_facets_distribution() {
+    let opt = meilisearch_http::option::Opt {
+        max_facets_distribution: Some(2),
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color", "size", "brand"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "shirt", "color": "blue", "size": "xl", "brand": "acme" },
+        { "id": 2, "title": "shirt", "color": "red", "size": "m", "brand": "acme" }
+    ])).await;
+
+    let query = json!({
+        "q": "shirt",
+        "facetsDistribution": ["color", "size"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["facetsDistribution"].as_object().unwrap().len(), 2);
+    });
+
+    let query = json!({
+        "q": "shirt",
+        "facetsDistribution": ["color", "size", "brand"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+
+    // the wildcard selects all the facets
+    let query = json!({
+        "q": "shirt",
+        "facetsDistribution": ["*"],
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_default_hits_per_page() {
+    let mut server = common::Server::test_server().await;
+
+    server.update_all_settings(json!({ "defaultHitsPerPage": 5 })).await;
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response["defaultHitsPerPage"], 5);
+
+    let query = json!({ "q": "exercitation" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 5);
+        assert_eq!(response["limit"], 5);
+    });
+
+    // the limit of the request takes precedence
+    let query = json!({ "q": "exercitation", "limit": 7 });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 7);
+    });
+
+    server.delete_request_async("/indexes/test/settings").await;
+    let query = json!({ "q": "exercitation" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["limit"], 20);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_numeric_comparisons() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    // the price is ranked and compared on the ranked map, the rating is read from the documents
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(price)"],
+    })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "book", "price": 5, "rating": 3, "year": 1995 },
+        { "id": 2, "title": "book", "price": 10, "rating": 4.5, "year": 2005 },
+        { "id": 3, "title": "book", "price": 20, "rating": 4, "year": 1999 },
+        { "id": 4, "title": "book", "price": 25.5, "rating": 5, "year": 2020 },
+        { "id": 5, "title": "book", "price": "free", "rating": "none", "year": 2001 },
+        // the numeric strings are compared as numbers, whether the attribute is ranked or not
+        { "id": 6, "title": "book", "price": "15", "rating": "4.2", "year": 2010 }
+    ])).await;
+
+    let sorted_hit_ids = |response: &Value| {
+        let mut ids = common::hit_ids(response);
+        ids.sort_unstable();
+        ids
+    };
+
+    let filters = [
+        ("price > 10", vec![3, 4, 6]),
+        ("price >= 10", vec![2, 3, 4, 6]),
+        ("price < 10", vec![1]),
+        ("price <= 20", vec![1, 2, 3, 6]),
+        ("rating >= 4", vec![2, 3, 4, 6]),
+        ("rating < 4.5", vec![1, 3, 6]),
+        ("year < 2000", vec![1, 3]),
+        ("price > 5 AND year > 2000", vec![2, 4, 6]),
+    ];
+    for (filter, expected) in filters.iter() {
+        let query = json!({ "q": "book", "filters": filter });
+        test_post_get_search!(server, query, |response, _status_code| {
+            assert_eq!(&sorted_hit_ids(&response), expected, "{}", filter);
+        });
+    }
+
+    // comparing with a string is a type error
+    let query = json!({ "q": "book", "filters": "price > free" });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        let message = response["message"].as_str().unwrap();
+        assert!(message.contains("the `>` operator only compares numbers, `free` is not a number"));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_highlight_segments() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "cherry",
+        "limit": 1,
+        "attributesToHighlight": ["name"],
+        "highlightFormat": "segments"
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        let hit = &response["hits"][0];
+        let segments = hit["_formatted"]["name"].as_array().unwrap();
+        assert_eq!(segments, &vec![
+            json!({ "value": "Cherry", "isMatch": true }),
+            json!({ "value": " Orr", "isMatch": false }),
+        ]);
+
+        let reconstructed: String = segments.iter().map(|s| s["value"].as_str().unwrap()).collect();
+        assert_eq!(reconstructed, hit["name"].as_str().unwrap());
+        // the attributes that are not highlighted are left untouched
+        assert_eq!(hit["_formatted"]["email"], hit["email"]);
+    });
+
+    let query = json!({
+        "q": "cherry",
+        "attributesToHighlight": ["name"],
+        "highlightFormat": "html"
+    });
+
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_suggested_query() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    let mut documents = vec![
+        json!({ "id": 1, "title": "cherry pie" }),
+        json!({ "id": 2, "title": "apple pie" }),
+    ];
+    documents.extend((3..13).map(|id| json!({ "id": id, "title": "apple tart" })));
+    server.add_or_replace_multiple_documents(Value::from(documents)).await;
+
+    let query = json!({
+        "q": "pye cherry",
+        "suggestQuery": true
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["nbHits"], 0);
+        assert_eq!(response["suggestedQuery"], "pie cherry");
+    });
+
+    // the last word is searched as a prefix, it is not corrected
+    let query = json!({
+        "q": "cherry pye",
+        "suggestQuery": true
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response.get("suggestedQuery").is_none());
+    });
+
+    // the typo of a query finding enough documents is not corrected
+    let query = json!({
+        "q": "appel tart",
+        "suggestQuery": true
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["nbHits"], 10);
+        assert!(response.get("suggestedQuery").is_none());
+    });
+
+    // a query made of known words has no suggestion
+    let query = json!({
+        "q": "cherry pie",
+        "suggestQuery": true
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response.get("suggestedQuery").is_none());
+    });
+
+    // the suggestion is only computed when requested
+    let query = json!({
+        "q": "pye cherry"
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response.get("suggestedQuery").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_overshooting_offset() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "cherry",
+        "offset": 1000
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        let nb_hits = response["nbHits"].as_u64().unwrap();
+        assert!(nb_hits > 0);
+        assert_eq!(
+            response["_warnings"],
+            json!([format!("the offset 1000 is past the end of the {} hits found", nb_hits)])
+        );
+    });
+
+    // a search without any hit is not paged past the end
+    let query = json!({
+        "q": "xxxxxxxxxxxxxxxxx",
+        "offset": 1000
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["nbHits"], 0);
+        assert!(response.get("_warnings").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn explain_search() {
+    let mut server = common::Server::test_server().await;
+
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(age)"],
+        "attributesForFaceting": ["color"],
+    })).await;
+
+    let body = json!({
+        "q": "cherry",
+        "filters": "age > 20",
+        "facetFilters": ["color:green"]
+    });
+
+    let (response, status_code) = server.post_request("/indexes/test/search/explain", body).await;
+    assert_eq!(status_code, 200, "{}", response);
+    assert_eq!(response["criteria"], json!([
+        "typo",
+        "words",
+        "proximity",
+        "attribute",
+        "words position",
+        "exactness",
+        "desc(age)",
+        "stable document id",
+    ]));
+    assert_eq!(response["filters"], "age > 20");
+    assert!(response["wordsCandidates"]["cherry"].as_u64().unwrap() > 0);
+    assert_eq!(response["facetCandidates"], 25);
+    assert!(response["nbCandidates"].as_u64().unwrap() <= 25);
+
+    // a placeholder search ranks all the documents matching the facet filters
+    let body = json!({ "facetFilters": ["color:green"] });
+
+    let (response, status_code) = server.post_request("/indexes/test/search/explain", body).await;
+    assert_eq!(status_code, 200, "{}", response);
+    assert_eq!(response["wordsCandidates"], json!({}));
+    assert_eq!(response["nbCandidates"], 25);
+
+    // the filter is parsed
+    let body = json!({ "q": "cherry", "filters": "age >" });
+
+    let (response, status_code) = server.post_request("/indexes/test/search/explain", body).await;
+    assert_eq!(status_code, 400, "{}", response);
+}
+
+#[actix_rt::test]
+async fn search_with_nulls_order() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(price)"],
+    })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "hotel", "price": 10 },
+        { "id": 2, "title": "hotel" },
+        { "id": 3, "title": "hotel", "price": 5 },
+        { "id": 4, "title": "hotel" },
+    ])).await;
+
+    let sorted = |ids: &[u64]| -> Vec<u64> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids
+    };
+
+    // the placeholder searches are sorted on the ranked map, the other ones by the criteria
+    for q in &["", "hotel"] {
+        for sort in &["price:asc", "price:desc"] {
+            let with_price = if *sort == "price:asc" { vec![3, 1] } else { vec![1, 3] };
+
+            let query = json!({ "q": q, "sort": [sort], "nullsOrder": "last" });
+            let (response, status_code) = server.search_post(query).await;
+            assert_eq!(status_code, 200, "{}", response);
+            let last = common::hit_ids(&response);
+            assert_eq!(last[..2], with_price[..]);
+            assert_eq!(sorted(&last[2..]), vec![2, 4]);
+
+            let query = json!({ "q": q, "sort": [sort], "nullsOrder": "first" });
+            let (response, status_code) = server.search_post(query).await;
+            assert_eq!(status_code, 200, "{}", response);
+            let first = common::hit_ids(&response);
+            assert_eq!(sorted(&first[..2]), vec![2, 4]);
+            assert_eq!(first[2..], with_price[..]);
+
+            // the missing values are placed last by default
+            let (response, _status_code) = server.search_post(json!({ "q": q, "sort": [sort] })).await;
+            let ids = common::hit_ids(&response);
+            assert_eq!(ids[..2], with_price[..], "{} {}", q, sort);
+            assert_eq!(sorted(&ids[2..]), vec![2, 4]);
+        }
+
+        // the nulls order also applies to the asc and desc ranking rules
+        let query = json!({ "q": q, "nullsOrder": "last" });
+        let (response, _status_code) = server.search_post(query).await;
+        let ids = common::hit_ids(&response);
+        assert_eq!(ids[..2], [3, 1]);
+        assert_eq!(sorted(&ids[2..]), vec![2, 4]);
+
+        // the asc and desc ranking rules also place the missing values last by default
+        let (response, _status_code) = server.search_post(json!({ "q": q })).await;
+        let ids = common::hit_ids(&response);
+        assert_eq!(ids[..2], [3, 1], "{}", q);
+        assert_eq!(sorted(&ids[2..]), vec![2, 4]);
+    }
+
+    let (_response, status_code) = server.search_post(json!({ "q": "hotel", "nullsOrder": "middle" })).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_attribute_aliases() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "cherry",
+        "limit": 1,
+        "attributesToHighlight": ["name"],
+        "attributeAliases": { "name": "title" }
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200, "{}", response);
+        let hit = &response["hits"][0];
+        assert_eq!(hit["title"], "Cherry Orr");
+        assert!(hit.get("name").is_none());
+        assert_eq!(hit["_formatted"]["title"], "<em>Cherry</em> Orr");
+        assert!(hit["_formatted"].get("name").is_none());
+    });
+
+    // an alias cannot replace another attribute
+    let query = json!({
+        "q": "cherry",
+        "attributeAliases": { "name": "email" }
+    });
+
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+
+    let query = json!({
+        "q": "cherry",
+        "attributeAliases": { "name": "title", "email": "title" }
+    });
+
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_repeated_facet_values() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["tags"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "movie", "tags": ["horror", "Horror", "comedy", "horror"] },
+        { "id": 2, "title": "movie", "tags": ["comedy"] },
+    ])).await;
+
+    // a value repeated in a document, or normalized to the same value, is counted once
+    let query = json!({
+        "q": "movie",
+        "facetsDistribution": ["tags"]
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200, "{}", response);
+        assert_eq!(response["facetsDistribution"]["tags"], json!({ "horror": 1, "comedy": 2 }));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_nested_array_facets() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["variants.color"] })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "shirt", "variants": [{ "color": "red" }, { "color": "blue" }] },
+        { "id": 2, "title": "shirt", "variants": [{ "color": "red" }, { "color": "red" }] },
+        { "id": 3, "title": "shirt", "variants": [{ "color": "green" }] },
+        { "id": 4, "title": "shirt", "variants": [] },
+    ])).await;
+
+    let query = json!({
+        "q": "shirt",
+        "facetsDistribution": ["variants.color"]
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200, "{}", response);
+        assert_eq!(response["facetsDistribution"]["variants.color"], json!({ "red": 2, "blue": 1, "green": 1 }));
+    });
+
+    let query = json!({
+        "q": "shirt",
+        "facetFilters": ["variants.color:red"]
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200, "{}", response);
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2]);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_parenthesized_filters() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "first", "a": 1, "c": 3 },
+        { "id": 2, "title": "second", "b": 2, "c": 0 },
+        { "id": 3, "title": "third", "a": 1, "c": 0 },
+        { "id": 4, "title": "fourth", "b": 2, "c": 3 }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let filters = [
+        ("(a = 1 OR b = 2) AND c = 3", vec![1, 4]),
+        // AND binds tighter than OR
+        ("a = 1 OR b = 2 AND c = 3", vec![1, 3, 4]),
+        ("a = 1 OR (b = 2 AND c = 3)", vec![1, 3, 4]),
+        ("c = 3 AND (a = 1 OR (b = 2 AND NOT c = 3))", vec![1]),
+        ("NOT (a = 1 OR c = 3)", vec![2]),
+    ];
+    for (filters, expected) in filters.iter() {
+        let query = json!({ "filters": filters });
+        test_post_get_search!(server, query, |response, _status_code| {
+            let mut ids = common::hit_ids(&response);
+            ids.sort_unstable();
+            assert_eq!(&ids, expected, "{}", filters);
+        });
+    }
+}
+
+#[actix_rt::test]
+async fn search_with_ids_only() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({ "q": "exercitation", "limit": 10 });
+    let (response, _status_code) = server.search_post(query).await;
+    let expected: Vec<_> = response["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["id"].clone())
+        .collect();
+    assert!(!expected.is_empty());
+
+    let query = json!({ "q": "exercitation", "limit": 10, "idsOnly": true });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(response["ids"], json!(expected));
+        assert_eq!(response["hits"], json!([]));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_lenient_facet_filters() {
+    let documents = json!([
+        { "id": 1, "title": "a green apple", "color": "green" },
+        { "id": 2, "title": "a red apple", "color": "red" },
+        { "id": 3, "title": "a green pear", "color": "green" }
+    ]);
+    let query = json!({ "q": "a", "facetFilters": ["color:green", "size:large"] });
+
+    // by default a clause on an unknown attribute fails the whole search
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(documents.clone()).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+
+    let opt = meilisearch_http::option::Opt {
+        lenient_facet_filters: true,
+        ..Default::default()
+    };
+    let mut server = common::Server::with_uid_and_options("test", opt);
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(documents).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3]);
+        let warnings = response["_warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().unwrap().contains("size"));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_relevance_preset() {
+    let mut server = common::Server::test_server().await;
+
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(age)"],
+    })).await;
+
+    // the custom rules of the index apply after the ones of the preset
+    let presets = [
+        ("precision", ["exactness", "proximity", "typo", "words", "attribute", "words position"]),
+        ("recall", ["words", "typo", "attribute", "proximity", "words position", "exactness"]),
+    ];
+    for (preset, criteria) in presets.iter() {
+        let body = json!({ "q": "cherry", "relevancePreset": preset });
+        let (response, status_code) = server.post_request("/indexes/test/search/explain", body).await;
+        assert_eq!(status_code, 200, "{}", response);
+
+        let mut expected = json!(criteria);
+        expected.as_array_mut().unwrap().push(json!("desc(age)"));
+        expected.as_array_mut().unwrap().push(json!("stable document id"));
+        assert_eq!(response["criteria"], expected, "{}", preset);
+
+        let query = json!({ "q": "cherry", "relevancePreset": preset });
+        test_post_get_search!(server, query, |response, status_code| {
+            assert_eq!(status_code, 200, "{}", response);
+            assert!(!response["hits"].as_array().unwrap().is_empty());
+        });
+    }
+
+    let query = json!({ "q": "cherry", "relevancePreset": "fuzzy" });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+
+    let query = json!({ "q": "cherry", "relevancePreset": "recall", "rankingRules": ["typo"] });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_snippet_parts() {
+    let mut server = common::Server::test_server().await;
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 5,
+        "attributesToRetrieve": ["id"],
+        "attributesToCrop": ["about:50"],
+    });
+    let (response, _status_code) = server.search_post(query).await;
+    let cropped: Vec<_> = response["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["_formatted"]["about"].as_str().unwrap().to_string())
+        .collect();
+    assert!(!cropped.is_empty());
+
+    let query = json!({
+        "q": "exercitation",
+        "limit": 5,
+        "attributesToRetrieve": ["id"],
+        "attributesToCrop": ["about:50"],
+        "snippetFormat": "parts",
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200, "{}", response);
+        let hits = response["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), cropped.len());
+        assert_eq!(hits[0]["_formatted"]["about"]["match"].as_str().unwrap().to_lowercase(), "exercitation");
+        for (hit, cropped) in hits.iter().zip(&cropped) {
+            let parts = &hit["_formatted"]["about"];
+            let text = format!(
+                "{}{}{}",
+                parts["before"].as_str().unwrap(),
+                parts["match"].as_str().unwrap(),
+                parts["after"].as_str().unwrap(),
+            );
+            assert_eq!(&text, cropped);
+        }
+    });
+
+    let query = json!({ "q": "exercitation", "attributesToCrop": ["about"], "snippetFormat": "words" });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 400);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_facets_percentages() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let dataset = include_bytes!("assets/test_set.json");
+    let body: Value = serde_json::from_slice(dataset).unwrap();
+    server.add_or_update_multiple_documents(body).await;
+
+    let query = json!({
+        "q": "a",
+        "facetsDistribution": ["color"],
+        "facetsPercentages": true,
+    });
+
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        let nb_hits = response["nbHits"].as_u64().unwrap() as f64;
+        let counts = response["facetsDistribution"]["color"].as_object().unwrap();
+        let percentages = response["facetsPercentages"]["color"].as_object().unwrap();
+        assert_eq!(counts.len(), percentages.len());
+        for (value, count) in counts {
+            let percentage = percentages[value].as_f64().unwrap();
+            assert!((percentage - count.as_f64().unwrap() / nb_hits).abs() < 1e-9);
+        }
+        // every document has a single color
+        let total: f64 = percentages.values().map(|p| p.as_f64().unwrap()).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    });
+
+    let query = json!({
+        "q": "a",
+        "facetsDistribution": ["color"],
+    });
+
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("facetsPercentages").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_debug_term_typos() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello world" }])).await;
+
+    // the second word is only found once corrected
+    let query = json!({
+        "q": "hello warld",
+        "debug": true,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"][0]["_termTypos"], json!([
+            { "term": "hello", "typos": 0 },
+            { "term": "warld", "typos": 1 },
+        ]));
+    });
+
+    let query = json!({ "q": "hello warld" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"][0].get("_termTypos").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_shorter_than_min_query_length() {
+    let mut server = common::Server::test_server().await;
+
+    server.update_all_settings(json!({ "minQueryLength": 2 })).await;
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response["minQueryLength"], 2);
+
+    let query = json!({ "q": "e" });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        assert_eq!(response["nbHits"], 0);
+        assert_eq!(response["_warnings"].as_array().unwrap().len(), 1);
+    });
+
+    let query = json!({ "q": "ex" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(!response["hits"].as_array().unwrap().is_empty());
+        assert!(response.get("_warnings").is_none());
+    });
+
+    // the placeholder search is not restricted
+    let query = json!({ "limit": 3 });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["hits"].as_array().unwrap().len(), 3);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_geo_distance() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "name": "hotel", "_geo": { "lat": 45.76, "lng": 4.83 } },
+        { "id": 2, "name": "hotel", "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": 3, "name": "hotel" },
+        { "id": 4, "name": "hotel", "_geo": { "lat": 48.86, "lng": 2.34 } }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "hotel",
+        "sort": ["_geoPoint(48.85, 2.35):asc"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        let ids = common::hit_ids(&response);
+        assert_eq!(ids, vec![2, 4, 1, 3]);
+
+        // the distances are in meters and follow the sort order
+        assert_eq!(hits[0]["_geoDistance"], 0);
+        let nearby = hits[1]["_geoDistance"].as_u64().unwrap();
+        assert!((1_000..2_000).contains(&nearby), "{}", nearby);
+        let lyon = hits[2]["_geoDistance"].as_u64().unwrap();
+        assert!((390_000..395_000).contains(&lyon), "{}", lyon);
+        // a document without coordinates has no distance
+        assert!(hits[3].get("_geoDistance").is_none());
+    });
+
+    // the placeholder searches give the same distances
+    let query = json!({ "sort": ["_geoPoint(48.85, 2.35):asc"] });
+    let (placeholder, _status_code) = server.search_post(query).await;
+    let (sorted, _status_code) = server.search_post(json!({
+        "q": "hotel",
+        "sort": ["_geoPoint(48.85, 2.35):asc"],
+    })).await;
+    assert_eq!(placeholder["hits"], sorted["hits"]);
+
+    // the distances are given to the center of the geo radius filter when there is no geo sort
+    let query = json!({
+        "q": "hotel",
+        "filters": "_geoRadius(48.85, 2.35, 10000)",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let hits = response["hits"].as_array().unwrap();
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 4]);
+        for hit in hits {
+            let distance = hit["_geoDistance"].as_u64().unwrap();
+            assert!(distance < 10_000, "{}", distance);
+        }
+    });
+
+    let query = json!({ "q": "hotel" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response["hits"][0].get("_geoDistance").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_columnar_format() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "hello world", "price": 10 },
+        { "id": 2, "title": "hello there" },
+        { "id": 3, "title": "hello", "tags": ["greeting"] }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (expected, _status_code) = server.search_post(json!({ "q": "hello" })).await;
+
+    let query = json!({ "q": "hello", "format": "columnar" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let fields = response["hits"]["fields"].as_array().unwrap();
+        let rows = response["hits"]["rows"].as_array().unwrap();
+        assert!(rows.iter().all(|row| row.as_array().unwrap().len() == fields.len()));
+
+        // the missing fields are null, the documents have no null value
+        let hits: Vec<Value> = rows
+            .iter()
+            .map(|row| {
+                let hit: serde_json::Map<String, Value> = fields
+                    .iter()
+                    .zip(row.as_array().unwrap())
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(field, value)| (field.as_str().unwrap().to_string(), value.clone()))
+                    .collect();
+                Value::Object(hit)
+            })
+            .collect();
+        assert_eq!(Value::Array(hits), expected["hits"]);
+        assert_eq!(response["nbHits"], expected["nbHits"]);
+    });
+
+    let query = json!({ "q": "hello", "format": "csv" });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_empty_facet_filters() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let (expected, _status_code) = server.search_post(json!({ "q": "a", "limit": 100 })).await;
+
+    for facet_filters in vec![json!([]), json!([[]]), json!([[], []])] {
+        let query = json!({
+            "q": "a",
+            "limit": 100,
+            "facetFilters": facet_filters,
+        });
+        test_post_get_search!(server, query, |response, status_code| {
+            assert_eq!(status_code, 200);
+            assert_eq!(response["nbHits"], expected["nbHits"]);
+            assert_eq!(response["hits"], expected["hits"]);
+        });
+    }
+}
+
+#[actix_rt::test]
+async fn search_with_field_types() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "hello", "price": 10, "available": true, "tags": ["a"], "size": { "w": 1 }, "note": null },
+        { "id": 2, "title": "hello", "price": 12.5, "available": false, "tags": [], "size": { "w": 2 }, "note": "new" },
+        { "id": 3, "title": "hello", "code": "x" },
+        { "id": 4, "title": "hello", "code": 4 }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "hello", "showFieldTypes": true });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["_fieldTypes"], json!({
+            "id": "number",
+            "title": "string",
+            "price": "number",
+            "available": "boolean",
+            "tags": "array",
+            "size": "object",
+            "note": "string",
+            "code": "mixed",
+        }));
+    });
+
+    // only the returned attributes are reported
+    let query = json!({ "q": "hello", "attributesToRetrieve": ["title"], "showFieldTypes": true });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["_fieldTypes"], json!({ "title": "string" }));
+    });
+
+    let query = json!({ "q": "hello" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert!(response.get("_fieldTypes").is_none());
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_max_values_per_facet() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["tag"] })).await;
+
+    // the value `v{n}` is found in `n` documents
+    let mut documents = Vec::new();
+    for n in 1..=60 {
+        for _ in 0..n {
+            let id = documents.len();
+            documents.push(json!({ "id": id, "title": "item", "tag": format!("v{}", n) }));
+        }
+    }
+    server.add_or_replace_multiple_documents(Value::Array(documents)).await;
+
+    let query = json!({
+        "q": "item",
+        "facetsDistribution": ["tag"],
+        "maxValuesPerFacet": 5,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetsDistribution"]["tag"], json!({
+            "v60": 60,
+            "v59": 59,
+            "v58": 58,
+            "v57": 57,
+            "v56": 56,
+        }));
+        assert_eq!(response["nbHits"], 1830);
+    });
+
+    // the placeholder search is capped the same way
+    let query = json!({
+        "facetsDistribution": ["tag"],
+        "maxValuesPerFacet": 2,
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetsDistribution"]["tag"], json!({ "v60": 60, "v59": 59 }));
+    });
+
+    let query = json!({ "q": "item", "facetsDistribution": ["tag"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetsDistribution"]["tag"].as_object().unwrap().len(), 60);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_score_expression() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(popularity)"],
+    })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "hotel", "popularity": 10 },
+        { "id": 2, "title": "hotel", "popularity": 30 },
+        { "id": 3, "title": "hotel", "popularity": 20 },
+        { "id": 4, "title": "hotel" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({ "q": "hotel" });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![1, 3, 2, 4]);
+    });
+
+    // the most popular hotels come first, the ones without popularity last
+    let query = json!({
+        "q": "hotel",
+        "rankingScoreExpression": "_rankingScore * 0.1 + popularity * 0.9",
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert_eq!(common::hit_ids(&response), vec![2, 3, 1, 4]);
+    });
+
+    // the page is taken from the re-ranked hits
+    let query = json!({
+        "q": "hotel",
+        "offset": 1,
+        "limit": 2,
+        "rankingScoreExpression": "_rankingScore * 0.1 + popularity * 0.9",
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(common::hit_ids(&response), vec![3, 1]);
+    });
+
+    // the expressions must be valid and only refer to ranked attributes
+    for expression in &["popularity % 2", "popularity +", "title * 2", "unknown + 1"] {
+        let query = json!({ "q": "hotel", "rankingScoreExpression": expression });
+        test_post_get_search!(server, query, |response, status_code| {
+            assert_eq!(status_code, 400);
+            assert_eq!(response["errorCode"], "bad_parameter");
+        });
+    }
+}
+
+#[actix_rt::test]
+async fn search_with_facet_values_over_max_length() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "attributesForFaceting": ["color", "variants.size"],
+        "maxFacetValueLength": 10,
+    })).await;
+
+    let long_value = "a".repeat(11);
+    let documents = json!([
+        { "id": 1, "title": "shirt", "color": "blue", "variants": [{ "size": "xl" }] },
+        { "id": 2, "title": "shirt", "color": ["red", long_value], "variants": [{ "size": long_value }] },
+        { "id": 3, "title": "shirt", "color": long_value }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // the documents are indexed, only their facet values over the max length are skipped,
+    // the nested ones included
+    let query = json!({ "q": "shirt", "facetsDistribution": ["color", "variants.size"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["nbHits"], 3);
+        assert_eq!(response["facetsDistribution"]["color"], json!({ "blue": 1, "red": 1 }));
+        assert_eq!(response["facetsDistribution"]["variants.size"], json!({ "xl": 1 }));
+    });
+
+    // the facet values are indexed again without the max length
+    server.update_all_settings(json!({ "maxFacetValueLength": null })).await;
+
+    let query = json!({ "q": "shirt", "facetsDistribution": ["color"] });
+    test_post_get_search!(server, query, |response, _status_code| {
+        assert_eq!(response["facetsDistribution"]["color"], json!({ "blue": 1, "red": 1, "aaaaaaaaaaa": 2 }));
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_event_stream_format() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "hello world" },
+        { "id": 2, "title": "hello there" },
+        { "id": 3, "title": "hello" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let (expected, _status_code) = server.search_post(json!({ "q": "hello" })).await;
+
+    let (body, status_code) = server.get_request_text("/indexes/test/search?q=hello&format=eventStream").await;
+    assert_eq!(status_code, 200);
+
+    // an event per hit, in the ranking order, then the metadata event closing the stream
+    let events: Vec<&str> = body.split_terminator("\n\n").collect();
+    assert_eq!(events.len(), 4);
+
+    let hits: Vec<Value> = events[..3]
+        .iter()
+        .map(|event| serde_json::from_str(event.strip_prefix("data: ").unwrap()).unwrap())
+        .collect();
+    assert_eq!(Value::Array(hits), expected["hits"]);
+
+    let metadata = events[3].strip_prefix("event: metadata\ndata: ").unwrap();
+    let metadata: Value = serde_json::from_str(metadata).unwrap();
+    assert!(metadata.get("hits").is_none());
+    assert_eq!(metadata["nbHits"], 3);
+    assert_eq!(metadata["query"], "hello");
+
+    // the types of the streamed hits are reported with the metadata
+    let (body, status_code) = server.get_request_text("/indexes/test/search?q=hello&format=eventStream&showFieldTypes=true").await;
+    assert_eq!(status_code, 200);
+    let events: Vec<&str> = body.split_terminator("\n\n").collect();
+    let metadata = events[3].strip_prefix("event: metadata\ndata: ").unwrap();
+    let metadata: Value = serde_json::from_str(metadata).unwrap();
+    assert_eq!(metadata["_fieldTypes"], json!({ "id": "number", "title": "string" }));
+
+    // the errors are returned before the stream starts
+    let (response, status_code) = server.search_get("q=hello&format=eventStream&rankingScoreExpression=unknown").await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["errorCode"], "bad_parameter");
+}
+
+#[actix_rt::test]
+async fn search_with_join_filter() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "shirt", "color": "blue" },
+        { "id": 2, "title": "shirt", "color": "red" },
+        { "id": 3, "title": "shirt", "color": "blue" },
+        { "id": 4, "title": "shirt", "color": "green" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    server.create_index(json!({ "uid": "wishlist", "primaryKey": "id" })).await;
+    let wishlist = json!([
+        { "id": "a", "productId": 1 },
+        { "id": "b", "productId": "3" },
+        { "id": "c", "productId": 42 },
+        { "id": "d" }
+    ]);
+    server.add_or_replace_multiple_documents_in("wishlist", wishlist).await;
+
+    // the ids of the wishlist that are not products are ignored
+    let query = json!({
+        "q": "shirt",
+        "joinFilter": { "indexUid": "wishlist", "key": "productId" },
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let mut ids = common::hit_ids(&response);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(response["nbHits"], 2);
+        assert_eq!(response["facetsDistribution"]["color"], json!({ "blue": 2 }));
+    });
+
+    let query = json!({ "q": "shirt", "joinFilter": { "indexUid": "wishlist", "key": "unknown" } });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert_eq!(response["errorCode"], "bad_parameter");
+    });
+
+    let query = json!({ "q": "shirt", "joinFilter": { "indexUid": "unknown", "key": "productId" } });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 404);
+        assert_eq!(response["errorCode"], "index_not_found");
+    });
+}
+
+#[actix_rt::test]
+async fn facets_distribution_depends_on_stop_words_and_locale() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.update_all_settings(json!({
+        "attributesForFaceting": ["kind"],
+        "stopWords": ["in"],
+    })).await;
+
+    let documents = json!([
+        { "id": 1, "title": "Apfel Strudel mit Sahne", "kind": "cake" },
+        { "id": 2, "title": "Schwarzwälder Kirschtorte", "kind": "cake" },
+        { "id": 3, "title": "bread baked in the oven", "kind": "bread" }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // the cached distribution of a request must not be returned for a request
+    // that only differs by its stop words handling
+    let query = json!({
+        "q": "in",
+        "removeStopWords": false,
+        "facetsDistribution": ["kind"],
+    });
+    let (kept, _status_code) = server.search_post(query).await;
+    assert_eq!(kept["facetsDistribution"]["kind"]["bread"], 1);
+
+    let query = json!({
+        "q": "in",
+        "facetsDistribution": ["kind"],
+    });
+    let (removed, _status_code) = server.search_post(query).await;
+    assert_eq!(removed["nbHits"], 0);
+    assert_ne!(removed["facetsDistribution"], kept["facetsDistribution"]);
+
+    // nor for a request that only differs by its locale
+    let query = json!({
+        "q": "apfelstrudel",
+        "locale": "de-DE",
+        "facetsDistribution": ["kind"],
+    });
+    let (german, _status_code) = server.search_post(query).await;
+    assert_eq!(german["facetsDistribution"]["kind"]["cake"], 1);
+
+    let query = json!({
+        "q": "apfelstrudel",
+        "locale": "en",
+        "facetsDistribution": ["kind"],
+    });
+    let (english, _status_code) = server.search_post(query).await;
+    assert_eq!(english["nbHits"], 0);
+    assert_ne!(english["facetsDistribution"], german["facetsDistribution"]);
+}
+
+#[actix_rt::test]
+async fn search_with_prefix_wildcard_in_attributes_to_retrieve() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        {
+            "id": 1,
+            "title": "hello world",
+            "meta.author": "Kevin",
+            "meta.year": 2020,
+            "meta.tags": ["greeting"],
+            "metadata": "not under meta"
+        }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    let query = json!({
+        "q": "hello",
+        "attributesToRetrieve": ["id", "meta.*"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = json!([
+            {
+                "id": 1,
+                "meta.author": "Kevin",
+                "meta.year": 2020,
+                "meta.tags": ["greeting"]
+            }
+        ]);
+        assert_json_eq!(expected, response["hits"].clone(), ordered: false);
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_exclusion_in_attributes_to_retrieve() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+
+    let documents = json!([
+        {
+            "id": 1,
+            "title": "hello world",
+            "body": "a very long body",
+            "meta.author": "Kevin"
+        }
+    ]);
+    server.add_or_replace_multiple_documents(documents).await;
+
+    // only giving exclusions retrieves all the other attributes
+    let query = json!({
+        "q": "hello",
+        "attributesToRetrieve": ["-body"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = json!([
+            {
+                "id": 1,
+                "title": "hello world",
+                "meta.author": "Kevin"
+            }
+        ]);
+        assert_json_eq!(expected, response["hits"].clone(), ordered: false);
+    });
+
+    // exclusions also apply to the wildcards
+    let query = json!({
+        "q": "hello",
+        "attributesToRetrieve": ["*", "-meta.*", "-title"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let expected = json!([
+            {
+                "id": 1,
+                "body": "a very long body"
+            }
+        ]);
+        assert_json_eq!(expected, response["hits"].clone(), ordered: false);
+    });
+}
+
+#[actix_rt::test]
+async fn facet_distribution_after_facets_rebuild() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let query = json!({ "facetsDistribution": ["color"] });
+    let (response, _status_code) = server.search_post(query.clone()).await;
+    let colors = response["facetsDistribution"]["color"].clone();
+    assert_eq!(colors, json!({ "blue": 28, "brown": 24, "green": 25 }));
+
+    let (response, _status_code) = server.rebuild_facets().await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["name"], "FacetsRebuild");
+
+    let (response, _status_code) = server.search_post(query).await;
+    assert_eq!(response["facetsDistribution"]["color"], colors);
+}
+
+#[actix_rt::test]
+async fn search_with_default_attributes_to_highlight() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "defaultAttributesToHighlight": ["name"] })).await;
+
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_eq!(response["defaultAttributesToHighlight"], json!(["name"]));
+
+    let query = json!({
+        "q": "cherry",
+        "limit": 1,
+        "attributesToHighlight": ["*"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let formatted = &response["hits"][0]["_formatted"];
+        assert_eq!(formatted["name"], "<em>Cherry</em> Orr");
+        assert_eq!(formatted["email"], "cherryorr@chorizon.com");
+    });
+
+    // the attributes explicitly asked for are still highlighted
+    let query = json!({
+        "q": "cherry",
+        "limit": 1,
+        "attributesToHighlight": ["email"],
+    });
+    test_post_get_search!(server, query, |response, _status_code| {
+        let formatted = &response["hits"][0]["_formatted"];
+        assert_eq!(formatted["email"], "<em>cherry</em>orr@chorizon.com");
+    });
+}
+
+#[actix_rt::test]
+async fn search_with_filterable_attributes() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "filterableAttributes": ["color", "age"] })).await;
+
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_json_eq!(response["filterableAttributes"].clone(), json!(["color", "age"]), ordered: false);
+
+    let query = json!({
+        "q": "a",
+        "filters": "color = green AND age > 30",
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(!response["hits"].as_array().unwrap().is_empty());
+    });
+
+    let query = json!({
+        "q": "a",
+        "filters": "color = green AND gender = male",
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 400);
+        assert!(response["message"].as_str().unwrap().contains("attribute `gender` is not filterable"));
+    });
+
+    // every attribute can be used once the setting is cleared
+    server.update_all_settings(json!({ "filterableAttributes": null })).await;
+    let query = json!({
+        "q": "a",
+        "filters": "gender = male",
+    });
+    test_post_get_search!(server, query, |_response, status_code| {
+        assert_eq!(status_code, 200);
+    });
+}
+
+#[actix_rt::test]
+async fn facets_distribution_without_hits() {
+    let mut server = common::Server::test_server().await;
+    server.update_all_settings(json!({ "attributesForFaceting": ["color"] })).await;
+
+    let query = json!({
+        "limit": 0,
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        assert_eq!(response["nbHits"], 77);
+        assert_eq!(response["facetsDistribution"], json!({ "color": { "blue": 28, "brown": 24, "green": 25 } }));
+    });
+
+    let query = json!({
+        "q": "a",
+        "limit": 0,
+        "facetsDistribution": ["color"],
+    });
+    test_post_get_search!(server, query, |response, status_code| {
+        assert_eq!(status_code, 200);
+        assert!(response["hits"].as_array().unwrap().is_empty());
+        assert!(response["nbHits"].as_u64().unwrap() > 0);
+        assert_eq!(response["facetsDistribution"]["color"].as_object().unwrap().len(), 3);
+    });
+}
@@ -48,6 +48,14 @@ async fn write_all_and_delete() {
             "street": ["avenue"],
         },
         "attributesForFaceting": ["name"],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     server.update_all_settings(body.clone()).await;
@@ -81,6 +89,14 @@ async fn write_all_and_delete() {
         "stopWords": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     assert_json_eq!(expect, response, ordered: false);
@@ -132,6 +148,14 @@ async fn write_all_and_update() {
             "street": ["avenue"],
         },
         "attributesForFaceting": ["name"],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     server.update_all_settings(body.clone()).await;
@@ -173,6 +197,14 @@ async fn write_all_and_update() {
             "street": ["avenue"],
         },
         "attributesForFaceting": ["title"],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     server.update_all_settings(body).await;
@@ -210,6 +242,14 @@ async fn write_all_and_update() {
             "street": ["avenue"],
         },
         "attributesForFaceting": ["title"],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     assert_json_eq!(expected, response, ordered: false);
@@ -240,6 +280,14 @@ async fn test_default_settings() {
         "stopWords": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -273,6 +321,14 @@ async fn test_default_settings_2() {
         "stopWords": [],
         "synonyms": {},
         "attributesForFaceting": [],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -381,6 +437,14 @@ async fn write_setting_and_update_partial() {
             "street": ["avenue"],
         },
         "attributesForFaceting": [],
+        "locale": null,
+        "filterableAttributes": null,
+        "defaultAttributesToHighlight": null,
+        "facetNormalization": null,
+        "payloadSizeLimit": null,
+        "defaultHitsPerPage": null,
+        "minQueryLength": null,
+        "maxFacetValueLength": null,
     });
 
     let (response, _status_code) = server.get_all_settings().await;
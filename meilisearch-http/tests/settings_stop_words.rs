@@ -59,3 +59,19 @@ async fn add_documents_and_stop_words() {
 
     // assert!(!response["hits"].as_array().unwrap().is_empty());
 }
+
+#[actix_rt::test]
+async fn search_without_removing_stop_words() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!(["ad", "in"]);
+    server.update_stop_words(body).await;
+
+    let (response, _status_code) = server.search_post(json!({ "q": "in" })).await;
+    assert!(response["hits"].as_array().unwrap().is_empty());
+
+    // the stop words of the query are searched, here as the prefix of the other words
+    let (response, _status_code) = server.search_post(json!({ "q": "in", "removeStopWords": false })).await;
+    assert!(!response["hits"].as_array().unwrap().is_empty());
+    assert!(response.get("_warnings").is_none());
+}
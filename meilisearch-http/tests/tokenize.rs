@@ -0,0 +1,36 @@
+use assert_json_diff::assert_json_eq;
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn tokenize_cjk_and_latin_text() {
+    let mut server = common::Server::with_uid("test");
+    server.create_index(json!({ "uid": "test" })).await;
+    server.update_stop_words(json!(["foo"])).await;
+
+    let (response, status_code) = server.tokenize(json!({ "text": "this isのス Foo myタイリ test" })).await;
+    assert_eq!(status_code, 200);
+
+    let expected = json!([
+        { "word": "this", "index": 0, "wordIndex": 0, "charIndex": 0, "stopWord": false },
+        { "word": "is", "index": 1, "wordIndex": 1, "charIndex": 5, "stopWord": false },
+        { "word": "の", "index": 2, "wordIndex": 2, "charIndex": 7, "stopWord": false },
+        { "word": "ス", "index": 3, "wordIndex": 3, "charIndex": 8, "stopWord": false },
+        { "word": "foo", "index": 4, "wordIndex": 4, "charIndex": 10, "stopWord": true },
+        { "word": "my", "index": 5, "wordIndex": 5, "charIndex": 14, "stopWord": false },
+        { "word": "タ", "index": 6, "wordIndex": 6, "charIndex": 16, "stopWord": false },
+        { "word": "イ", "index": 7, "wordIndex": 7, "charIndex": 17, "stopWord": false },
+        { "word": "リ", "index": 8, "wordIndex": 8, "charIndex": 18, "stopWord": false },
+        { "word": "test", "index": 9, "wordIndex": 9, "charIndex": 20, "stopWord": false }
+    ]);
+    assert_json_eq!(expected, response, ordered: true);
+}
+
+#[actix_rt::test]
+async fn tokenize_on_unknown_index() {
+    let mut server = common::Server::with_uid("test");
+
+    let (_response, status_code) = server.tokenize(json!({ "text": "hello" })).await;
+    assert_eq!(status_code, 404);
+}